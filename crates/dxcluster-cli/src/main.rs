@@ -2,7 +2,9 @@ use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
 use dxcluster_client::{ClientEvent, TelnetClient, TelnetOptions};
 use dxcluster_types::{Callsign, FrequencyHz};
-use dxcluster_wire::{ServerLine, UserCommand, user::ShowCommand};
+use dxcluster_wire::format::{announce_user_line, spot_user_line};
+use dxcluster_wire::user::{ShowCommand, parse_dx_query};
+use dxcluster_wire::{ServerLine, UserCommand};
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about = "Interact with dxcluster nodes over telnet", long_about = None)]
@@ -25,12 +27,15 @@ struct Cli {
 
 #[derive(Subcommand, Debug, Clone)]
 enum Commands {
-    /// Connect to a cluster node and print the first response
+    /// Connect to a cluster node and print its greeting
     Login,
     /// Send a DX spot to the cluster
     Spot(SpotArgs),
     /// Request a list of recent spots
-    List,
+    List {
+        /// `sh/dx` arguments, e.g. `25 on 20m` or `by G4ABC`
+        query: Vec<String>,
+    },
     /// Stream cluster output until disconnected
     Watch,
 }
@@ -51,7 +56,7 @@ async fn main() -> Result<()> {
     match &cli.command {
         Commands::Login => login(&cli).await?,
         Commands::Spot(args) => spot(&cli, args.clone()).await?,
-        Commands::List => list(&cli).await?,
+        Commands::List { query } => list(&cli, &query.join(" ")).await?,
         Commands::Watch => watch(&cli).await?,
     }
 
@@ -61,45 +66,60 @@ async fn main() -> Result<()> {
 async fn login(cli: &Cli) -> Result<()> {
     let mut client = connect(cli).await?;
     println!("connected to {}", cli.addr);
-    if let Some(event) = client.next_event().await.transpose()? {
-        print_event(&event);
-    }
-    Ok(())
+    print_until_prompt(&mut client).await
 }
 
 async fn spot(cli: &Cli, args: SpotArgs) -> Result<()> {
-    let client = connect(cli).await?;
+    let mut client = connect(cli).await?;
     let command = args.into_command()?;
+    wait_for_prompt(&mut client).await?;
     client
         .send_command(command)
         .await
         .context("failed to submit spot")?;
+    print_until_prompt(&mut client).await?;
     println!("spot submitted to {}", cli.addr);
     Ok(())
 }
 
-async fn list(cli: &Cli) -> Result<()> {
+async fn list(cli: &Cli, args: &str) -> Result<()> {
+    let query = parse_dx_query(args).context("invalid query")?;
     let mut client = connect(cli).await?;
+    wait_for_prompt(&mut client).await?;
     client
-        .send_command(UserCommand::Show(ShowCommand::Dx))
+        .send_command(UserCommand::Show(ShowCommand::Dx(query)))
         .await
         .context("failed to request spots")?;
+    print_until_prompt(&mut client).await
+}
 
+async fn watch(cli: &Cli) -> Result<()> {
+    let mut client = connect(cli).await?;
+    println!("listening for cluster output from {}", cli.addr);
     while let Some(event) = client.next_event().await.transpose()? {
         print_event(&event);
+    }
+    Ok(())
+}
+
+/// Skip the login exchange and banner until the node shows its first
+/// command prompt.
+async fn wait_for_prompt(client: &mut TelnetClient) -> Result<()> {
+    while let Some(event) = client.next_event().await.transpose()? {
         if matches!(event, ClientEvent::UserLine(ServerLine::Prompt)) {
-            break;
+            return Ok(());
         }
     }
-
-    Ok(())
+    anyhow::bail!("connection closed before the node was ready")
 }
 
-async fn watch(cli: &Cli) -> Result<()> {
-    let mut client = connect(cli).await?;
-    println!("listening for cluster output from {}", cli.addr);
+/// Print responses up to and including the next command prompt.
+async fn print_until_prompt(client: &mut TelnetClient) -> Result<()> {
     while let Some(event) = client.next_event().await.transpose()? {
         print_event(&event);
+        if matches!(event, ClientEvent::UserLine(ServerLine::Prompt)) {
+            break;
+        }
     }
     Ok(())
 }
@@ -143,7 +163,10 @@ fn print_event(event: &ClientEvent) {
     match event {
         ClientEvent::UserLine(ServerLine::Banner(msg)) => println!("banner: {msg}"),
         ClientEvent::UserLine(ServerLine::Prompt) => println!(">"),
-        ClientEvent::UserLine(ServerLine::Spot(spot)) => println!("spot: {spot:?}"),
+        ClientEvent::UserLine(ServerLine::Spot(spot)) => println!("{}", spot_user_line(spot)),
+        ClientEvent::UserLine(ServerLine::Announce(announcement)) => {
+            println!("{}", announce_user_line(announcement))
+        }
         ClientEvent::UserLine(ServerLine::Message(msg)) => println!("message: {msg}"),
         ClientEvent::PeerFrame(frame) => println!("peer: {frame:?}"),
     }
@@ -196,8 +219,13 @@ mod tests {
     fn parses_list_command() {
         let cli = Cli::parse_from(["dxcluster-cli", "list"]);
 
-        assert!(matches!(cli.command, Commands::List));
+        assert!(matches!(cli.command, Commands::List { ref query } if query.is_empty()));
         assert_eq!(cli.addr, "127.0.0.1:7300");
+
+        let cli = Cli::parse_from(["dxcluster-cli", "list", "25", "on", "20m"]);
+        assert!(
+            matches!(cli.command, Commands::List { ref query } if query == &["25", "on", "20m"])
+        );
     }
 
     #[test]
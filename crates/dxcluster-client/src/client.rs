@@ -1,4 +1,9 @@
+use dxcluster_wire::user::format_command;
 use dxcluster_wire::{PeerFrame, ServerLine, UserCommand};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::error::ClientError;
 
 #[derive(Debug)]
 pub enum ClientEvent {
@@ -6,15 +11,46 @@ pub enum ClientEvent {
     PeerFrame(PeerFrame),
 }
 
+/// Cloneable sender for outgoing lines on an established connection.
+///
+/// Lines are queued to a writer task so handles can be shared between tasks
+/// without locking the socket.
 #[derive(Debug, Clone)]
-pub struct ClientHandle;
+pub struct ClientHandle {
+    tx: mpsc::UnboundedSender<String>,
+}
 
 impl ClientHandle {
-    pub async fn send_command(&self, _cmd: UserCommand) -> Result<(), crate::error::ClientError> {
-        Ok(())
+    /// Spawn a writer task for `writer` and return a handle feeding it.
+    pub(crate) fn spawn_writer<W>(writer: W) -> Self
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            let mut writer = writer;
+            while let Some(line) = rx.recv().await {
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+                if writer.write_all(b"\n").await.is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+        ClientHandle { tx }
+    }
+
+    pub async fn send_command(&self, cmd: UserCommand) -> Result<(), ClientError> {
+        self.send_line(format_command(&cmd))
+    }
+
+    pub async fn send_frame(&self, frame: PeerFrame) -> Result<(), ClientError> {
+        self.send_line(frame.to_line())
     }
 
-    pub async fn send_frame(&self, _frame: PeerFrame) -> Result<(), crate::error::ClientError> {
-        Ok(())
+    pub(crate) fn send_line(&self, line: String) -> Result<(), ClientError> {
+        self.tx.send(line).map_err(|_| ClientError::Closed)
     }
 }
@@ -2,6 +2,8 @@
 pub enum ClientError {
     #[error("connection failed")]
     Connection,
+    #[error("connection closed")]
+    Closed,
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -8,5 +8,5 @@ pub mod reconnect;
 pub mod telnet;
 
 pub use client::{ClientEvent, ClientHandle};
-pub use peer::PeerClient;
+pub use peer::{PeerClient, PeerOptions};
 pub use telnet::{TelnetClient, TelnetOptions};
@@ -1,44 +1,204 @@
+//! Node-to-node peer link client.
+//!
+//! [`PeerClient`] connects to a node's peer port, introduces itself with
+//! `HELLO`, `CAPS` and (optionally) `AUTH` frames built from [`PeerOptions`],
+//! answers `PING` with `PONG`, and sends `HEARTBEAT` frames at the configured
+//! interval. Every parsed frame received from the remote node is yielded as
+//! [`ClientEvent::PeerFrame`]; lines that do not parse, or that run past
+//! [`MAX_LINE_LEN`] bytes, are skipped.
+
+use std::io;
+use std::time::Duration;
+
 use dxcluster_types::NodeId;
 use dxcluster_wire::PeerFrame;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{MissedTickBehavior, interval_at};
 
 use crate::client::{ClientEvent, ClientHandle};
 use crate::error::ClientError;
+use crate::telnet::MAX_LINE_LEN;
 
 #[derive(Debug, Clone)]
 pub struct PeerOptions {
     pub node_id: NodeId,
     pub version: String,
+    pub capabilities: Vec<String>,
+    pub auth_token: Option<String>,
+    pub heartbeat_interval: Duration,
 }
 
-#[derive(Debug, Clone)]
+impl PeerOptions {
+    /// Options with the same defaults the node uses for its own peer links.
+    pub fn new(node_id: NodeId) -> Self {
+        PeerOptions {
+            node_id,
+            version: "1".to_string(),
+            capabilities: vec!["spots-v1".to_string(), "heartbeat".to_string()],
+            auth_token: None,
+            heartbeat_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct PeerClient {
     handle: ClientHandle,
     options: PeerOptions,
+    events: mpsc::UnboundedReceiver<Result<ClientEvent, ClientError>>,
 }
 
 impl PeerClient {
-    pub async fn connect(
-        _addr: impl AsRef<str>,
-        options: PeerOptions,
-    ) -> Result<Self, ClientError> {
+    /// Connect to `addr` and send the handshake frames.
+    pub async fn connect(addr: impl AsRef<str>, options: PeerOptions) -> Result<Self, ClientError> {
+        let stream = TcpStream::connect(addr.as_ref()).await?;
+        let (reader, writer) = stream.into_split();
+        let handle = ClientHandle::spawn_writer(writer);
+
+        handle
+            .send_frame(PeerFrame::Hello {
+                node_id: options.node_id.clone(),
+                version: options.version.clone(),
+            })
+            .await?;
+        handle
+            .send_frame(PeerFrame::Capabilities {
+                values: options.capabilities.clone(),
+            })
+            .await?;
+        if let Some(token) = options.auth_token.clone() {
+            handle.send_frame(PeerFrame::Auth { token }).await?;
+        }
+
+        let (events_tx, events) = mpsc::unbounded_channel();
+        tokio::spawn(read_loop(
+            reader,
+            handle.clone(),
+            options.heartbeat_interval,
+            events_tx,
+        ));
+
         Ok(PeerClient {
-            handle: ClientHandle,
+            handle,
             options,
+            events,
         })
     }
 
+    /// Wait for the next frame from the remote node, or `None` once the
+    /// link has closed.
     pub async fn next_event(&mut self) -> Option<Result<ClientEvent, ClientError>> {
-        let frame = PeerFrame::Pong {
-            nonce: String::from("0"),
-        };
-        Some(Ok(ClientEvent::PeerFrame(frame)))
+        self.events.recv().await
     }
 
     pub async fn send_frame(&self, frame: PeerFrame) -> Result<(), ClientError> {
         self.handle.send_frame(frame).await
     }
 
+    /// Cloneable handle for sending frames from other tasks.
+    pub fn handle(&self) -> ClientHandle {
+        self.handle.clone()
+    }
+
     pub fn options(&self) -> &PeerOptions {
         &self.options
     }
 }
+
+async fn read_loop<R>(
+    reader: R,
+    handle: ClientHandle,
+    heartbeat_interval: Duration,
+    events: mpsc::UnboundedSender<Result<ClientEvent, ClientError>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let heartbeat_interval = heartbeat_interval.max(Duration::from_millis(10));
+    let mut ticker = interval_at(
+        tokio::time::Instant::now() + heartbeat_interval,
+        heartbeat_interval,
+    );
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut counter = 0u64;
+
+    loop {
+        tokio::select! {
+            _ = events.closed() => break,
+            _ = ticker.tick() => {
+                counter += 1;
+                let nonce = counter.to_string();
+                if handle.send_frame(PeerFrame::Heartbeat { nonce }).await.is_err() {
+                    break;
+                }
+            }
+            read = read_capped_line(&mut reader, &mut line) => {
+                match read {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(err) => {
+                        let _ = events.send(Err(ClientError::Io(err)));
+                        break;
+                    }
+                }
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\r', '\n']);
+                let parsed = PeerFrame::parse(text);
+                if let Err(err) = &parsed
+                    && !text.trim().is_empty()
+                {
+                    tracing::debug!(%err, line = text, "skipping unparseable peer line");
+                }
+                line.clear();
+                let Ok(frame) = parsed else {
+                    continue;
+                };
+                if let PeerFrame::Ping { nonce } = &frame {
+                    let _ = handle
+                        .send_frame(PeerFrame::Pong {
+                            nonce: nonce.clone(),
+                        })
+                        .await;
+                }
+                if events.send(Ok(ClientEvent::PeerFrame(frame))).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Read one line into `line`, returning the number of bytes consumed (0 at
+/// end of stream). A line longer than [`MAX_LINE_LEN`] is skipped up to its
+/// newline and `line` left empty.
+async fn read_capped_line<R>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut read = (&mut *reader)
+        .take(MAX_LINE_LEN as u64)
+        .read_until(b'\n', line)
+        .await?;
+    if read < MAX_LINE_LEN || line.last() == Some(&b'\n') {
+        return Ok(read);
+    }
+    line.clear();
+    loop {
+        let buf = reader.fill_buf().await?;
+        if buf.is_empty() {
+            return Ok(read);
+        }
+        let (len, done) = match buf.iter().position(|byte| *byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (buf.len(), false),
+        };
+        reader.consume(len);
+        read += len;
+        if done {
+            return Ok(read);
+        }
+    }
+}
@@ -1,36 +1,305 @@
+//! Telnet-style user client.
+//!
+//! [`TelnetClient`] connects to a node's user port, answers the login and
+//! password prompts from [`TelnetOptions`], and turns incoming text into
+//! [`ServerLine`] values. Lines before the first command prompt are reported
+//! as [`ServerLine::Banner`]; `DX de` lines become [`ServerLine::Spot`],
+//! `To ALL de` lines [`ServerLine::Announce`] and everything else
+//! [`ServerLine::Message`]. Lines longer than [`MAX_LINE_LEN`] bytes are
+//! dropped rather than buffered. Prompts that arrive without a
+//! trailing newline are recognized as well: the login and password prompts,
+//! which are answered once before the first command prompt, and command
+//! prompts in the node's own `>` form or DXSpider's `N0CALL de NODE >`
+//! addressed to our callsign.
+
 use dxcluster_types::Callsign;
+use dxcluster_wire::user::{format_prompt, parse_announce_line, parse_spot_line};
 use dxcluster_wire::{ServerLine, UserCommand};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
 
 use crate::client::{ClientEvent, ClientHandle};
 use crate::error::ClientError;
 
+/// Longest line accepted from the node; anything longer is discarded up to
+/// the next newline.
+pub const MAX_LINE_LEN: usize = 1024;
+
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+
 #[derive(Debug, Default, Clone)]
 pub struct TelnetOptions {
     pub callsign: Option<Callsign>,
     pub password: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct TelnetClient {
     handle: ClientHandle,
+    events: mpsc::UnboundedReceiver<Result<ClientEvent, ClientError>>,
 }
 
 impl TelnetClient {
+    /// Open a TCP connection to `addr` and start logging in.
     pub async fn connect(
-        _addr: impl AsRef<str>,
-        _options: TelnetOptions,
+        addr: impl AsRef<str>,
+        options: TelnetOptions,
     ) -> Result<Self, ClientError> {
-        Ok(TelnetClient {
-            handle: ClientHandle,
-        })
+        let stream = TcpStream::connect(addr.as_ref()).await?;
+        let (reader, writer) = stream.into_split();
+        let handle = ClientHandle::spawn_writer(writer);
+        let (events_tx, events) = mpsc::unbounded_channel();
+        tokio::spawn(read_loop(reader, handle.clone(), options, events_tx));
+        Ok(TelnetClient { handle, events })
     }
 
+    /// Wait for the next line from the node, or `None` once the connection
+    /// has closed.
     pub async fn next_event(&mut self) -> Option<Result<ClientEvent, ClientError>> {
-        let line = ServerLine::Message(String::from("ok"));
-        Some(Ok(ClientEvent::UserLine(line)))
+        self.events.recv().await
     }
 
     pub async fn send_command(&self, cmd: UserCommand) -> Result<(), ClientError> {
         self.handle.send_command(cmd).await
     }
+
+    /// Cloneable handle for sending commands from other tasks.
+    pub fn handle(&self) -> ClientHandle {
+        self.handle.clone()
+    }
+}
+
+async fn read_loop<R>(
+    mut reader: R,
+    handle: ClientHandle,
+    options: TelnetOptions,
+    events: mpsc::UnboundedSender<Result<ClientEvent, ClientError>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let mut splitter = LineSplitter::new(handle, options);
+    let mut chunk = [0u8; 2048];
+    loop {
+        let read = match reader.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) => {
+                let _ = events.send(Err(ClientError::Io(err)));
+                return;
+            }
+        };
+        for line in splitter.feed(&chunk[..read]) {
+            if events.send(Ok(ClientEvent::UserLine(line))).is_err() {
+                return;
+            }
+        }
+    }
+    if let Some(line) = splitter.finish() {
+        let _ = events.send(Ok(ClientEvent::UserLine(line)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TelnetState {
+    Data,
+    Command,
+    Option,
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+/// Splits the byte stream into classified lines and answers login prompts.
+struct LineSplitter {
+    handle: ClientHandle,
+    options: TelnetOptions,
+    buf: Vec<u8>,
+    /// Length of the unterminated line at the end of `buf`.
+    pending: usize,
+    /// Set while skipping the rest of an overlong line.
+    discarding: bool,
+    telnet: TelnetState,
+    seen_prompt: bool,
+    sent_callsign: bool,
+    sent_password: bool,
+}
+
+impl LineSplitter {
+    fn new(handle: ClientHandle, options: TelnetOptions) -> Self {
+        LineSplitter {
+            handle,
+            options,
+            buf: Vec::new(),
+            pending: 0,
+            discarding: false,
+            telnet: TelnetState::Data,
+            seen_prompt: false,
+            sent_callsign: false,
+            sent_password: false,
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) -> Vec<ServerLine> {
+        for &byte in bytes {
+            self.push_byte(byte);
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.buf.iter().position(|byte| *byte == b'\n') {
+            let raw: Vec<u8> = self.buf.drain(..=end).collect();
+            let text = String::from_utf8_lossy(&raw);
+            if let Some(line) = self.classify(text.trim_end_matches(['\r', '\n'])) {
+                lines.push(line);
+            }
+        }
+
+        let pending = String::from_utf8_lossy(&self.buf).into_owned();
+        if self.is_partial_prompt(&pending) {
+            self.buf.clear();
+            self.pending = 0;
+            if let Some(line) = self.classify(&pending) {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    fn finish(&mut self) -> Option<ServerLine> {
+        self.pending = 0;
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut self.buf)).into_owned();
+        self.classify(&rest)
+    }
+
+    /// Strip telnet negotiation sequences, which the node never needs us to
+    /// act on.
+    fn push_byte(&mut self, byte: u8) {
+        self.telnet = match (self.telnet, byte) {
+            (TelnetState::Data, IAC) => TelnetState::Command,
+            (TelnetState::Data, byte) => {
+                self.push_data(byte);
+                TelnetState::Data
+            }
+            (TelnetState::Command, IAC) => {
+                self.push_data(IAC);
+                TelnetState::Data
+            }
+            (TelnetState::Command, SB) => TelnetState::Subnegotiation,
+            (TelnetState::Command, WILL..=DONT) => TelnetState::Option,
+            (TelnetState::Command, _) | (TelnetState::Option, _) => TelnetState::Data,
+            (TelnetState::Subnegotiation, IAC) => TelnetState::SubnegotiationIac,
+            (TelnetState::Subnegotiation, _) => TelnetState::Subnegotiation,
+            (TelnetState::SubnegotiationIac, SE) => TelnetState::Data,
+            (TelnetState::SubnegotiationIac, _) => TelnetState::Subnegotiation,
+        };
+    }
+
+    /// Append a data byte, dropping the current line once it grows past
+    /// [`MAX_LINE_LEN`] and skipping input until its newline.
+    fn push_data(&mut self, byte: u8) {
+        if self.discarding {
+            self.discarding = byte != b'\n';
+            return;
+        }
+        self.buf.push(byte);
+        if byte == b'\n' {
+            self.pending = 0;
+        } else if self.pending == MAX_LINE_LEN {
+            self.buf.truncate(self.buf.len() - self.pending - 1);
+            self.pending = 0;
+            self.discarding = true;
+        } else {
+            self.pending += 1;
+        }
+    }
+
+    fn classify(&mut self, text: &str) -> Option<ServerLine> {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        let lower = trimmed.to_ascii_lowercase();
+        if !self.seen_prompt && !self.sent_callsign && is_login_prompt(&lower) {
+            if let Some(callsign) = &self.options.callsign {
+                let _ = self.handle.send_line(callsign.to_string());
+                self.sent_callsign = true;
+            }
+            return Some(ServerLine::Message(trimmed.to_string()));
+        }
+        if !self.seen_prompt && !self.sent_password && is_password_prompt(&lower) {
+            if let Some(password) = &self.options.password {
+                let _ = self.handle.send_line(password.clone());
+                self.sent_password = true;
+            }
+            return Some(ServerLine::Message(trimmed.to_string()));
+        }
+
+        if lower.starts_with("dx de ")
+            && let Ok(spot) = parse_spot_line(trimmed, time::OffsetDateTime::now_utc())
+        {
+            return Some(ServerLine::Spot(Box::new(spot)));
+        }
+
+        if lower.starts_with("to ")
+            && let Ok(announcement) = parse_announce_line(trimmed, time::OffsetDateTime::now_utc())
+        {
+            return Some(ServerLine::Announce(Box::new(announcement)));
+        }
+
+        if self.is_command_prompt(trimmed) {
+            self.seen_prompt = true;
+            return Some(ServerLine::Prompt);
+        }
+
+        if self.seen_prompt {
+            Some(ServerLine::Message(trimmed.to_string()))
+        } else {
+            Some(ServerLine::Banner(trimmed.to_string()))
+        }
+    }
+
+    /// The node's own `>` prompt, or a DXSpider `<call> de <node> ... >`
+    /// prompt addressed to our callsign. Other lines ending in `>`, such as
+    /// spot comments, are not prompts.
+    fn is_command_prompt(&self, trimmed: &str) -> bool {
+        if trimmed == format_prompt() {
+            return true;
+        }
+        let Some(head) = trimmed.strip_suffix('>') else {
+            return false;
+        };
+        let mut words = head.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(call), Some(de), Some(_node)) if de.eq_ignore_ascii_case("de") => self
+                .options
+                .callsign
+                .as_ref()
+                .is_some_and(|callsign| callsign.as_str().eq_ignore_ascii_case(call)),
+            _ => false,
+        }
+    }
+
+    fn is_partial_prompt(&self, pending: &str) -> bool {
+        let trimmed = pending.trim_end();
+        if trimmed.is_empty() {
+            return false;
+        }
+        let lower = trimmed.to_ascii_lowercase();
+        let login = !self.seen_prompt && (is_login_prompt(&lower) || is_password_prompt(&lower));
+        login || self.is_command_prompt(trimmed)
+    }
+}
+
+fn is_login_prompt(lower: &str) -> bool {
+    ["login:", "call:", "callsign:"]
+        .iter()
+        .any(|prompt| lower.ends_with(prompt))
+}
+
+fn is_password_prompt(lower: &str) -> bool {
+    lower.ends_with("password:")
 }
@@ -0,0 +1,183 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use dxcluster_client::telnet::MAX_LINE_LEN;
+use dxcluster_client::{ClientEvent, PeerClient, PeerOptions};
+use dxcluster_model::Spot;
+use dxcluster_node::{Node, NodeConfig};
+use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use dxcluster_wire::PeerFrame;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::time::{sleep, timeout};
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind temp port");
+    let addr = listener.local_addr().expect("addr");
+    drop(listener);
+    addr
+}
+
+fn make_spot(dx: &str) -> Spot {
+    let ts = time::OffsetDateTime::now_utc();
+    Spot::new_local(
+        SpotId::hash_components(&[dx.as_bytes(), &ts.unix_timestamp().to_be_bytes()]),
+        ts,
+        FrequencyHz(14_074_000),
+        Callsign::parse_loose(dx).expect("dx callsign"),
+        Callsign::parse_loose("N0CALL").expect("spotter callsign"),
+        "via peer client",
+        None,
+    )
+}
+
+async fn next_frame(client: &mut PeerClient) -> PeerFrame {
+    let event = timeout(Duration::from_secs(3), client.next_event())
+        .await
+        .expect("frame should arrive")
+        .expect("link open")
+        .expect("no io error");
+    match event {
+        ClientEvent::PeerFrame(frame) => frame,
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn handshakes_answers_ping_and_sends_heartbeats() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(lines.next_line().await.unwrap().unwrap());
+        }
+
+        writer
+            .write_all(b"HELLO|remote|1\nPING|42\n")
+            .await
+            .unwrap();
+        let mut saw_pong = false;
+        let mut saw_heartbeat = false;
+        while !(saw_pong && saw_heartbeat) {
+            let line = lines.next_line().await.unwrap().unwrap();
+            saw_pong |= line == "PONG|42";
+            saw_heartbeat |= line.starts_with("HEARTBEAT|");
+        }
+        received
+    });
+
+    let options = PeerOptions {
+        auth_token: Some("s3cret".to_string()),
+        heartbeat_interval: Duration::from_millis(50),
+        ..PeerOptions::new(NodeId("bridge".to_string()))
+    };
+    let mut client = PeerClient::connect(addr.to_string(), options)
+        .await
+        .expect("connect");
+
+    assert_eq!(
+        next_frame(&mut client).await,
+        PeerFrame::Hello {
+            node_id: NodeId("remote".to_string()),
+            version: "1".to_string(),
+        }
+    );
+    assert_eq!(
+        next_frame(&mut client).await,
+        PeerFrame::Ping {
+            nonce: "42".to_string()
+        }
+    );
+
+    let handshake = timeout(Duration::from_secs(3), server)
+        .await
+        .expect("server finished")
+        .expect("server task");
+    assert_eq!(
+        handshake,
+        vec!["HELLO|bridge|1", "CAPS|spots-v1,heartbeat", "AUTH|s3cret"]
+    );
+}
+
+#[tokio::test]
+async fn skips_overlong_and_unparseable_lines() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept");
+        let mut overlong = b"HELLO|".to_vec();
+        overlong.extend(vec![b'x'; MAX_LINE_LEN * 4]);
+        overlong.extend(b"|1\n");
+        stream.write_all(&overlong).await.unwrap();
+        stream.write_all(b"NOT A FRAME\nPING|7\n").await.unwrap();
+        // Hold the link open until the client goes away.
+        let mut rest = Vec::new();
+        let _ = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut rest).await;
+    });
+
+    let mut client = PeerClient::connect(
+        addr.to_string(),
+        PeerOptions::new(NodeId("bridge".to_string())),
+    )
+    .await
+    .expect("connect");
+    assert_eq!(
+        next_frame(&mut client).await,
+        PeerFrame::Ping {
+            nonce: "7".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn exchanges_spots_with_a_node() {
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let mut client = PeerClient::connect(
+        peer_listen.to_string(),
+        PeerOptions::new(NodeId("bridge".to_string())),
+    )
+    .await
+    .expect("connect");
+
+    client
+        .send_frame(PeerFrame::Spot {
+            spot: Box::new(make_spot("K1ABC")),
+        })
+        .await
+        .expect("send spot");
+    timeout(Duration::from_secs(3), async {
+        while !handle
+            .recent_spots(10)
+            .await
+            .iter()
+            .any(|spot| spot.dx.as_str() == "K1ABC")
+        {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("node should store the spot");
+
+    handle.inject_spot(make_spot("W1AW")).await;
+    loop {
+        if let PeerFrame::Spot { spot } = next_frame(&mut client).await
+            && spot.dx.as_str() == "W1AW"
+        {
+            break;
+        }
+    }
+
+    handle.shutdown().await;
+}
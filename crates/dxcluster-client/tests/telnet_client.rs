@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use dxcluster_client::telnet::MAX_LINE_LEN;
+use dxcluster_client::{ClientEvent, TelnetClient, TelnetOptions};
+use dxcluster_model::AnnounceScope;
+use dxcluster_types::{Callsign, FrequencyHz};
+use dxcluster_wire::{ServerLine, UserCommand};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+
+async fn next_line(client: &mut TelnetClient) -> ServerLine {
+    let event = timeout(Duration::from_secs(3), client.next_event())
+        .await
+        .expect("event should arrive")
+        .expect("connection open")
+        .expect("no io error");
+    match event {
+        ClientEvent::UserLine(line) => line,
+        other => panic!("unexpected event {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn logs_in_and_classifies_lines() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+
+        // IAC WILL ECHO, then a banner and an unterminated login prompt.
+        writer.write_all(&[255, 251, 1]).await.unwrap();
+        writer
+            .write_all(b"Welcome to TEST DX cluster\r\nlogin: ")
+            .await
+            .unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line.trim(), "N0CALL");
+
+        line.clear();
+        writer.write_all(b"password: ").await.unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line.trim(), "secret");
+
+        writer.write_all(b"N0CALL de TEST >").await.unwrap();
+        writer
+            .write_all(b"\r\nDX de W1AW:     14025.0  K1ABC        cq dx     1200Z\r\n")
+            .await
+            .unwrap();
+        writer.write_all(b"PONG\r\n").await.unwrap();
+
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        line
+    });
+
+    let options = TelnetOptions {
+        callsign: Some(Callsign::parse_loose("n0call").unwrap()),
+        password: Some("secret".to_string()),
+    };
+    let mut client = TelnetClient::connect(addr.to_string(), options)
+        .await
+        .expect("connect");
+
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Banner("Welcome to TEST DX cluster".to_string())
+    );
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Message("login:".to_string())
+    );
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Message("password:".to_string())
+    );
+    assert_eq!(next_line(&mut client).await, ServerLine::Prompt);
+
+    match next_line(&mut client).await {
+        ServerLine::Spot(spot) => {
+            assert_eq!(spot.spotter.as_str(), "W1AW");
+            assert_eq!(spot.dx.as_str(), "K1ABC");
+            assert_eq!(spot.freq, FrequencyHz(14_025_000));
+            assert_eq!(spot.comment, "cq dx");
+        }
+        other => panic!("expected spot, got {other:?}"),
+    }
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Message("PONG".to_string())
+    );
+
+    client
+        .send_command(UserCommand::Heartbeat)
+        .await
+        .expect("send command");
+    let received = server.await.expect("server task");
+    assert_eq!(received.trim(), "PING");
+
+    let closed = timeout(Duration::from_secs(3), client.next_event())
+        .await
+        .expect("close observed");
+    assert!(closed.is_none());
+}
+
+#[tokio::test]
+async fn only_node_prompts_are_prompts() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept");
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+
+        writer.write_all(b"login: ").await.unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        writer.write_all(b">\n").await.unwrap();
+        writer
+            .write_all(b"DX de W1AW:     14025.0  K1ABC        qsy -> 14030 >\r\n")
+            .await
+            .unwrap();
+        writer.write_all(b"G4XYZ de TEST >\r\n").await.unwrap();
+        writer.write_all(b"new user login:\r\n").await.unwrap();
+        writer.write_all(b"PONG\r\n").await.unwrap();
+
+        // Only the first login prompt is answered.
+        let mut rest = String::new();
+        reader.read_line(&mut rest).await.unwrap();
+        (line, rest)
+    });
+
+    let options = TelnetOptions {
+        callsign: Some(Callsign::parse_loose("N0CALL").unwrap()),
+        password: None,
+    };
+    let mut client = TelnetClient::connect(addr.to_string(), options)
+        .await
+        .expect("connect");
+
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Message("login:".to_string())
+    );
+    assert_eq!(next_line(&mut client).await, ServerLine::Prompt);
+    match next_line(&mut client).await {
+        ServerLine::Spot(spot) => assert_eq!(spot.comment, "qsy -> 14030 >"),
+        other => panic!("expected spot, got {other:?}"),
+    }
+    for text in ["G4XYZ de TEST >", "new user login:", "PONG"] {
+        assert_eq!(
+            next_line(&mut client).await,
+            ServerLine::Message(text.to_string())
+        );
+    }
+
+    client
+        .send_command(UserCommand::Heartbeat)
+        .await
+        .expect("send command");
+    let (login, rest) = server.await.expect("server task");
+    assert_eq!(login.trim(), "N0CALL");
+    assert_eq!(rest.trim(), "PING");
+}
+
+#[tokio::test]
+async fn classifies_announcements_and_drops_overlong_lines() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("accept");
+        stream.write_all(b">\r\n").await.unwrap();
+        stream
+            .write_all(b"To ALL de G4ABC: qsl via bureau\r\n")
+            .await
+            .unwrap();
+        stream
+            .write_all(b"To SYSOP de G4ABC: reboot at 2200z\r\n")
+            .await
+            .unwrap();
+        // An overlong line split across writes is dropped up to its newline.
+        let flood = vec![b'x'; MAX_LINE_LEN * 4];
+        stream.write_all(&flood).await.unwrap();
+        stream.write_all(&flood).await.unwrap();
+        stream.write_all(b"\r\nPONG\r\n").await.unwrap();
+        // Keep the connection open until the client has read everything.
+        let mut rest = Vec::new();
+        let _ = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut rest).await;
+    });
+
+    let mut client = TelnetClient::connect(addr.to_string(), TelnetOptions::default())
+        .await
+        .expect("connect");
+
+    assert_eq!(next_line(&mut client).await, ServerLine::Prompt);
+    for (scope, text) in [
+        (AnnounceScope::Full, "qsl via bureau"),
+        (AnnounceScope::Sysop, "reboot at 2200z"),
+    ] {
+        match next_line(&mut client).await {
+            ServerLine::Announce(announcement) => {
+                assert_eq!(announcement.scope, scope);
+                assert_eq!(announcement.from.as_str(), "G4ABC");
+                assert_eq!(announcement.text, text);
+            }
+            other => panic!("expected announcement, got {other:?}"),
+        }
+    }
+    assert_eq!(
+        next_line(&mut client).await,
+        ServerLine::Message("PONG".to_string())
+    );
+}
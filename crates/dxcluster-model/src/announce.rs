@@ -0,0 +1,221 @@
+//! Announcements and the history kept for `sh/ann`.
+//!
+//! An announcement is a line of free text from a user. Local announcements
+//! stay on the node they were made on; full and sysop announcements travel
+//! the whole network. DXSpider frames carry no identifier or timestamp, so
+//! an announcement's id is derived from its sender and text, and a copy that
+//! arrives again over another link within the dedupe window is dropped.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use dxcluster_types::{Callsign, NodeId, SpotId};
+
+use crate::dedupe::{DedupeResult, DedupeTable};
+use crate::spot::Timestamp;
+
+/// Who an announcement is meant for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AnnounceScope {
+    /// Users of the node it was made on (`ann <text>`).
+    #[default]
+    Local,
+    /// Users of every node in the network (`ann full <text>`).
+    Full,
+    /// Sysops of every node in the network (`ann sysop <text>`).
+    Sysop,
+}
+
+impl AnnounceScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnounceScope::Local => "local",
+            AnnounceScope::Full => "full",
+            AnnounceScope::Sysop => "sysop",
+        }
+    }
+
+    /// The scope named by `word`, ignoring case.
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "local" => Some(AnnounceScope::Local),
+            "full" => Some(AnnounceScope::Full),
+            "sysop" => Some(AnnounceScope::Sysop),
+            _ => None,
+        }
+    }
+
+    /// Whether announcements of this scope are forwarded to peers.
+    pub fn is_networked(&self) -> bool {
+        !matches!(self, AnnounceScope::Local)
+    }
+}
+
+impl fmt::Display for AnnounceScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A user announcement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// See [`Announcement::content_id`].
+    pub id: SpotId,
+    pub ts: Timestamp,
+    pub from: Callsign,
+    pub text: String,
+    pub scope: AnnounceScope,
+    /// Node the sender is logged into.
+    pub origin: Option<NodeId>,
+    pub hop: u32,
+}
+
+impl Announcement {
+    /// Build an announcement made on this node, with its id derived from
+    /// `from` and `text`.
+    pub fn new_local(
+        ts: Timestamp,
+        from: Callsign,
+        text: impl Into<String>,
+        scope: AnnounceScope,
+        origin: Option<NodeId>,
+    ) -> Self {
+        let text = text.into();
+        Self {
+            id: Self::content_id(&from, &text),
+            ts,
+            from,
+            text,
+            scope,
+            origin,
+            hop: 0,
+        }
+    }
+
+    /// Identity of an announcement: the same sender repeating the same text
+    /// is the same announcement, whichever path it took.
+    pub fn content_id(from: &Callsign, text: &str) -> SpotId {
+        SpotId::hash_components(&[b"ANN", from.as_str().as_bytes(), text.as_bytes()])
+    }
+}
+
+/// Bounded history of recent announcements with duplicate suppression.
+#[derive(Debug)]
+pub struct AnnounceCache {
+    capacity: usize,
+    entries: VecDeque<Announcement>,
+    seen: DedupeTable,
+}
+
+impl AnnounceCache {
+    /// Keep up to `capacity` announcements and drop copies of one seen
+    /// within `dedupe_ttl`.
+    pub fn new(capacity: usize, dedupe_ttl: Duration) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            seen: DedupeTable::new(dedupe_ttl),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Store `announcement` seen at `now` (Unix seconds), evicting the oldest
+    /// when full. Returns `false` without storing it if its id was seen
+    /// within the dedupe window.
+    pub fn push(&mut self, announcement: Announcement, now: u64) -> bool {
+        if self.seen.check_and_mark(announcement.id.clone(), now) == DedupeResult::Duplicate {
+            return false;
+        }
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(announcement);
+        true
+    }
+
+    /// The `n` most recent announcements, newest first.
+    pub fn recent(&self, n: usize) -> impl Iterator<Item = &Announcement> {
+        self.entries.iter().rev().take(n)
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn ann(from: &str, text: &str, scope: AnnounceScope) -> Announcement {
+        Announcement::new_local(
+            OffsetDateTime::from_unix_timestamp(NOW as i64).unwrap(),
+            Callsign::parse_loose(from).unwrap(),
+            text,
+            scope,
+            None,
+        )
+    }
+
+    fn texts(cache: &AnnounceCache, n: usize) -> Vec<&str> {
+        cache.recent(n).map(|ann| ann.text.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_newest_up_to_capacity() {
+        let mut cache = AnnounceCache::new(2, Duration::from_secs(600));
+        assert!(cache.push(ann("G4ABC", "one", AnnounceScope::Local), NOW));
+        assert!(cache.push(ann("G4ABC", "two", AnnounceScope::Full), NOW + 1));
+        assert!(cache.push(ann("G4ABC", "three", AnnounceScope::Full), NOW + 2));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(texts(&cache, 10), ["three", "two"]);
+        assert_eq!(texts(&cache, 1), ["three"]);
+    }
+
+    #[test]
+    fn drops_repeats_within_the_dedupe_window() {
+        let mut cache = AnnounceCache::new(10, Duration::from_secs(600));
+        assert!(cache.push(ann("G4ABC", "qsl via bureau", AnnounceScope::Full), NOW));
+        // Same sender and text, e.g. received again over a second link.
+        assert!(!cache.push(ann("G4ABC", "qsl via bureau", AnnounceScope::Full), NOW + 5));
+        assert!(cache.push(ann("K1XYZ", "qsl via bureau", AnnounceScope::Full), NOW + 5));
+        assert!(cache.push(
+            ann("G4ABC", "qsl via bureau", AnnounceScope::Full),
+            NOW + 700
+        ));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn scope_keywords_round_trip() {
+        for scope in [
+            AnnounceScope::Local,
+            AnnounceScope::Full,
+            AnnounceScope::Sysop,
+        ] {
+            assert_eq!(AnnounceScope::from_keyword(scope.as_str()), Some(scope));
+        }
+        assert_eq!(
+            AnnounceScope::from_keyword("FULL"),
+            Some(AnnounceScope::Full)
+        );
+        assert!(!AnnounceScope::Local.is_networked());
+        assert!(AnnounceScope::Sysop.is_networked());
+    }
+}
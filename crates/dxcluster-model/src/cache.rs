@@ -1,28 +1,255 @@
-use crate::spot::Spot;
+//! Bounded store of recent spots.
+//!
+//! Spots are kept in arrival order in a ring buffer and indexed by dx call,
+//! spotter and band, so per-call and per-band queries touch only matching
+//! spots. Each spot gets a sequence number when it is pushed, and the
+//! indexes hold sequence numbers. Eviction always removes the oldest spot,
+//! which is also the first entry of every index it appears in, so inserts
+//! and evictions are O(1).
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::Duration;
+
+use dxcluster_types::{Band, Callsign};
+
+use crate::spot::{Spot, Timestamp, unix_seconds};
 
 #[derive(Debug)]
 pub struct SpotCache {
     capacity: usize,
-    items: Vec<Spot>,
+    max_age: Option<Duration>,
+    spots: VecDeque<Spot>,
+    /// Sequence number of `spots[0]`.
+    first_seq: u64,
+    by_dx: HashMap<Callsign, VecDeque<u64>>,
+    by_spotter: HashMap<Callsign, VecDeque<u64>>,
+    by_band: HashMap<Band, VecDeque<u64>>,
 }
 
 impl SpotCache {
     pub fn new(capacity: usize) -> Self {
         SpotCache {
             capacity,
-            items: Vec::with_capacity(capacity),
+            max_age: None,
+            spots: VecDeque::with_capacity(capacity),
+            first_seq: 0,
+            by_dx: HashMap::new(),
+            by_spotter: HashMap::new(),
+            by_band: HashMap::new(),
         }
     }
 
+    /// Also drop spots older than `max_age` when [`SpotCache::expire`] runs.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn len(&self) -> usize {
+        self.spots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spots.is_empty()
+    }
+
+    /// Append `spot`, evicting the oldest spot when the cache is full.
     pub fn push(&mut self, spot: Spot) {
-        if self.items.len() == self.capacity {
-            self.items.remove(0);
+        if self.capacity == 0 {
+            return;
+        }
+        if self.spots.len() == self.capacity {
+            self.evict_oldest();
+        }
+
+        let seq = self.first_seq + self.spots.len() as u64;
+        index(&mut self.by_dx, spot.dx.clone(), seq);
+        index(&mut self.by_spotter, spot.spotter.clone(), seq);
+        if let Some(band) = Band::from_frequency(spot.freq) {
+            index(&mut self.by_band, band, seq);
+        }
+        self.spots.push_back(spot);
+    }
+
+    /// Drop spots older than the maximum age as of `now`, returning how many
+    /// were removed. Spots are expired in arrival order, so one that arrived
+    /// late with an old timestamp stays until everything before it has gone.
+    pub fn expire(&mut self, now: Timestamp) -> usize {
+        let Some(max_age) = self.max_age else {
+            return 0;
+        };
+        let cutoff = unix_seconds(&now).saturating_sub(max_age.as_secs() as i64);
+        let mut removed = 0;
+        while self
+            .spots
+            .front()
+            .is_some_and(|spot| unix_seconds(&spot.ts) < cutoff)
+        {
+            self.evict_oldest();
+            removed += 1;
         }
-        self.items.push(spot);
+        removed
     }
 
+    /// The `n` most recently added spots, newest first.
     pub fn recent(&self, n: usize) -> impl Iterator<Item = &Spot> {
-        let start = self.items.len().saturating_sub(n);
-        self.items[start..].iter().rev()
+        self.spots.iter().rev().take(n)
+    }
+
+    /// Spots of `dx`, newest first.
+    pub fn by_dx<'a>(&'a self, dx: &Callsign) -> impl Iterator<Item = &'a Spot> + use<'a> {
+        self.lookup(self.by_dx.get(dx))
+    }
+
+    /// Spots sent by `spotter`, newest first.
+    pub fn by_spotter<'a>(
+        &'a self,
+        spotter: &Callsign,
+    ) -> impl Iterator<Item = &'a Spot> + use<'a> {
+        self.lookup(self.by_spotter.get(spotter))
+    }
+
+    /// Spots on `band`, newest first.
+    pub fn on_band(&self, band: Band) -> impl Iterator<Item = &Spot> {
+        self.lookup(self.by_band.get(&band))
+    }
+
+    fn lookup<'a>(&'a self, seqs: Option<&'a VecDeque<u64>>) -> impl Iterator<Item = &'a Spot> {
+        seqs.into_iter()
+            .flatten()
+            .rev()
+            .map(|seq| &self.spots[(seq - self.first_seq) as usize])
+    }
+
+    fn evict_oldest(&mut self) {
+        let Some(spot) = self.spots.pop_front() else {
+            return;
+        };
+        let seq = self.first_seq;
+        self.first_seq += 1;
+        unindex(&mut self.by_dx, &spot.dx, seq);
+        unindex(&mut self.by_spotter, &spot.spotter, seq);
+        if let Some(band) = Band::from_frequency(spot.freq) {
+            unindex(&mut self.by_band, &band, seq);
+        }
+    }
+}
+
+fn index<K: Eq + Hash>(map: &mut HashMap<K, VecDeque<u64>>, key: K, seq: u64) {
+    map.entry(key).or_default().push_back(seq);
+}
+
+/// Remove `seq`, the oldest cached spot, from the front of `key`'s entries.
+fn unindex<K: Eq + Hash>(map: &mut HashMap<K, VecDeque<u64>>, key: &K, seq: u64) {
+    if let Some(seqs) = map.get_mut(key) {
+        debug_assert_eq!(seqs.front(), Some(&seq));
+        seqs.pop_front();
+        if seqs.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use dxcluster_types::{FrequencyHz, SpotId};
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn spot(dx: &str, spotter: &str, khz: u64, ts: i64) -> Spot {
+        Spot::new_local(
+            SpotId::hash_components(&[dx.as_bytes(), &ts.to_be_bytes()]),
+            OffsetDateTime::from_unix_timestamp(ts).unwrap(),
+            FrequencyHz(khz * 1000),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(spotter).unwrap(),
+            "",
+            None,
+        )
+    }
+
+    fn calls<'a>(spots: impl Iterator<Item = &'a Spot>) -> Vec<&'a str> {
+        spots.map(|spot| spot.dx.as_str()).collect()
+    }
+
+    fn call(value: &str) -> Callsign {
+        Callsign::parse_loose(value).unwrap()
+    }
+
+    #[test]
+    fn evicts_oldest_at_capacity() {
+        let mut cache = SpotCache::new(3);
+        for (i, dx) in ["K1A", "K1B", "K1C", "K1D"].into_iter().enumerate() {
+            cache.push(spot(dx, "G4X", 14_025, NOW + i as i64));
+        }
+        assert_eq!(cache.len(), 3);
+        assert_eq!(calls(cache.recent(10)), ["K1D", "K1C", "K1B"]);
+        assert_eq!(calls(cache.recent(2)), ["K1D", "K1C"]);
+        assert_eq!(cache.by_dx(&call("K1A")).count(), 0);
+        assert!(!cache.by_dx.contains_key(&call("K1A")));
+    }
+
+    #[test]
+    fn indexes_follow_eviction() {
+        let mut cache = SpotCache::new(4);
+        cache.push(spot("K1ABC", "G4X", 14_025, NOW));
+        cache.push(spot("DL1AA", "G4X", 7_010, NOW + 1));
+        cache.push(spot("K1ABC", "F5Y", 21_010, NOW + 2));
+        cache.push(spot("JA1ZZ", "F5Y", 14_200, NOW + 3));
+
+        let by_dx: Vec<u64> = cache
+            .by_dx(&call("K1ABC"))
+            .map(|spot| spot.freq.0 / 1000)
+            .collect();
+        assert_eq!(by_dx, [21_010, 14_025]);
+        assert_eq!(calls(cache.on_band(Band::Meter20)), ["JA1ZZ", "K1ABC"]);
+        assert_eq!(calls(cache.by_spotter(&call("F5Y"))), ["JA1ZZ", "K1ABC"]);
+
+        cache.push(spot("VK2AA", "G4X", 14_010, NOW + 4));
+        assert_eq!(cache.by_dx(&call("K1ABC")).count(), 1);
+        assert_eq!(calls(cache.on_band(Band::Meter20)), ["VK2AA", "JA1ZZ"]);
+        assert_eq!(calls(cache.by_spotter(&call("G4X"))), ["VK2AA", "DL1AA"]);
+        assert_eq!(cache.on_band(Band::Meter15).count(), 1);
+    }
+
+    #[test]
+    fn expires_spots_past_max_age() {
+        let mut cache = SpotCache::new(10).with_max_age(Duration::from_secs(600));
+        cache.push(spot("K1A", "G4X", 14_025, NOW - 900));
+        cache.push(spot("K1B", "G4X", 14_025, NOW - 300));
+        cache.push(spot("K1C", "G4X", 14_025, NOW));
+
+        assert_eq!(
+            cache.expire(OffsetDateTime::from_unix_timestamp(NOW).unwrap()),
+            1
+        );
+        assert_eq!(calls(cache.recent(10)), ["K1C", "K1B"]);
+        assert_eq!(cache.by_spotter(&call("G4X")).count(), 2);
+
+        assert_eq!(
+            cache.expire(OffsetDateTime::from_unix_timestamp(NOW + 1_000).unwrap()),
+            2
+        );
+        assert!(cache.is_empty());
+        assert!(cache.by_spotter.is_empty() && cache.by_band.is_empty());
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut cache = SpotCache::new(0);
+        cache.push(spot("K1A", "G4X", 14_025, NOW));
+        assert!(cache.is_empty());
     }
 }
@@ -1,39 +1,373 @@
-use std::collections::HashMap;
+//! Duplicate suppression for spots arriving over several links.
+//!
+//! Ids are remembered for a TTL and pruned as new spots arrive, oldest first,
+//! so memory stays bounded by the spot rate and an optional hard capacity.
+//! An optional fuzzy window also catches the same DX reported by different
+//! spotters on slightly different frequencies.
+
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 
-use dxcluster_types::SpotId;
+use dxcluster_types::{Callsign, SpotId};
+
+use crate::spot::Spot;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DedupeResult {
     Fresh,
     Duplicate,
+    /// The id was seen before, but longer ago than the TTL.
     Expired,
 }
 
+/// Same DX call from another spotter within `freq_hz` either side and
+/// `window` of an earlier spot counts as a duplicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyWindow {
+    pub freq_hz: u64,
+    pub window: Duration,
+}
+
+/// Running totals since the table was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeStats {
+    pub fresh: u64,
+    pub duplicate: u64,
+    /// Entries pruned because they outlived the TTL or fuzzy window.
+    pub expired: u64,
+    /// Entries dropped early to stay within capacity.
+    pub evicted: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Recent {
+    freq_hz: u64,
+    spotter: Callsign,
+    seen: u64,
+}
+
+#[derive(Debug)]
 pub struct DedupeTable {
     ttl: Duration,
+    capacity: Option<usize>,
+    fuzzy: Option<FuzzyWindow>,
     seen: HashMap<SpotId, u64>,
+    /// Ids in the order they were marked. An id marked again appears twice;
+    /// the older entry is stale and skipped when it reaches the front.
+    order: VecDeque<(SpotId, u64)>,
+    recent: HashMap<Callsign, VecDeque<Recent>>,
+    /// DX calls in the order their fuzzy entries were added.
+    recent_order: VecDeque<Callsign>,
+    stats: DedupeStats,
 }
 
 impl DedupeTable {
     pub fn new(ttl: Duration) -> Self {
         Self {
             ttl,
+            capacity: None,
+            fuzzy: None,
             seen: HashMap::new(),
+            order: VecDeque::new(),
+            recent: HashMap::new(),
+            recent_order: VecDeque::new(),
+            stats: DedupeStats::default(),
         }
     }
 
+    /// Remember at most `capacity` ids (and fuzzy entries), evicting the
+    /// oldest first.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    pub fn with_fuzzy(mut self, fuzzy: FuzzyWindow) -> Self {
+        self.fuzzy = Some(fuzzy);
+        self
+    }
+
+    /// Ids currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn stats(&self) -> DedupeStats {
+        self.stats
+    }
+
+    /// Check `spot_id` against ids seen within the TTL and remember it as
+    /// seen at `now` (Unix seconds).
     pub fn check_and_mark(&mut self, spot_id: SpotId, now: u64) -> DedupeResult {
-        let mut result = DedupeResult::Fresh;
-        if let Some(prev) = self.seen.get(&spot_id).copied() {
-            if now.saturating_sub(prev) <= self.ttl.as_secs() {
-                result = DedupeResult::Duplicate;
-            } else {
-                result = DedupeResult::Expired;
-            }
+        let result = self.mark_id(spot_id, now);
+        self.count(result);
+        result
+    }
+
+    /// [`DedupeTable::check_and_mark`] by id, then, with a fuzzy window, by
+    /// DX call, frequency and spotter.
+    pub fn check_spot(&mut self, spot: &Spot, now: u64) -> DedupeResult {
+        let mut result = self.mark_id(spot.spot_id.clone(), now);
+        if let Some(fuzzy) = self.fuzzy
+            && self.mark_recent(spot, fuzzy, now)
+        {
+            result = DedupeResult::Duplicate;
         }
+        self.count(result);
+        result
+    }
+
+    fn mark_id(&mut self, spot_id: SpotId, now: u64) -> DedupeResult {
+        let result = match self.seen.get(&spot_id) {
+            Some(prev) if now.saturating_sub(*prev) <= self.ttl.as_secs() => {
+                DedupeResult::Duplicate
+            }
+            Some(_) => DedupeResult::Expired,
+            None => DedupeResult::Fresh,
+        };
+        self.prune(now);
 
-        self.seen.insert(spot_id, now);
+        self.seen.insert(spot_id.clone(), now);
+        self.order.push_back((spot_id, now));
+        self.enforce_capacity();
         result
     }
+
+    /// Remember `spot` for fuzzy matching, returning whether another spotter
+    /// reported the same DX nearby within the window.
+    fn mark_recent(&mut self, spot: &Spot, fuzzy: FuzzyWindow, now: u64) -> bool {
+        let near = self.recent.get(&spot.dx).is_some_and(|entries| {
+            entries.iter().any(|entry| {
+                entry.spotter != spot.spotter
+                    && entry.freq_hz.abs_diff(spot.freq.0) <= fuzzy.freq_hz
+                    && now.saturating_sub(entry.seen) <= fuzzy.window.as_secs()
+            })
+        });
+        self.recent
+            .entry(spot.dx.clone())
+            .or_default()
+            .push_back(Recent {
+                freq_hz: spot.freq.0,
+                spotter: spot.spotter.clone(),
+                seen: now,
+            });
+        self.recent_order.push_back(spot.dx.clone());
+        self.enforce_capacity();
+        near
+    }
+
+    /// Drop ids older than the TTL and fuzzy entries older than the window.
+    fn prune(&mut self, now: u64) {
+        let ttl = self.ttl.as_secs();
+        while let Some((_, marked)) = self.order.front() {
+            if now.saturating_sub(*marked) <= ttl {
+                break;
+            }
+            if self.pop_oldest_id() {
+                self.stats.expired += 1;
+            }
+        }
+
+        if let Some(fuzzy) = self.fuzzy {
+            let window = fuzzy.window.as_secs();
+            while let Some(dx) = self.recent_order.front() {
+                let expired = self
+                    .recent
+                    .get(dx)
+                    .and_then(|entries| entries.front())
+                    .is_some_and(|entry| now.saturating_sub(entry.seen) > window);
+                if !expired {
+                    break;
+                }
+                self.pop_oldest_recent();
+                self.stats.expired += 1;
+            }
+        }
+    }
+
+    fn enforce_capacity(&mut self) {
+        let Some(capacity) = self.capacity else {
+            return;
+        };
+        // `order` holds every live id plus stale entries left by re-marking,
+        // so bounding it bounds both.
+        while self.order.len() > capacity {
+            if self.pop_oldest_id() {
+                self.stats.evicted += 1;
+            }
+        }
+        while self.recent_order.len() > capacity {
+            self.pop_oldest_recent();
+            self.stats.evicted += 1;
+        }
+    }
+
+    /// Pop the front of `order`, returning whether it removed a live id
+    /// rather than a stale entry.
+    fn pop_oldest_id(&mut self) -> bool {
+        let Some((id, marked)) = self.order.pop_front() else {
+            return false;
+        };
+        if self.seen.get(&id) == Some(&marked) {
+            self.seen.remove(&id);
+            return true;
+        }
+        false
+    }
+
+    fn pop_oldest_recent(&mut self) {
+        let Some(dx) = self.recent_order.pop_front() else {
+            return;
+        };
+        if let Some(entries) = self.recent.get_mut(&dx) {
+            entries.pop_front();
+            if entries.is_empty() {
+                self.recent.remove(&dx);
+            }
+        }
+    }
+
+    fn count(&mut self, result: DedupeResult) {
+        match result {
+            DedupeResult::Fresh | DedupeResult::Expired => self.stats.fresh += 1,
+            DedupeResult::Duplicate => self.stats.duplicate += 1,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use dxcluster_types::FrequencyHz;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn id(n: u32) -> SpotId {
+        SpotId::hash_components(&[&n.to_be_bytes()])
+    }
+
+    fn spot(n: u32, dx: &str, spotter: &str, hz: u64) -> Spot {
+        Spot::new_local(
+            id(n),
+            OffsetDateTime::from_unix_timestamp(NOW as i64).unwrap(),
+            FrequencyHz(hz),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(spotter).unwrap(),
+            "",
+            None,
+        )
+    }
+
+    #[test]
+    fn prunes_ids_past_the_ttl() {
+        let mut table = DedupeTable::new(Duration::from_secs(60));
+        assert_eq!(table.check_and_mark(id(1), NOW), DedupeResult::Fresh);
+        assert_eq!(
+            table.check_and_mark(id(1), NOW + 30),
+            DedupeResult::Duplicate
+        );
+        assert_eq!(table.check_and_mark(id(2), NOW + 40), DedupeResult::Fresh);
+        assert_eq!(
+            table.check_and_mark(id(1), NOW + 200),
+            DedupeResult::Expired
+        );
+        assert_eq!(table.len(), 1);
+        assert_eq!(
+            table.stats(),
+            DedupeStats {
+                fresh: 3,
+                duplicate: 1,
+                expired: 2,
+                evicted: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_capacity() {
+        let mut table = DedupeTable::new(Duration::from_secs(3_600)).with_capacity(2);
+        for n in 0..4 {
+            table.check_and_mark(id(n), NOW + u64::from(n));
+        }
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.stats().evicted, 2);
+        assert_eq!(
+            table.check_and_mark(id(3), NOW + 5),
+            DedupeResult::Duplicate
+        );
+        assert_eq!(table.check_and_mark(id(0), NOW + 6), DedupeResult::Fresh);
+
+        // Re-marking an id leaves a stale entry behind; it is skipped, not
+        // counted as an eviction.
+        let mut table = DedupeTable::new(Duration::from_secs(3_600)).with_capacity(2);
+        table.check_and_mark(id(0), NOW);
+        table.check_and_mark(id(0), NOW + 1);
+        table.check_and_mark(id(1), NOW + 2);
+        table.check_and_mark(id(2), NOW + 3);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.stats().evicted, 1);
+        assert_eq!(
+            table.check_and_mark(id(2), NOW + 4),
+            DedupeResult::Duplicate
+        );
+    }
+
+    #[test]
+    fn replayed_ids_stay_within_capacity() {
+        let mut table = DedupeTable::new(Duration::from_secs(3_600)).with_capacity(4);
+        table.check_and_mark(id(0), NOW);
+        for n in 1..1_000 {
+            assert_eq!(
+                table.check_and_mark(id(0), NOW + n),
+                DedupeResult::Duplicate
+            );
+            assert!(table.order.len() <= 4);
+        }
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.stats().evicted, 0);
+    }
+
+    #[test]
+    fn fuzzy_window_catches_other_spotters_nearby() {
+        let mut table = DedupeTable::new(Duration::from_secs(3_600)).with_fuzzy(FuzzyWindow {
+            freq_hz: 1_000,
+            window: Duration::from_secs(300),
+        });
+        let first = spot(1, "K1ABC", "G4ABC", 14_025_000);
+        assert_eq!(table.check_spot(&first, NOW), DedupeResult::Fresh);
+
+        // The same spotter re-spotting, another frequency or another call
+        // are all fresh.
+        let respot = spot(3, "K1ABC", "G4ABC", 14_025_500);
+        assert_eq!(table.check_spot(&respot, NOW + 30), DedupeResult::Fresh);
+        let other_freq = spot(4, "K1ABC", "F5YY", 14_030_000);
+        assert_eq!(
+            table.check_spot(&other_freq, NOW + 100),
+            DedupeResult::Fresh
+        );
+        let other_call = spot(5, "K1ABD", "F5YY", 14_025_000);
+        assert_eq!(
+            table.check_spot(&other_call, NOW + 110),
+            DedupeResult::Fresh
+        );
+
+        let nearby = spot(2, "K1ABC", "DL1XX", 14_025_800);
+        assert_eq!(
+            table.check_spot(&nearby, NOW + 120),
+            DedupeResult::Duplicate
+        );
+
+        // Outside the window the earlier reports have been pruned.
+        let later = spot(6, "K1ABC", "VK2AA", 14_025_000);
+        assert_eq!(table.check_spot(&later, NOW + 1_000), DedupeResult::Fresh);
+        let stats = table.stats();
+        assert_eq!((stats.fresh, stats.duplicate), (5, 1));
+        assert_eq!(stats.expired, 5);
+    }
 }
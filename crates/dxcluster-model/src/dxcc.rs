@@ -0,0 +1,628 @@
+//! DXCC entity resolution from the `cty.dat` / `cty.csv` country files
+//! published at <https://www.country-files.com>.
+//!
+//! A [`CtyTable`] maps callsigns to entities by exact (`=`) entries first and
+//! then by longest prefix. Per-prefix overrides for zones, continent,
+//! location and UTC offset are applied to the result. [`SharedCtyTable`]
+//! wraps a table so readers can keep using a snapshot while a newer file is
+//! swapped in.
+//!
+//! The country files give longitude and UTC offset with west positive; both
+//! are converted to the usual east-positive convention on load.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use dxcluster_types::{Callsign, CallsignParts, Location};
+
+use crate::error::CtyError;
+use crate::filter::DxccLookup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    /// Two-letter abbreviation used by the country files and filters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Continent::Africa => "AF",
+            Continent::Antarctica => "AN",
+            Continent::Asia => "AS",
+            Continent::Europe => "EU",
+            Continent::NorthAmerica => "NA",
+            Continent::Oceania => "OC",
+            Continent::SouthAmerica => "SA",
+        }
+    }
+}
+
+impl fmt::Display for Continent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Continent {
+    type Err = CtyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "AF" => Ok(Continent::Africa),
+            "AN" => Ok(Continent::Antarctica),
+            "AS" => Ok(Continent::Asia),
+            "EU" => Ok(Continent::Europe),
+            "NA" => Ok(Continent::NorthAmerica),
+            "OC" => Ok(Continent::Oceania),
+            "SA" => Ok(Continent::SouthAmerica),
+            other => Err(CtyError::UnknownContinent(other.to_string())),
+        }
+    }
+}
+
+/// One entity record from the country file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxccEntity {
+    pub name: String,
+    /// Primary prefix, without the `*` marking WAE-only entities.
+    pub prefix: String,
+    /// ADIF entity number; only `cty.csv` carries it.
+    pub adif: Option<u16>,
+    pub continent: Continent,
+    pub cq_zone: u8,
+    pub itu_zone: u8,
+    /// Degrees, north positive.
+    pub latitude: f64,
+    /// Degrees, east positive.
+    pub longitude: f64,
+    /// Hours from UTC, east positive.
+    pub utc_offset: f32,
+    /// Counts for the DARC WAEDC award but is not a DXCC entity.
+    pub waedc_only: bool,
+}
+
+/// Result of a lookup: the entity with any per-prefix overrides applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxccInfo<'a> {
+    pub entity: &'a DxccEntity,
+    /// The country-file prefix or exact call that matched.
+    pub matched: &'a str,
+    pub continent: Continent,
+    pub cq_zone: u8,
+    pub itu_zone: u8,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub utc_offset: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Overrides {
+    cq_zone: Option<u8>,
+    itu_zone: Option<u8>,
+    continent: Option<Continent>,
+    location: Option<(f64, f64)>,
+    utc_offset: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    entity: usize,
+    pattern: String,
+    overrides: Overrides,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CtyTable {
+    entities: Vec<DxccEntity>,
+    prefixes: HashMap<String, Entry>,
+    exact: HashMap<String, Entry>,
+    longest_prefix: usize,
+}
+
+impl CtyTable {
+    /// Load a country file from disk; files ending in `.csv` are read as
+    /// `cty.csv`, anything else as `cty.dat`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CtyError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            Self::parse_csv(&text)
+        } else {
+            Self::parse_dat(&text)
+        }
+    }
+
+    /// Parse the `cty.dat` format: an eight-field, colon-separated entity
+    /// header followed by a comma-separated alias list ending in `;`.
+    pub fn parse_dat(text: &str) -> Result<Self, CtyError> {
+        let mut table = CtyTable::default();
+        let mut line = 1;
+        for record in text.split(';') {
+            let leading = record.len() - record.trim_start().len();
+            let record_line = line + record[..leading].matches('\n').count();
+            line += record.matches('\n').count();
+            if record.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = record.splitn(9, ':').collect();
+            if fields.len() != 9 {
+                return Err(CtyError::Syntax {
+                    line: record_line,
+                    message: "entity header needs 8 colon-separated fields".to_string(),
+                });
+            }
+            let syntax = |message: String| CtyError::Syntax {
+                line: record_line,
+                message,
+            };
+            let entity = EntityFields {
+                name: fields[0],
+                prefix: fields[7],
+                adif: None,
+                continent: fields[3],
+                cq_zone: fields[1],
+                itu_zone: fields[2],
+                latitude: fields[4],
+                longitude: fields[5],
+                utc_offset: fields[6],
+            }
+            .parse()
+            .map_err(syntax)?;
+            let aliases = fields[8].split(',').map(str::trim);
+            table.push_entity(entity, aliases).map_err(syntax)?;
+        }
+        Ok(table)
+    }
+
+    /// Parse the `cty.csv` format: one entity per line with the prefix list
+    /// space-separated in the last field.
+    pub fn parse_csv(text: &str) -> Result<Self, CtyError> {
+        let mut table = CtyTable::default();
+        for (index, row) in text.lines().enumerate() {
+            if row.trim().is_empty() {
+                continue;
+            }
+            let syntax = |message: String| CtyError::Syntax {
+                line: index + 1,
+                message,
+            };
+            let fields: Vec<&str> = row.splitn(10, ',').collect();
+            if fields.len() != 10 {
+                return Err(syntax("expected 10 comma-separated fields".to_string()));
+            }
+            let adif = fields[2]
+                .trim()
+                .parse()
+                .map_err(|_| syntax(format!("invalid entity number: {}", fields[2])))?;
+            let entity = EntityFields {
+                name: fields[1],
+                prefix: fields[0],
+                adif: Some(adif),
+                continent: fields[3],
+                cq_zone: fields[4],
+                itu_zone: fields[5],
+                latitude: fields[6],
+                longitude: fields[7],
+                utc_offset: fields[8],
+            }
+            .parse()
+            .map_err(syntax)?;
+            let aliases = fields[9].trim().trim_end_matches(';').split_whitespace();
+            table.push_entity(entity, aliases).map_err(syntax)?;
+        }
+        Ok(table)
+    }
+
+    fn push_entity<'a>(
+        &mut self,
+        entity: DxccEntity,
+        aliases: impl Iterator<Item = &'a str>,
+    ) -> Result<(), String> {
+        let index = self.entities.len();
+        for alias in aliases.filter(|alias| !alias.is_empty()) {
+            let (exact, pattern, overrides) = parse_alias(alias)?;
+            let entry = Entry {
+                entity: index,
+                pattern: pattern.clone(),
+                overrides,
+            };
+            if exact {
+                self.exact.insert(pattern, entry);
+            } else {
+                self.longest_prefix = self.longest_prefix.max(pattern.len());
+                self.prefixes.insert(pattern, entry);
+            }
+        }
+        self.entities.push(entity);
+        Ok(())
+    }
+
+    pub fn entities(&self) -> &[DxccEntity] {
+        &self.entities
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Whether entities carry DXCC numbers. Only `cty.csv` provides them;
+    /// without them `call_dxcc`/`by_dxcc` predicates match nothing.
+    pub fn has_dxcc_numbers(&self) -> bool {
+        self.entities.iter().any(|entity| entity.adif.is_some())
+    }
+
+    /// Resolve a callsign. Exact entries are tried against the whole call
+    /// and then against the base call when the station is at home;
+    /// otherwise the prefix that governs its [`Location`] is matched,
+    /// longest first. Maritime and aeronautical mobile stations (`/MM`,
+    /// `/AM`) have no entity.
+    pub fn lookup(&self, call: &Callsign) -> Option<DxccInfo<'_>> {
+        self.lookup_str(call.as_str())
+    }
+
+    fn lookup_str(&self, call: &str) -> Option<DxccInfo<'_>> {
+        let call = call.trim().to_ascii_uppercase();
+        let full = call.split_once('-').map_or(call.as_str(), |(base, _)| base);
+        if let Some(entry) = self.exact.get(full) {
+            return Some(self.resolve(entry));
+        }
+
+        // Calls that do not decompose are matched as written.
+        let Ok(parts) = CallsignParts::parse(full) else {
+            return self.lookup_prefix(full);
+        };
+        match parts.location() {
+            Location::Home => match self.exact.get(&parts.base) {
+                Some(entry) => Some(self.resolve(entry)),
+                None => self.lookup_prefix(&parts.base),
+            },
+            Location::Prefix(prefix) => self.lookup_prefix(&prefix),
+            Location::NoEntity => None,
+        }
+    }
+
+    /// Longest-prefix match of `prefix` against the table, ignoring exact
+    /// entries.
+    pub fn lookup_prefix(&self, prefix: &str) -> Option<DxccInfo<'_>> {
+        let prefix = prefix.to_ascii_uppercase();
+        let mut end = prefix.len().min(self.longest_prefix);
+        while end > 0 {
+            if let Some(candidate) = prefix.get(..end)
+                && let Some(entry) = self.prefixes.get(candidate)
+            {
+                return Some(self.resolve(entry));
+            }
+            end -= 1;
+        }
+        None
+    }
+
+    fn resolve<'a>(&'a self, entry: &'a Entry) -> DxccInfo<'a> {
+        let entity = &self.entities[entry.entity];
+        let overrides = &entry.overrides;
+        let (latitude, longitude) = overrides
+            .location
+            .unwrap_or((entity.latitude, entity.longitude));
+        DxccInfo {
+            entity,
+            matched: &entry.pattern,
+            continent: overrides.continent.unwrap_or(entity.continent),
+            cq_zone: overrides.cq_zone.unwrap_or(entity.cq_zone),
+            itu_zone: overrides.itu_zone.unwrap_or(entity.itu_zone),
+            latitude,
+            longitude,
+            utc_offset: overrides.utc_offset.unwrap_or(entity.utc_offset),
+        }
+    }
+}
+
+/// Entity numbers come from the ADIF column, so only tables loaded from
+/// `cty.csv` resolve `call_dxcc`/`by_dxcc` filter predicates.
+impl DxccLookup for CtyTable {
+    fn dxcc(&self, call: &str) -> Option<u16> {
+        self.lookup_str(call)?.entity.adif
+    }
+}
+
+/// A [`CtyTable`] shared between tasks and replaceable at runtime. Clones
+/// refer to the same table; lookups go through a [`current`] snapshot so a
+/// reload never disturbs a lookup in progress.
+///
+/// [`current`]: SharedCtyTable::current
+#[derive(Debug, Clone, Default)]
+pub struct SharedCtyTable {
+    inner: Arc<RwLock<Arc<CtyTable>>>,
+}
+
+impl SharedCtyTable {
+    pub fn new(table: CtyTable) -> Self {
+        SharedCtyTable {
+            inner: Arc::new(RwLock::new(Arc::new(table))),
+        }
+    }
+
+    /// The table in use right now.
+    pub fn current(&self) -> Arc<CtyTable> {
+        self.inner
+            .read()
+            .expect("country table lock poisoned")
+            .clone()
+    }
+
+    pub fn replace(&self, table: CtyTable) {
+        *self.inner.write().expect("country table lock poisoned") = Arc::new(table);
+    }
+
+    /// Load `path` and swap it in. On error the current table is kept.
+    pub fn reload(&self, path: impl AsRef<Path>) -> Result<(), CtyError> {
+        self.replace(CtyTable::load(path)?);
+        Ok(())
+    }
+}
+
+impl DxccLookup for SharedCtyTable {
+    fn dxcc(&self, call: &str) -> Option<u16> {
+        self.current().dxcc(call)
+    }
+}
+
+/// Raw entity fields as they appear in either file format.
+struct EntityFields<'a> {
+    name: &'a str,
+    prefix: &'a str,
+    adif: Option<u16>,
+    continent: &'a str,
+    cq_zone: &'a str,
+    itu_zone: &'a str,
+    latitude: &'a str,
+    longitude: &'a str,
+    utc_offset: &'a str,
+}
+
+impl EntityFields<'_> {
+    fn parse(self) -> Result<DxccEntity, String> {
+        let prefix = self.prefix.trim();
+        let (waedc_only, prefix) = match prefix.strip_prefix('*') {
+            Some(prefix) => (true, prefix),
+            None => (false, prefix),
+        };
+        Ok(DxccEntity {
+            name: self.name.trim().to_string(),
+            prefix: prefix.to_string(),
+            adif: self.adif,
+            continent: self
+                .continent
+                .parse()
+                .map_err(|err: CtyError| err.to_string())?,
+            cq_zone: number(self.cq_zone, "CQ zone")?,
+            itu_zone: number(self.itu_zone, "ITU zone")?,
+            latitude: number(self.latitude, "latitude")?,
+            longitude: -number::<f64>(self.longitude, "longitude")?,
+            utc_offset: -number::<f32>(self.utc_offset, "UTC offset")?,
+            waedc_only,
+        })
+    }
+}
+
+fn number<T: FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid {what}: {}", value.trim()))
+}
+
+/// Split an alias such as `=VP2E/W1AW(8)[11]` into its exactness, the
+/// uppercase pattern and any overrides.
+fn parse_alias(alias: &str) -> Result<(bool, String, Overrides), String> {
+    let (exact, alias) = match alias.strip_prefix('=') {
+        Some(rest) => (true, rest),
+        None => (false, alias),
+    };
+    let split = alias.find(['(', '[', '<', '{', '~']).unwrap_or(alias.len());
+    let (pattern, mut rest) = alias.split_at(split);
+    if pattern.is_empty() {
+        return Err(format!("empty prefix in alias {alias}"));
+    }
+
+    let mut overrides = Overrides::default();
+    while let Some(open) = rest.chars().next() {
+        let close = match open {
+            '(' => ')',
+            '[' => ']',
+            '<' => '>',
+            '{' => '}',
+            '~' => '~',
+            _ => return Err(format!("unexpected {open:?} in alias {alias}")),
+        };
+        let end = rest[1..]
+            .find(close)
+            .ok_or_else(|| format!("unterminated {open:?} in alias {alias}"))?;
+        let value = &rest[1..=end];
+        match open {
+            '(' => overrides.cq_zone = Some(number(value, "CQ zone")?),
+            '[' => overrides.itu_zone = Some(number(value, "ITU zone")?),
+            '<' => {
+                let (lat, lon) = value
+                    .split_once('/')
+                    .ok_or_else(|| format!("invalid location in alias {alias}"))?;
+                overrides.location =
+                    Some((number(lat, "latitude")?, -number::<f64>(lon, "longitude")?));
+            }
+            '{' => {
+                overrides.continent = Some(value.parse().map_err(|err: CtyError| err.to_string())?)
+            }
+            _ => overrides.utc_offset = Some(-number::<f32>(value, "UTC offset")?),
+        }
+        rest = &rest[end + 2..];
+    }
+    Ok((exact, pattern.to_ascii_uppercase(), overrides))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTY_DAT: &str = "\
+England:                  14:  27:  EU:   52.77:     1.47:     0.0:  G:
+    2E,G,M,=G4ABC/LH(15);
+United States:            05:  08:  NA:   37.53:    91.67:     5.0:  K:
+    AA,K,N,W,
+    KH6BB(31)[61]<21.12/157.00>{OC}~10.0~,=VP2EAA;
+Hawaii:                   31:  61:  OC:   21.12:   157.48:    10.0:  KH6:
+    AH6,KH6,NH6,WH6;
+Canary Islands:           33:  36:  AF:   28.32:    15.85:     0.0:  EA8:
+    AM8,EA8,EH8;
+Shetland Islands:         14:  27:  EU:   60.50:     1.50:     0.0:  *GM/s:
+    =GM4ZHL;
+";
+
+    fn call(value: &str) -> Callsign {
+        Callsign::parse_loose(value).unwrap()
+    }
+
+    fn name(table: &CtyTable, value: &str) -> Option<String> {
+        table
+            .lookup(&call(value))
+            .map(|info| info.entity.name.clone())
+    }
+
+    #[test]
+    fn parses_dat_entities() {
+        let table = CtyTable::parse_dat(CTY_DAT).unwrap();
+        assert_eq!(table.entities().len(), 5);
+        let usa = &table.entities()[1];
+        assert_eq!(usa.prefix, "K");
+        assert_eq!(usa.continent, Continent::NorthAmerica);
+        assert_eq!((usa.cq_zone, usa.itu_zone), (5, 8));
+        assert_eq!(usa.longitude, -91.67);
+        assert_eq!(usa.utc_offset, -5.0);
+        let shetland = &table.entities()[4];
+        assert!(shetland.waedc_only);
+        assert_eq!(shetland.prefix, "GM/s");
+        assert!(!table.has_dxcc_numbers());
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let table = CtyTable::parse_dat(CTY_DAT).unwrap();
+        assert_eq!(name(&table, "G4XYZ").as_deref(), Some("England"));
+        assert_eq!(name(&table, "KH6ABC").as_deref(), Some("Hawaii"));
+        assert_eq!(name(&table, "K1ABC").as_deref(), Some("United States"));
+        assert_eq!(name(&table, "JA1ABC"), None);
+    }
+
+    #[test]
+    fn exact_entries_and_overrides() {
+        let table = CtyTable::parse_dat(CTY_DAT).unwrap();
+        assert_eq!(name(&table, "VP2EAA").as_deref(), Some("United States"));
+        assert_eq!(name(&table, "GM4ZHL").as_deref(), Some("Shetland Islands"));
+
+        let lighthouse = table.lookup(&call("G4ABC/LH")).unwrap();
+        assert_eq!(lighthouse.matched, "G4ABC/LH");
+        assert_eq!(lighthouse.cq_zone, 15);
+        assert_eq!(lighthouse.itu_zone, 27);
+
+        let info = table.lookup(&call("KH6BBZ")).unwrap();
+        assert_eq!(info.entity.name, "United States");
+        assert_eq!((info.cq_zone, info.itu_zone), (31, 61));
+        assert_eq!(info.continent, Continent::Oceania);
+        assert_eq!((info.latitude, info.longitude), (21.12, -157.0));
+        assert_eq!(info.utc_offset, -10.0);
+    }
+
+    #[test]
+    fn portable_prefix_governs_location() {
+        let table = CtyTable::parse_dat(CTY_DAT).unwrap();
+        assert_eq!(name(&table, "EA8/G4XYZ").as_deref(), Some("Canary Islands"));
+        assert_eq!(name(&table, "G4XYZ/EA8").as_deref(), Some("Canary Islands"));
+        assert_eq!(name(&table, "G4XYZ/P").as_deref(), Some("England"));
+        assert_eq!(name(&table, "G4XYZ/MM"), None);
+        assert_eq!(name(&table, "KH6/W1AW/M").as_deref(), Some("Hawaii"));
+        assert_eq!(name(&table, "KH6ABC/4").as_deref(), Some("Hawaii"));
+        assert_eq!(
+            name(&table, "GM4ZHL/P").as_deref(),
+            Some("Shetland Islands")
+        );
+    }
+
+    #[test]
+    fn parses_csv() {
+        let csv = "\
+G,England,223,EU,14,27,52.77,1.47,0.0,2E G M =G4ABC/LH(15);
+EA8,Canary Islands,29,AF,33,36,28.32,15.85,0.0,AM8 EA8 EH8;
+";
+        let table = CtyTable::parse_csv(csv).unwrap();
+        assert_eq!(table.entities()[0].adif, Some(223));
+        assert!(table.has_dxcc_numbers());
+        let info = table.lookup(&call("EH8ABC")).unwrap();
+        assert_eq!(info.entity.name, "Canary Islands");
+        assert_eq!(info.continent, Continent::Africa);
+        assert_eq!(table.lookup(&call("G4ABC/LH")).unwrap().cq_zone, 15);
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_line() {
+        let err =
+            CtyTable::parse_dat("England: 14: 27: EU: 52.77: 1.47: 0.0: G:\n    G;\nBad: 1: 2;")
+                .unwrap_err();
+        assert!(matches!(err, CtyError::Syntax { line: 3, .. }), "{err}");
+
+        let err = CtyTable::parse_csv("G,England,x,EU,14,27,52.77,1.47,0.0,G;").unwrap_err();
+        assert!(matches!(err, CtyError::Syntax { line: 1, .. }), "{err}");
+    }
+
+    #[test]
+    fn shared_table_swaps_atomically() {
+        let shared = SharedCtyTable::default();
+        assert!(shared.current().is_empty());
+
+        let before = shared.current();
+        shared.replace(CtyTable::parse_dat(CTY_DAT).unwrap());
+        assert!(before.is_empty());
+        assert_eq!(
+            shared
+                .clone()
+                .current()
+                .lookup(&call("G4XYZ"))
+                .unwrap()
+                .entity
+                .name,
+            "England"
+        );
+    }
+
+    #[test]
+    fn reload_keeps_table_on_error() {
+        let shared = SharedCtyTable::new(CtyTable::parse_dat(CTY_DAT).unwrap());
+        let dir = std::env::temp_dir().join(format!("cty-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = dir.join("missing.dat");
+        assert!(matches!(shared.reload(&missing), Err(CtyError::Io(_))));
+        assert_eq!(shared.current().entities().len(), 5);
+
+        let csv = dir.join("cty.csv");
+        std::fs::write(
+            &csv,
+            "EA8,Canary Islands,29,AF,33,36,28.32,15.85,0.0,EA8;\n",
+        )
+        .unwrap();
+        shared.reload(&csv).unwrap();
+        assert_eq!(shared.current().entities().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
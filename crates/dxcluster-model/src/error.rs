@@ -1,14 +1,80 @@
+/// Why a spot was refused by [`Policy`](crate::Policy).
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[error("{reason}")]
-pub struct PolicyReject {
-    pub reason: String,
+pub enum PolicyReject {
+    #[error("frequency {freq_hz} Hz is outside the band plan")]
+    OutOfBand { freq_hz: u64 },
+    #[error("spot time is {ahead_secs}s in the future")]
+    FutureTimestamp { ahead_secs: i64 },
+    #[error("spot is {age_secs}s old")]
+    StaleTimestamp { age_secs: i64 },
+    #[error("spotter {0} is banned")]
+    BannedSpotter(String),
+    #[error("spots of {0} are not accepted")]
+    BannedDx(String),
+    #[error("spots from node {0} are not accepted")]
+    BannedNode(String),
+    #[error("comment is {len} characters, limit is {max}")]
+    CommentTooLong { len: usize, max: usize },
+    #[error("comment contains a forbidden word: {0}")]
+    BadWord(String),
+    #[error("{0} cannot spot themselves")]
+    SelfSpot(String),
 }
 
 impl PolicyReject {
-    pub fn new(reason: impl Into<String>) -> Self {
-        PolicyReject {
-            reason: reason.into(),
+    /// Stable machine-readable identifier for this rejection.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PolicyReject::OutOfBand { .. } => "out_of_band",
+            PolicyReject::FutureTimestamp { .. } => "future_timestamp",
+            PolicyReject::StaleTimestamp { .. } => "stale_timestamp",
+            PolicyReject::BannedSpotter(_) => "banned_spotter",
+            PolicyReject::BannedDx(_) => "banned_dx",
+            PolicyReject::BannedNode(_) => "banned_node",
+            PolicyReject::CommentTooLong { .. } => "comment_too_long",
+            PolicyReject::BadWord(_) => "bad_word",
+            PolicyReject::SelfSpot(_) => "self_spot",
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FilterError {
+    #[error("filter line was empty")]
+    Empty,
+    #[error("filter line must start with accept/ or reject/")]
+    UnknownAction,
+    #[error("unsupported filter type: {0}")]
+    UnknownType(String),
+    #[error("filter slot must be 0-9, got {0}")]
+    InvalidSlot(String),
+    #[error("unknown filter keyword: {0}")]
+    UnknownKeyword(String),
+    #[error("filter keyword {0} is missing its argument")]
+    MissingArgument(&'static str),
+    #[error("invalid argument for {keyword}: {value}")]
+    InvalidArgument {
+        keyword: &'static str,
+        value: String,
+    },
+    #[error("unbalanced parentheses in filter")]
+    UnbalancedParens,
+    #[error("filter nests more than {0} levels deep")]
+    TooDeep(usize),
+    #[error("unexpected token in filter: {0}")]
+    UnexpectedToken(String),
+    #[error("filter expression is missing")]
+    MissingExpression,
+}
+
+/// Failure to load a `cty.dat` / `cty.csv` country file.
+#[derive(Debug, thiserror::Error)]
+pub enum CtyError {
+    #[error("failed to read country file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("country file line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("unknown continent: {0}")]
+    UnknownContinent(String),
+}
@@ -1,10 +1,1078 @@
+//! DXSpider-style filters.
+//!
+//! Each filter line names an action, a filter type, an optional slot number
+//! and a boolean expression built from predicates such as `on 20m`,
+//! `call K,W`, `by G`, `info iota` or `origin GB7`, combined with `and`, `or`,
+//! `not` and parentheses:
+//!
+//! ```text
+//! reject/spots 0 on hf and (call_dxcc 291 or info ft8)
+//! accept/spots freq 14000/14100 and not by K1ABC
+//! accept/announce 2 by G
+//! ```
+//!
+//! A [`Filter`] holds up to [`MAX_SLOT`]` + 1` numbered slots for one filter
+//! type, each with at most one accept and one reject line; lines without a
+//! number go into [`DEFAULT_SLOT`]. Slots are tried in ascending order, reject
+//! before accept, and the first matching line decides. When nothing matches,
+//! the item passes only if the filter has no accept lines. A [`FilterSet`]
+//! keeps one [`Filter`] per [`FilterType`]. Lines render back to the same
+//! syntax through [`Display`].
+//!
+//! [`Display`]: std::fmt::Display
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use dxcluster_types::{Band, Callsign, FrequencyHz, Mode};
+
+use crate::error::FilterError;
 use crate::spot::Spot;
 
-#[derive(Debug, Clone, Default)]
-pub struct Filter;
+/// Slot used when a filter line does not carry an explicit number.
+pub const DEFAULT_SLOT: u8 = 1;
+/// Highest slot number accepted in a filter line.
+pub const MAX_SLOT: u8 = 9;
+/// Deepest nesting of parentheses and `not` accepted in an expression.
+pub const MAX_NESTING: usize = 32;
+
+/// Resolves callsigns and prefixes to DXCC entity numbers for the
+/// `call_dxcc` and `by_dxcc` predicates.
+pub trait DxccLookup {
+    fn dxcc(&self, call: &str) -> Option<u16>;
+}
+
+/// Slot-numbered accept/reject lines for a single filter type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    rules: Vec<FilterRule>,
+}
 
 impl Filter {
-    pub fn matches(&self, _spot: &Spot) -> bool {
-        true
+    /// Build a filter from already parsed lines. Later lines replace earlier
+    /// ones with the same slot and action.
+    pub fn new(rules: Vec<FilterRule>) -> Self {
+        let mut filter = Filter::default();
+        for rule in rules {
+            filter.push(rule);
+        }
+        filter
+    }
+
+    /// Store a line in its slot, replacing any line with the same action
+    /// already there.
+    pub fn push(&mut self, rule: FilterRule) {
+        self.rules
+            .retain(|existing| existing.slot != rule.slot || existing.action != rule.action);
+        self.rules.push(rule);
+        self.rules
+            .sort_by_key(|rule| (rule.slot, rule.action == FilterAction::Accept));
+    }
+
+    /// Remove both lines in `slot`, returning whether anything was removed.
+    pub fn clear_slot(&mut self, slot: u8) -> bool {
+        let before = self.rules.len();
+        self.rules.retain(|rule| rule.slot != slot);
+        self.rules.len() != before
+    }
+
+    /// Remove every line, returning the filter to accept-all.
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Lines in evaluation order.
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate the filter without DXCC information. `call_dxcc`/`by_dxcc`
+    /// prefix arguments fall back to plain prefix matching and entity numbers
+    /// never match.
+    pub fn matches(&self, spot: &Spot) -> bool {
+        self.evaluate(spot, None)
+    }
+
+    /// Evaluate the filter, resolving DXCC predicates through `lookup`.
+    pub fn matches_with_dxcc(&self, spot: &Spot, lookup: &dyn DxccLookup) -> bool {
+        self.evaluate(spot, Some(lookup))
+    }
+
+    fn evaluate(&self, spot: &Spot, lookup: Option<&dyn DxccLookup>) -> bool {
+        if let Some(rule) = self.rules.iter().find(|rule| rule.expr.eval(spot, lookup)) {
+            return rule.action == FilterAction::Accept;
+        }
+        !self
+            .rules
+            .iter()
+            .any(|rule| rule.action == FilterAction::Accept)
+    }
+}
+
+/// Per-user collection of filters keyed by [`FilterType`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterSet {
+    filters: BTreeMap<FilterType, Filter>,
+}
+
+impl FilterSet {
+    /// Store a line in the filter for its type.
+    pub fn push(&mut self, rule: FilterRule) {
+        self.filters.entry(rule.kind).or_default().push(rule);
+    }
+
+    /// Remove both lines in `slot` of the `kind` filter.
+    pub fn clear_slot(&mut self, kind: FilterType, slot: u8) -> bool {
+        let removed = self
+            .filters
+            .get_mut(&kind)
+            .is_some_and(|filter| filter.clear_slot(slot));
+        self.filters.retain(|_, filter| !filter.is_empty());
+        removed
+    }
+
+    /// Remove every line of the `kind` filter.
+    pub fn clear(&mut self, kind: FilterType) -> bool {
+        self.filters.remove(&kind).is_some()
+    }
+
+    /// Filter for `kind`, or `None` when it accepts everything.
+    pub fn get(&self, kind: FilterType) -> Option<&Filter> {
+        self.filters.get(&kind)
+    }
+
+    /// All lines, grouped by type and in evaluation order.
+    pub fn rules(&self) -> impl Iterator<Item = &FilterRule> {
+        self.filters.values().flat_map(|filter| filter.rules())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Evaluate the `spots` filter against a spot.
+    pub fn matches_spot(&self, spot: &Spot) -> bool {
+        self.get(FilterType::Spots)
+            .is_none_or(|filter| filter.matches(spot))
+    }
+
+    /// Evaluate the `spots` filter, resolving DXCC predicates through
+    /// `lookup`.
+    pub fn matches_spot_with_dxcc(&self, spot: &Spot, lookup: &dyn DxccLookup) -> bool {
+        self.get(FilterType::Spots)
+            .is_none_or(|filter| filter.matches_with_dxcc(spot, lookup))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterAction {
+    Accept,
+    Reject,
+}
+
+impl FilterAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterAction::Accept => "accept",
+            FilterAction::Reject => "reject",
+        }
+    }
+}
+
+/// Kind of traffic a filter line applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FilterType {
+    Spots,
+    Announce,
+    Wwv,
+    Wcy,
+}
+
+impl FilterType {
+    /// Parse the part after the `/` in `accept/spots`, accepting the singular
+    /// and abbreviated forms DXSpider users type.
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        match input.to_ascii_lowercase().as_str() {
+            "spots" | "spot" => Ok(FilterType::Spots),
+            "announce" | "ann" => Ok(FilterType::Announce),
+            "wwv" => Ok(FilterType::Wwv),
+            "wcy" => Ok(FilterType::Wcy),
+            _ => Err(FilterError::UnknownType(input.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterType::Spots => "spots",
+            FilterType::Announce => "announce",
+            FilterType::Wwv => "wwv",
+            FilterType::Wcy => "wcy",
+        }
+    }
+}
+
+impl fmt::Display for FilterType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse a slot number in `0..=MAX_SLOT`.
+pub fn parse_slot(input: &str) -> Result<u8, FilterError> {
+    input
+        .parse::<u8>()
+        .ok()
+        .filter(|slot| *slot <= MAX_SLOT)
+        .ok_or_else(|| FilterError::InvalidSlot(input.to_string()))
+}
+
+/// A single `accept/<type> [slot] <expr>` or `reject/<type> [slot] <expr>`
+/// line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterRule {
+    pub action: FilterAction,
+    pub kind: FilterType,
+    pub slot: u8,
+    pub expr: Expr,
+}
+
+impl FilterRule {
+    /// Parse a full filter line such as `rej/spots 2 on 6m and not call K`.
+    ///
+    /// Actions may be abbreviated to `acc`/`rej`, and the slot defaults to
+    /// [`DEFAULT_SLOT`] when omitted.
+    pub fn parse(line: &str) -> Result<Self, FilterError> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Err(FilterError::Empty);
+        }
+
+        let (head, rest) = split_word(trimmed);
+        let Some((action, kind)) = head.split_once('/') else {
+            return Err(FilterError::UnknownAction);
+        };
+        let action = parse_action(action)?;
+        let kind = FilterType::parse(kind)?;
+
+        let (first, after) = split_word(rest);
+        let (slot, rest) = if !first.is_empty() && first.chars().all(|c| c.is_ascii_digit()) {
+            (parse_slot(first)?, after)
+        } else {
+            (DEFAULT_SLOT, rest)
+        };
+
+        let expr = Expr::parse(rest)?;
+        Ok(FilterRule {
+            action,
+            kind,
+            slot,
+            expr,
+        })
+    }
+}
+
+fn split_word(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    input.split_once(char::is_whitespace).unwrap_or((input, ""))
+}
+
+fn parse_action(input: &str) -> Result<FilterAction, FilterError> {
+    let lower = input.to_ascii_lowercase();
+    if lower.len() >= 3 && "accept".starts_with(&lower) {
+        Ok(FilterAction::Accept)
+    } else if lower.len() >= 3 && "reject".starts_with(&lower) {
+        Ok(FilterAction::Reject)
+    } else {
+        Err(FilterError::UnknownAction)
+    }
+}
+
+impl FromStr for FilterRule {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FilterRule::parse(s)
+    }
+}
+
+impl fmt::Display for FilterRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} {} {}",
+            self.action.as_str(),
+            self.kind,
+            self.slot,
+            self.expr
+        )
+    }
+}
+
+/// Boolean filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Predicate(Predicate),
+}
+
+impl Expr {
+    /// Parse an expression without the leading `accept/spots` keyword.
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(FilterError::MissingExpression);
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.parse_or()?;
+        match parser.next() {
+            None => Ok(expr),
+            Some(Token::Close) => Err(FilterError::UnbalancedParens),
+            Some(token) => Err(FilterError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    pub fn eval(&self, spot: &Spot, lookup: Option<&dyn DxccLookup>) -> bool {
+        match self {
+            Expr::And(lhs, rhs) => lhs.eval(spot, lookup) && rhs.eval(spot, lookup),
+            Expr::Or(lhs, rhs) => lhs.eval(spot, lookup) || rhs.eval(spot, lookup),
+            Expr::Not(inner) => !inner.eval(spot, lookup),
+            Expr::Predicate(predicate) => predicate.eval(spot, lookup),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Not(_) | Expr::Predicate(_) => 3,
+        }
+    }
+
+    fn fmt_child(&self, child: &Expr, rhs: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Binary operators parse left-associatively, so a right-hand child of
+        // equal precedence needs parentheses to keep its grouping.
+        let needs_parens = child.precedence() < self.precedence()
+            || (rhs && child.precedence() == self.precedence() && self.precedence() < 3);
+        if needs_parens {
+            write!(f, "({child})")
+        } else {
+            write!(f, "{child}")
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::And(lhs, rhs) => {
+                self.fmt_child(lhs, false, f)?;
+                f.write_str(" and ")?;
+                self.fmt_child(rhs, true, f)
+            }
+            Expr::Or(lhs, rhs) => {
+                self.fmt_child(lhs, false, f)?;
+                f.write_str(" or ")?;
+                self.fmt_child(rhs, true, f)
+            }
+            Expr::Not(inner) => {
+                f.write_str("not ")?;
+                self.fmt_child(inner, false, f)
+            }
+            Expr::Predicate(predicate) => write!(f, "{predicate}"),
+        }
+    }
+}
+
+/// A leaf test against a single spot attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// `on <band|range>[,...]`
+    On(Vec<FreqSelector>),
+    /// `freq <band|range>[,...]`
+    Freq(Vec<FreqSelector>),
+    /// `call <prefix>[,...]` against the DX callsign.
+    Call(Vec<String>),
+    /// `call_dxcc <entity|prefix>[,...]` against the DX callsign.
+    CallDxcc(Vec<DxccSelector>),
+    /// `by <prefix>[,...]` against the spotter.
+    By(Vec<String>),
+    /// `by_dxcc <entity|prefix>[,...]` against the spotter.
+    ByDxcc(Vec<DxccSelector>),
+    /// `info <text>`, a case-insensitive substring of the comment.
+    Info(String),
+    /// `origin <prefix>[,...]` against the originating node.
+    Origin(Vec<String>),
+    /// `mode <mode>[,...]` against the spot's mode; `data` selects every
+    /// digital mode.
+    Mode(Vec<Mode>),
+}
+
+impl Predicate {
+    fn eval(&self, spot: &Spot, lookup: Option<&dyn DxccLookup>) -> bool {
+        match self {
+            Predicate::On(selectors) | Predicate::Freq(selectors) => selectors
+                .iter()
+                .any(|selector| selector.contains(spot.freq)),
+            Predicate::Call(prefixes) => matches_prefix(prefixes, spot.dx.as_str()),
+            Predicate::CallDxcc(selectors) => matches_dxcc(selectors, &spot.dx, lookup),
+            Predicate::By(prefixes) => matches_prefix(prefixes, spot.spotter.as_str()),
+            Predicate::ByDxcc(selectors) => matches_dxcc(selectors, &spot.spotter, lookup),
+            Predicate::Info(text) => spot
+                .comment
+                .to_ascii_uppercase()
+                .contains(&text.to_ascii_uppercase()),
+            Predicate::Origin(prefixes) => spot
+                .origin
+                .as_ref()
+                .is_some_and(|origin| matches_prefix(prefixes, &origin.0.to_ascii_uppercase())),
+            Predicate::Mode(modes) => spot
+                .mode
+                .as_ref()
+                .is_some_and(|mode| modes.iter().any(|wanted| wanted.selects(mode))),
+        }
+    }
+
+    fn keyword(&self) -> &'static str {
+        match self {
+            Predicate::On(_) => "on",
+            Predicate::Freq(_) => "freq",
+            Predicate::Call(_) => "call",
+            Predicate::CallDxcc(_) => "call_dxcc",
+            Predicate::By(_) => "by",
+            Predicate::ByDxcc(_) => "by_dxcc",
+            Predicate::Info(_) => "info",
+            Predicate::Origin(_) => "origin",
+            Predicate::Mode(_) => "mode",
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.keyword())?;
+        match self {
+            Predicate::On(selectors) | Predicate::Freq(selectors) => write_list(f, selectors),
+            Predicate::Call(prefixes) | Predicate::By(prefixes) | Predicate::Origin(prefixes) => {
+                write_list(f, prefixes)
+            }
+            Predicate::CallDxcc(selectors) | Predicate::ByDxcc(selectors) => {
+                write_list(f, selectors)
+            }
+            Predicate::Info(text) if needs_quotes(text) => write!(f, "\"{text}\""),
+            Predicate::Info(text) => f.write_str(text),
+            Predicate::Mode(modes) => write_list(f, modes),
+        }
+    }
+}
+
+fn needs_quotes(text: &str) -> bool {
+    is_operator(text)
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || c == '(' || c == ')')
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            f.write_str(",")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+fn matches_prefix(prefixes: &[String], value: &str) -> bool {
+    prefixes
+        .iter()
+        .any(|prefix| value.starts_with(prefix.as_str()))
+}
+
+fn matches_dxcc(
+    selectors: &[DxccSelector],
+    call: &Callsign,
+    lookup: Option<&dyn DxccLookup>,
+) -> bool {
+    let entity = lookup.and_then(|lookup| lookup.dxcc(call.as_str()));
+    selectors.iter().any(|selector| match (selector, lookup) {
+        (DxccSelector::Entity(number), _) => entity == Some(*number),
+        (DxccSelector::Prefix(prefix), Some(lookup)) => {
+            entity.is_some() && lookup.dxcc(prefix) == entity
+        }
+        (DxccSelector::Prefix(prefix), None) => call.as_str().starts_with(prefix.as_str()),
+    })
+}
+
+/// Frequency argument accepted by `on` and `freq`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreqSelector {
+    Band(Band),
+    Group(BandGroup),
+    /// Inclusive range, written in kHz as `low/high`.
+    Range(FrequencyHz, FrequencyHz),
+}
+
+impl FreqSelector {
+    pub fn contains(&self, freq: FrequencyHz) -> bool {
+        match self {
+            FreqSelector::Band(band) => band.contains(freq),
+            FreqSelector::Group(group) => group.contains(freq),
+            FreqSelector::Range(low, high) => freq >= *low && freq <= *high,
+        }
+    }
+
+    /// Parse a band, band group or `low/high` kHz range; `keyword` names the
+    /// argument in errors.
+    pub fn parse(keyword: &'static str, input: &str) -> Result<Self, FilterError> {
+        let invalid = || FilterError::InvalidArgument {
+            keyword,
+            value: input.to_string(),
+        };
+
+        if let Some((low, high)) = input.split_once('/') {
+            let low = FrequencyHz::from_khz_str(low).map_err(|_| invalid())?;
+            let high = FrequencyHz::from_khz_str(high).map_err(|_| invalid())?;
+            if low > high {
+                return Err(invalid());
+            }
+            return Ok(FreqSelector::Range(low, high));
+        }
+
+        if let Some(group) = BandGroup::from_label(input) {
+            return Ok(FreqSelector::Group(group));
+        }
+
+        Band::from_label(input)
+            .map(FreqSelector::Band)
+            .ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for FreqSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreqSelector::Band(band) => write!(f, "{band}"),
+            FreqSelector::Group(group) => f.write_str(group.label()),
+            FreqSelector::Range(low, high) => write!(f, "{low}/{high}"),
+        }
+    }
+}
+
+/// Coarse band groupings understood by DXSpider filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BandGroup {
+    Hf,
+    Vhf,
+    Uhf,
+}
+
+impl BandGroup {
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "hf" => Some(BandGroup::Hf),
+            "vhf" => Some(BandGroup::Vhf),
+            "uhf" => Some(BandGroup::Uhf),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BandGroup::Hf => "hf",
+            BandGroup::Vhf => "vhf",
+            BandGroup::Uhf => "uhf",
+        }
+    }
+
+    pub fn contains(&self, freq: FrequencyHz) -> bool {
+        let (low, high) = match self {
+            BandGroup::Hf => (1_800_000, 30_000_000),
+            BandGroup::Vhf => (30_000_000, 300_000_000),
+            BandGroup::Uhf => (300_000_000, 3_000_000_000),
+        };
+        freq.0 >= low && freq.0 < high
+    }
+}
+
+/// Argument to `call_dxcc`/`by_dxcc`: either an entity number or a prefix
+/// whose entity is looked up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DxccSelector {
+    Entity(u16),
+    Prefix(String),
+}
+
+impl fmt::Display for DxccSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DxccSelector::Entity(number) => write!(f, "{number}"),
+            DxccSelector::Prefix(prefix) => f.write_str(prefix),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    Quoted(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::Word(word) => f.write_str(word),
+            Token::Quoted(text) => write!(f, "\"{text}\""),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(FilterError::UnexpectedToken(format!("\"{text}"))),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            c => {
+                let mut word = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == '(' || next == ')' || next == '"' {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.parse_unary()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            let rhs = self.parse_unary()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        if self.depth == MAX_NESTING {
+            return Err(FilterError::TooDeep(MAX_NESTING));
+        }
+        self.depth += 1;
+        let expr = self.parse_nested();
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_nested(&mut self) -> Result<Expr, FilterError> {
+        match self.next() {
+            None => Err(FilterError::MissingExpression),
+            Some(Token::Open) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(FilterError::UnbalancedParens),
+                }
+            }
+            Some(Token::Close) => Err(FilterError::UnbalancedParens),
+            Some(Token::Quoted(text)) => Err(FilterError::UnexpectedToken(format!("\"{text}\""))),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("not") => {
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Word(word)) => self.parse_predicate(&word).map(Expr::Predicate),
+        }
+    }
+
+    fn parse_predicate(&mut self, keyword: &str) -> Result<Predicate, FilterError> {
+        let keyword = match keyword.to_ascii_lowercase().as_str() {
+            "on" => "on",
+            "freq" => "freq",
+            "call" => "call",
+            "call_dxcc" => "call_dxcc",
+            "by" => "by",
+            "by_dxcc" => "by_dxcc",
+            "info" => "info",
+            "origin" => "origin",
+            "mode" => "mode",
+            _ => return Err(FilterError::UnknownKeyword(keyword.to_string())),
+        };
+
+        let argument = match self.next() {
+            Some(Token::Word(word)) if !is_operator(&word) => word,
+            Some(Token::Quoted(text)) if keyword == "info" => text,
+            _ => return Err(FilterError::MissingArgument(keyword)),
+        };
+
+        let predicate = match keyword {
+            "on" => Predicate::On(parse_list(keyword, &argument, FreqSelector::parse)?),
+            "freq" => Predicate::Freq(parse_list(keyword, &argument, FreqSelector::parse)?),
+            "call" => Predicate::Call(parse_list(keyword, &argument, parse_prefix)?),
+            "call_dxcc" => Predicate::CallDxcc(parse_list(keyword, &argument, parse_dxcc)?),
+            "by" => Predicate::By(parse_list(keyword, &argument, parse_prefix)?),
+            "by_dxcc" => Predicate::ByDxcc(parse_list(keyword, &argument, parse_dxcc)?),
+            "origin" => Predicate::Origin(parse_list(keyword, &argument, parse_prefix)?),
+            "mode" => Predicate::Mode(parse_list(keyword, &argument, parse_mode)?),
+            _ => Predicate::Info(argument),
+        };
+        Ok(predicate)
+    }
+}
+
+fn is_operator(word: &str) -> bool {
+    ["and", "or", "not"]
+        .iter()
+        .any(|op| word.eq_ignore_ascii_case(op))
+}
+
+fn parse_list<T>(
+    keyword: &'static str,
+    argument: &str,
+    parse: fn(&'static str, &str) -> Result<T, FilterError>,
+) -> Result<Vec<T>, FilterError> {
+    argument
+        .split(',')
+        .map(|item| {
+            let item = item.trim();
+            if item.is_empty() {
+                return Err(FilterError::InvalidArgument {
+                    keyword,
+                    value: argument.to_string(),
+                });
+            }
+            parse(keyword, item)
+        })
+        .collect()
+}
+
+fn parse_prefix(keyword: &'static str, input: &str) -> Result<String, FilterError> {
+    let prefix = input.trim_end_matches('*').to_ascii_uppercase();
+    let valid = !prefix.is_empty()
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '/' || c == '-');
+    if !valid {
+        return Err(FilterError::InvalidArgument {
+            keyword,
+            value: input.to_string(),
+        });
+    }
+    Ok(prefix)
+}
+
+fn parse_mode(keyword: &'static str, input: &str) -> Result<Mode, FilterError> {
+    input.parse().map_err(|_| FilterError::InvalidArgument {
+        keyword,
+        value: input.to_string(),
+    })
+}
+
+fn parse_dxcc(keyword: &'static str, input: &str) -> Result<DxccSelector, FilterError> {
+    if input.chars().all(|c| c.is_ascii_digit()) {
+        return input
+            .parse()
+            .map(DxccSelector::Entity)
+            .map_err(|_| FilterError::InvalidArgument {
+                keyword,
+                value: input.to_string(),
+            });
+    }
+    parse_prefix(keyword, input).map(DxccSelector::Prefix)
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use super::*;
+    use dxcluster_types::{NodeId, SpotId};
+
+    fn spot(freq_hz: u64, dx: &str, spotter: &str, comment: &str) -> Spot {
+        Spot::new_local(
+            SpotId([0u8; 32]),
+            time::OffsetDateTime::UNIX_EPOCH,
+            FrequencyHz(freq_hz),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(spotter).unwrap(),
+            comment,
+            Some(NodeId("GB7DJK".to_string())),
+        )
+    }
+
+    fn filter(lines: &[&str]) -> Filter {
+        Filter::new(
+            lines
+                .iter()
+                .map(|line| FilterRule::parse(line).expect("filter line parses"))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn empty_filter_accepts_everything() {
+        assert!(Filter::default().matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+    }
+
+    #[test]
+    fn accept_on_band() {
+        let filter = filter(&["accept/spots on 20m"]);
+        assert!(filter.matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(7_074_000, "K1ABC", "G4XYZ", "")));
+    }
+
+    #[test]
+    fn mode_predicate_uses_spot_mode() {
+        let wanted = filter(&["accept/spots mode cw,rtty"]);
+        assert!(wanted.matches(&spot(14_025_000, "K1ABC", "G4XYZ", "")));
+        assert!(wanted.matches(&spot(14_085_000, "K1ABC", "G4XYZ", "RTTY")));
+        assert!(!wanted.matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+        assert_eq!(wanted.rules()[0].to_string(), "accept/spots 1 mode CW,RTTY");
+
+        let digital = filter(&["reject/spots mode data"]);
+        assert!(!digital.matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+        assert!(!digital.matches(&spot(7_025_000, "K1ABC", "G4XYZ", "psk31")));
+        assert!(digital.matches(&spot(14_250_000, "K1ABC", "G4XYZ", "")));
+
+        // A mode reported with the spot wins over the frequency.
+        let mut skimmed = spot(14_074_000, "K1ABC", "G4XYZ", "");
+        skimmed.mode = Some(Mode::Cw);
+        assert!(wanted.matches(&skimmed));
+        assert!(digital.matches(&skimmed));
+    }
+
+    #[test]
+    fn accept_on_new_bands() {
+        let filter = filter(&["accept/spots on 4m or on 23cm"]);
+        assert!(filter.matches(&spot(70_200_000, "K1ABC", "G4XYZ", "")));
+        assert!(filter.matches(&spot(1_296_200_000, "K1ABC", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(50_313_000, "K1ABC", "G4XYZ", "")));
+    }
+
+    #[test]
+    fn reject_wins_over_accept() {
+        let filter = filter(&["acc/spots on hf", "rej/spot call K1"]);
+        assert!(!filter.matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+        assert!(filter.matches(&spot(14_074_000, "W1AW", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(144_300_000, "W1AW", "G4XYZ", "")));
+    }
+
+    #[test]
+    fn boolean_operators_and_parentheses() {
+        let filter = filter(&["accept/spots freq 14000/14100 and not (by G or info ft8)"]);
+        assert!(filter.matches(&spot(14_025_000, "K1ABC", "DL1ABC", "cq")));
+        assert!(!filter.matches(&spot(14_025_000, "K1ABC", "G4XYZ", "cq")));
+        assert!(!filter.matches(&spot(14_074_000, "K1ABC", "DL1ABC", "FT8 -10dB")));
+        assert!(!filter.matches(&spot(14_200_000, "K1ABC", "DL1ABC", "cq")));
+    }
+
+    #[test]
+    fn origin_and_prefix_lists() {
+        let filter = filter(&["accept/spots origin GB7 and call VP8,VK0"]);
+        assert!(filter.matches(&spot(14_074_000, "VP8PJ", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(14_074_000, "K1ABC", "G4XYZ", "")));
+    }
+
+    #[test]
+    fn dxcc_predicates_use_lookup() {
+        struct Fixed;
+        impl DxccLookup for Fixed {
+            fn dxcc(&self, call: &str) -> Option<u16> {
+                match call.chars().next() {
+                    Some('G') | Some('M') => Some(223),
+                    Some('K') | Some('W') => Some(291),
+                    _ => None,
+                }
+            }
+        }
+
+        let filter = filter(&["accept/spots call_dxcc 291 or by_dxcc G"]);
+        assert!(filter.matches_with_dxcc(&spot(14_074_000, "W1AW", "DL1ABC", ""), &Fixed));
+        assert!(filter.matches_with_dxcc(&spot(14_074_000, "DL1ABC", "M0ABC", ""), &Fixed));
+        assert!(!filter.matches_with_dxcc(&spot(14_074_000, "DL1ABC", "F5ABC", ""), &Fixed));
+        assert!(!filter.matches(&spot(14_074_000, "W1AW", "DL1ABC", "")));
+    }
+
+    #[test]
+    fn renders_back_to_same_syntax() {
+        for line in [
+            "accept/spots 1 on 20m,40m and not call K1",
+            "reject/spots 0 (by G or by M) and info \"dx pedition\"",
+            "accept/spots 9 freq 14000/14100 or origin GB7DJK",
+            "reject/spots 1 not (on hf or on vhf)",
+            "accept/announce 2 call K and (by G and by M1)",
+        ] {
+            let rule = FilterRule::parse(line).expect("parses");
+            assert_eq!(rule.to_string(), line);
+            assert_eq!(FilterRule::parse(&rule.to_string()).unwrap(), rule);
+        }
+    }
+
+    #[test]
+    fn slots_evaluate_in_order() {
+        let filter = filter(&[
+            "accept/spots 2 on 20m",
+            "reject/spots 1 call K1",
+            "accept/spots 0 by G",
+        ]);
+        let numbers: Vec<_> = filter.rules().iter().map(|rule| rule.slot).collect();
+        assert_eq!(numbers, vec![0, 1, 2]);
+
+        // Slot 0 accepts before slot 1 gets a chance to reject.
+        assert!(filter.matches(&spot(7_025_000, "K1ABC", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(14_025_000, "K1ABC", "DL1ABC", "")));
+        assert!(filter.matches(&spot(14_025_000, "W1AW", "DL1ABC", "")));
+        assert!(!filter.matches(&spot(7_025_000, "W1AW", "DL1ABC", "")));
+    }
+
+    #[test]
+    fn lines_replace_and_clear_by_slot() {
+        let mut filter = filter(&["accept/spots on 20m", "reject/spots 1 call K"]);
+        filter.push(FilterRule::parse("accept/spots 1 on 40m").unwrap());
+        assert_eq!(filter.rules().len(), 2);
+        assert!(filter.matches(&spot(7_025_000, "W1AW", "G4XYZ", "")));
+        assert!(!filter.matches(&spot(14_025_000, "W1AW", "G4XYZ", "")));
+
+        assert!(filter.clear_slot(1));
+        assert!(!filter.clear_slot(1));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn filter_set_keeps_types_apart() {
+        let mut set = FilterSet::default();
+        set.push(FilterRule::parse("rej/announce 2 by G").unwrap());
+        assert!(set.matches_spot(&spot(14_025_000, "W1AW", "G4XYZ", "")));
+
+        set.push(FilterRule::parse("rej/spots 3 by G").unwrap());
+        assert!(!set.matches_spot(&spot(14_025_000, "W1AW", "G4XYZ", "")));
+        assert_eq!(set.rules().count(), 2);
+
+        assert!(set.clear_slot(FilterType::Spots, 3));
+        assert!(set.matches_spot(&spot(14_025_000, "W1AW", "G4XYZ", "")));
+        assert!(set.get(FilterType::Spots).is_none());
+        assert!(set.clear(FilterType::Announce));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            FilterRule::parse("accept/spots 10 on 20m"),
+            Err(FilterError::InvalidSlot("10".to_string()))
+        );
+        assert_eq!(
+            FilterRule::parse("accept/qsl on 20m"),
+            Err(FilterError::UnknownType("qsl".to_string()))
+        );
+        assert_eq!(FilterRule::parse(""), Err(FilterError::Empty));
+        assert_eq!(
+            FilterRule::parse("set/spots on 20m"),
+            Err(FilterError::UnknownAction)
+        );
+        assert_eq!(
+            FilterRule::parse("accept/spots"),
+            Err(FilterError::MissingExpression)
+        );
+        assert_eq!(
+            FilterRule::parse("accept/spots (on 20m"),
+            Err(FilterError::UnbalancedParens)
+        );
+        assert_eq!(
+            FilterRule::parse("accept/spots on 20m)"),
+            Err(FilterError::UnbalancedParens)
+        );
+        let deep = format!("accept/spots {}on 20m{}", "(".repeat(100), ")".repeat(100));
+        assert_eq!(
+            FilterRule::parse(&deep),
+            Err(FilterError::TooDeep(MAX_NESTING))
+        );
+        let deep = format!("accept/spots {}on 20m", "not ".repeat(100_000));
+        assert_eq!(
+            FilterRule::parse(&deep),
+            Err(FilterError::TooDeep(MAX_NESTING))
+        );
+        let nested = format!("accept/spots {}on 20m{}", "(".repeat(10), ")".repeat(10));
+        assert!(FilterRule::parse(&nested).is_ok());
+        assert_eq!(
+            FilterRule::parse("accept/spots zone 14"),
+            Err(FilterError::UnknownKeyword("zone".to_string()))
+        );
+        assert_eq!(
+            FilterRule::parse("accept/spots on and call K"),
+            Err(FilterError::MissingArgument("on"))
+        );
+        assert!(matches!(
+            FilterRule::parse("accept/spots on 11m"),
+            Err(FilterError::InvalidArgument { keyword: "on", .. })
+        ));
+        assert!(matches!(
+            FilterRule::parse("accept/spots freq 14100/14000"),
+            Err(FilterError::InvalidArgument {
+                keyword: "freq",
+                ..
+            })
+        ));
     }
 }
@@ -1,19 +1,27 @@
 //! Domain model types and deterministic business logic.
 
+pub mod announce;
 pub mod cache;
 pub mod dedupe;
+pub mod dxcc;
 pub mod error;
 pub mod filter;
 pub mod policy;
+pub mod query;
 #[cfg(feature = "rate_limit")]
 pub mod rate_limit;
+pub mod skimmer;
 pub mod spot;
 
+pub use announce::{AnnounceCache, AnnounceScope, Announcement};
 pub use cache::SpotCache;
-pub use dedupe::{DedupeResult, DedupeTable};
-pub use error::PolicyReject;
-pub use filter::Filter;
+pub use dedupe::{DedupeResult, DedupeStats, DedupeTable, FuzzyWindow};
+pub use dxcc::{Continent, CtyTable, DxccEntity, DxccInfo, SharedCtyTable};
+pub use error::{CtyError, FilterError, PolicyReject};
+pub use filter::{Filter, FilterAction, FilterRule, FilterSet, FilterType};
 pub use policy::Policy;
+pub use query::{CallMatch, DayRange, DxQuery};
 #[cfg(feature = "rate_limit")]
-pub use rate_limit::RateLimiter;
-pub use spot::Spot;
+pub use rate_limit::{Budget, RateClass, RateKey, RateLimited, RateLimiter, RateLimits};
+pub use skimmer::{SkimmerAggregator, SkimmerRules};
+pub use spot::{Spot, SpotKind};
@@ -1,11 +1,321 @@
+//! Spot acceptance rules applied before a spot enters the node.
+//!
+//! [`Policy`] is plain configuration; [`Policy::accept`] checks a spot against
+//! each rule in a fixed order and reports the first violation as a
+//! [`PolicyReject`], whose [`code`](PolicyReject::code) is stable for logs
+//! and tooling.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use dxcluster_types::{Band, Callsign, NodeId, Region};
+
 use crate::error::PolicyReject;
-use crate::spot::Spot;
+use crate::spot::{Spot, Timestamp, unix_seconds};
 
-#[derive(Debug, Clone, Default)]
-pub struct Policy;
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// Reject spots whose frequency is outside every known [`Band`].
+    pub require_band: bool,
+    /// Band plan checked by `require_band`; `None` accepts an allocation in
+    /// any region. Nodes set this from their configured region.
+    pub region: Option<Region>,
+    /// How far a spot's timestamp may be ahead of the current time.
+    pub max_future: Option<Duration>,
+    /// How old a spot's timestamp may be.
+    pub max_age: Option<Duration>,
+    /// Spotters whose spots are refused, with any prefix, suffix or SSID.
+    pub banned_spotters: HashSet<Callsign>,
+    /// Stations that may not be spotted, with any prefix, suffix or SSID.
+    pub banned_dx: HashSet<Callsign>,
+    /// Nodes whose spots are refused, matched against the spot's origin.
+    pub banned_nodes: HashSet<NodeId>,
+    /// Maximum comment length in characters.
+    pub max_comment_len: Option<usize>,
+    /// Words refused anywhere in a comment, compared case-insensitively
+    /// against whole words.
+    pub bad_words: Vec<String>,
+    /// Allow spotters to spot themselves (ignoring `/P`-style suffixes and
+    /// SSIDs).
+    pub allow_self_spots: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            require_band: true,
+            region: None,
+            max_future: Some(Duration::from_secs(5 * 60)),
+            max_age: Some(Duration::from_secs(60 * 60)),
+            banned_spotters: HashSet::new(),
+            banned_dx: HashSet::new(),
+            banned_nodes: HashSet::new(),
+            max_comment_len: Some(80),
+            bad_words: Vec::new(),
+            allow_self_spots: false,
+        }
+    }
+}
 
 impl Policy {
-    pub fn accept(&self, _spot: &Spot) -> Result<(), PolicyReject> {
+    /// A policy with every rule disabled.
+    pub fn permissive() -> Self {
+        Self {
+            require_band: false,
+            max_future: None,
+            max_age: None,
+            max_comment_len: None,
+            allow_self_spots: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn accept(&self, spot: &Spot, now: Timestamp) -> Result<(), PolicyReject> {
+        self.check(spot, now, self.region)
+    }
+
+    /// Like [`Policy::accept`], but checking the band plan of `region` when
+    /// the policy does not name one itself.
+    pub fn accept_in(
+        &self,
+        spot: &Spot,
+        now: Timestamp,
+        region: Region,
+    ) -> Result<(), PolicyReject> {
+        self.check(spot, now, self.region.or(Some(region)))
+    }
+
+    fn check(
+        &self,
+        spot: &Spot,
+        now: Timestamp,
+        region: Option<Region>,
+    ) -> Result<(), PolicyReject> {
+        let band = match region {
+            Some(region) => Band::from_frequency_in(spot.freq, region),
+            None => Band::from_frequency(spot.freq),
+        };
+        if self.require_band && band.is_none() {
+            return Err(PolicyReject::OutOfBand {
+                freq_hz: spot.freq.0,
+            });
+        }
+
+        let offset = unix_seconds(&spot.ts) - unix_seconds(&now);
+        if let Some(max_future) = self.max_future
+            && offset > max_future.as_secs() as i64
+        {
+            return Err(PolicyReject::FutureTimestamp { ahead_secs: offset });
+        }
+        if let Some(max_age) = self.max_age
+            && -offset > max_age.as_secs() as i64
+        {
+            return Err(PolicyReject::StaleTimestamp { age_secs: -offset });
+        }
+
+        if is_banned(&self.banned_spotters, &spot.spotter) {
+            return Err(PolicyReject::BannedSpotter(spot.spotter.to_string()));
+        }
+        if is_banned(&self.banned_dx, &spot.dx) {
+            return Err(PolicyReject::BannedDx(spot.dx.to_string()));
+        }
+        if let Some(origin) = &spot.origin
+            && self.banned_nodes.contains(origin)
+        {
+            return Err(PolicyReject::BannedNode(origin.0.clone()));
+        }
+
+        if let Some(max) = self.max_comment_len {
+            let len = spot.comment.chars().count();
+            if len > max {
+                return Err(PolicyReject::CommentTooLong { len, max });
+            }
+        }
+        if let Some(word) = spot
+            .comment
+            .split(|c: char| !c.is_alphanumeric())
+            .find_map(|word| {
+                self.bad_words
+                    .iter()
+                    .find(|bad| !word.is_empty() && bad.eq_ignore_ascii_case(word))
+            })
+        {
+            return Err(PolicyReject::BadWord(word.clone()));
+        }
+
+        if !self.allow_self_spots && spot.dx.base_call() == spot.spotter.base_call() {
+            return Err(PolicyReject::SelfSpot(spot.spotter.to_string()));
+        }
+
         Ok(())
     }
 }
+
+/// Whether `call` or its base call is in `banned`.
+fn is_banned(banned: &HashSet<Callsign>, call: &Callsign) -> bool {
+    banned.contains(call) || banned.contains(&call.base_call())
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use super::*;
+    use dxcluster_types::{FrequencyHz, SpotId};
+    use time::OffsetDateTime;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn now() -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(NOW).unwrap()
+    }
+
+    fn spot(freq_hz: u64, dx: &str, spotter: &str, comment: &str) -> Spot {
+        Spot::new_local(
+            SpotId([0u8; 32]),
+            now(),
+            FrequencyHz(freq_hz),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(spotter).unwrap(),
+            comment,
+            Some(NodeId("GB7DJK".to_string())),
+        )
+    }
+
+    fn code(policy: &Policy, spot: &Spot) -> Option<&'static str> {
+        policy.accept(spot, now()).err().map(|reject| reject.code())
+    }
+
+    #[test]
+    fn accepts_ordinary_spot() {
+        let policy = Policy::default();
+        assert_eq!(
+            policy.accept(&spot(14_025_000, "K1ABC", "G4XYZ", "tnx"), now()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_band_frequency() {
+        let policy = Policy::default();
+        assert_eq!(
+            code(&policy, &spot(12_345_000, "K1ABC", "G4XYZ", "")),
+            Some("out_of_band")
+        );
+        let europe = Policy {
+            region: Some(Region::R1),
+            ..Policy::default()
+        };
+        assert_eq!(code(&europe, &spot(70_200_000, "K1ABC", "G4XYZ", "")), None);
+        assert_eq!(
+            code(&europe, &spot(7_250_000, "K1ABC", "G4XYZ", "")),
+            Some("out_of_band")
+        );
+        assert_eq!(code(&policy, &spot(7_250_000, "K1ABC", "G4XYZ", "")), None);
+        let phone = spot(7_250_000, "K1ABC", "G4XYZ", "");
+        assert!(policy.accept_in(&phone, now(), Region::R1).is_err());
+        let americas = Policy {
+            region: Some(Region::R2),
+            ..Policy::default()
+        };
+        assert_eq!(americas.accept_in(&phone, now(), Region::R1), Ok(()));
+        assert_eq!(
+            code(
+                &Policy::permissive(),
+                &spot(12_345_000, "K1ABC", "G4XYZ", "")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_future_and_stale_timestamps() {
+        let policy = Policy::default();
+        let mut future = spot(14_025_000, "K1ABC", "G4XYZ", "");
+        future.ts = OffsetDateTime::from_unix_timestamp(NOW + 600).unwrap();
+        assert_eq!(
+            policy.accept(&future, now()),
+            Err(PolicyReject::FutureTimestamp { ahead_secs: 600 })
+        );
+
+        let mut stale = spot(14_025_000, "K1ABC", "G4XYZ", "");
+        stale.ts = OffsetDateTime::from_unix_timestamp(NOW - 7200).unwrap();
+        assert_eq!(code(&policy, &stale), Some("stale_timestamp"));
+
+        stale.ts = OffsetDateTime::from_unix_timestamp(NOW - 600).unwrap();
+        assert_eq!(code(&policy, &stale), None);
+    }
+
+    #[test]
+    fn rejects_banned_calls_and_nodes() {
+        let mut policy = Policy::default();
+        policy
+            .banned_spotters
+            .insert(Callsign::parse_loose("G4BAD").unwrap());
+        policy
+            .banned_dx
+            .insert(Callsign::parse_loose("K1BAD").unwrap());
+        policy.banned_nodes.insert(NodeId("GB7DJK".to_string()));
+
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1ABC", "G4BAD", "")),
+            Some("banned_spotter")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1ABC", "G4BAD/M", "")),
+            Some("banned_spotter")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1BAD", "G4XYZ", "")),
+            Some("banned_dx")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "VP2E/K1BAD/P", "G4XYZ", "")),
+            Some("banned_dx")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1ABC", "G4XYZ", "")),
+            Some("banned_node")
+        );
+    }
+
+    #[test]
+    fn rejects_long_comments_and_bad_words() {
+        let policy = Policy {
+            max_comment_len: Some(12),
+            bad_words: vec!["lid".to_string()],
+            ..Policy::default()
+        };
+        assert_eq!(
+            policy.accept(
+                &spot(14_025_000, "K1ABC", "G4XYZ", "a very long comment"),
+                now()
+            ),
+            Err(PolicyReject::CommentTooLong { len: 19, max: 12 })
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1ABC", "G4XYZ", "what a LID!")),
+            Some("bad_word")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "K1ABC", "G4XYZ", "solid sig")),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_self_spots() {
+        let policy = Policy::default();
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "G4XYZ/P", "G4XYZ", "")),
+            Some("self_spot")
+        );
+        assert_eq!(
+            code(&policy, &spot(14_025_000, "EA8/G4XYZ", "G4XYZ/M", "")),
+            Some("self_spot")
+        );
+        let policy = Policy {
+            allow_self_spots: true,
+            ..Policy::default()
+        };
+        assert_eq!(code(&policy, &spot(14_025_000, "G4XYZ", "G4XYZ", "")), None);
+    }
+}
@@ -0,0 +1,316 @@
+//! Structured `sh/dx` queries and their evaluation against a [`SpotCache`].
+
+use std::fmt;
+
+use dxcluster_types::Callsign;
+
+use crate::cache::SpotCache;
+use crate::filter::FreqSelector;
+use crate::spot::{Spot, Timestamp, unix_seconds};
+
+/// Spots returned when a query gives no count.
+pub const DEFAULT_DX_COUNT: usize = 10;
+/// Most spots a query returns, as in DXSpider; larger counts are clamped.
+pub const MAX_DX_COUNT: usize = 100;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// How a callsign argument selects calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallMatch {
+    /// `K1ABC`: calls starting with the text.
+    Prefix(String),
+    /// `*ABC`: calls containing the text anywhere.
+    Anywhere(String),
+    /// `K1ABC` with `exact`: that call only.
+    Exact(String),
+}
+
+impl CallMatch {
+    pub fn matches(&self, call: &Callsign) -> bool {
+        let call = call.as_str();
+        match self {
+            CallMatch::Prefix(prefix) => call.starts_with(prefix.as_str()),
+            CallMatch::Anywhere(text) => call.contains(text.as_str()),
+            CallMatch::Exact(exact) => call == exact,
+        }
+    }
+
+    /// Turn a prefix into an exact match; other forms are unchanged.
+    pub fn into_exact(self) -> Self {
+        match self {
+            CallMatch::Prefix(call) => CallMatch::Exact(call),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for CallMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallMatch::Prefix(call) | CallMatch::Exact(call) => f.write_str(call),
+            CallMatch::Anywhere(text) => write!(f, "*{text}"),
+        }
+    }
+}
+
+/// Spots from between `from` and `to` days ago; `day 3` is `0..3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayRange {
+    pub from: u32,
+    pub to: u32,
+}
+
+impl DayRange {
+    fn contains(&self, age_secs: i64) -> bool {
+        age_secs >= i64::from(self.from) * SECS_PER_DAY
+            && age_secs < i64::from(self.to) * SECS_PER_DAY
+    }
+}
+
+impl fmt::Display for DayRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.from == 0 {
+            write!(f, "{}", self.to)
+        } else {
+            write!(f, "{}-{}", self.from, self.to)
+        }
+    }
+}
+
+/// Arguments of a `sh/dx` command. Every criterion that is set must match.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DxQuery {
+    /// Spots to return; [`DEFAULT_DX_COUNT`] when unset and at most
+    /// [`MAX_DX_COUNT`].
+    pub count: Option<usize>,
+    /// DX callsign.
+    pub dx: Option<CallMatch>,
+    /// Spotter callsign, given with `by`.
+    pub spotter: Option<CallMatch>,
+    /// Band, band group or frequency range, given with `on` or bare as
+    /// `low/high`.
+    pub freq: Option<FreqSelector>,
+    pub days: Option<DayRange>,
+    /// Text the comment must contain, ignoring case.
+    pub info: Option<String>,
+}
+
+impl DxQuery {
+    pub fn limit(&self) -> usize {
+        self.count.unwrap_or(DEFAULT_DX_COUNT).min(MAX_DX_COUNT)
+    }
+
+    /// Whether `spot` meets every criterion, with ages measured from `now`.
+    pub fn matches(&self, spot: &Spot, now: Timestamp) -> bool {
+        self.dx.as_ref().is_none_or(|dx| dx.matches(&spot.dx))
+            && self
+                .spotter
+                .as_ref()
+                .is_none_or(|spotter| spotter.matches(&spot.spotter))
+            && self.freq.is_none_or(|freq| freq.contains(spot.freq))
+            && self
+                .days
+                .is_none_or(|days| days.contains(unix_seconds(&now) - unix_seconds(&spot.ts)))
+            && self.info.as_ref().is_none_or(|info| {
+                spot.comment
+                    .to_ascii_uppercase()
+                    .contains(&info.to_ascii_uppercase())
+            })
+    }
+}
+
+impl fmt::Display for DxQuery {
+    /// Arguments in canonical order, e.g. `25 on 20m K1ABC by G4ABC exact`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut args = Vec::new();
+        if let Some(count) = self.count {
+            args.push(count.to_string());
+        }
+        if let Some(freq) = &self.freq {
+            args.push(format!("on {freq}"));
+        }
+        if let Some(dx) = &self.dx {
+            args.push(dx.to_string());
+        }
+        if let Some(spotter) = &self.spotter {
+            args.push(format!("by {spotter}"));
+        }
+        if let Some(days) = &self.days {
+            args.push(format!("day {days}"));
+        }
+        if let Some(info) = &self.info {
+            args.push(format!("info {info}"));
+        }
+        let exact = [&self.dx, &self.spotter]
+            .into_iter()
+            .flatten()
+            .any(|call| matches!(call, CallMatch::Exact(_)));
+        if exact {
+            args.push("exact".to_string());
+        }
+        f.write_str(&args.join(" "))
+    }
+}
+
+impl SpotCache {
+    /// Spots matching `query`, newest first. Take [`DxQuery::limit`] of them
+    /// after any further filtering. Exact calls and bands are answered from
+    /// the cache's indexes rather than a full scan.
+    pub fn query<'a>(
+        &'a self,
+        query: &'a DxQuery,
+        now: Timestamp,
+    ) -> impl Iterator<Item = &'a Spot> + 'a {
+        let candidates: Box<dyn Iterator<Item = &'a Spot> + 'a> = match query {
+            DxQuery {
+                dx: Some(CallMatch::Exact(call)),
+                ..
+            } => indexed(call, |call| self.by_dx(call)),
+            DxQuery {
+                spotter: Some(CallMatch::Exact(call)),
+                ..
+            } => indexed(call, |call| self.by_spotter(call)),
+            DxQuery {
+                freq: Some(FreqSelector::Band(band)),
+                ..
+            } => Box::new(self.on_band(*band)),
+            _ => Box::new(self.recent(self.len())),
+        };
+        candidates.filter(move |spot| query.matches(spot, now))
+    }
+}
+
+/// Spots from a callsign index, or none when `call` is not a callsign.
+fn indexed<'a, I>(
+    call: &str,
+    lookup: impl FnOnce(&Callsign) -> I,
+) -> Box<dyn Iterator<Item = &'a Spot> + 'a>
+where
+    I: Iterator<Item = &'a Spot> + 'a,
+{
+    match Callsign::parse_loose(call) {
+        Ok(call) => Box::new(lookup(&call)),
+        Err(_) => Box::new(std::iter::empty()),
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use dxcluster_types::{Band, FrequencyHz, SpotId};
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn spot(dx: &str, spotter: &str, khz: u64, age_secs: i64, comment: &str) -> Spot {
+        Spot::new_local(
+            SpotId::hash_components(&[dx.as_bytes(), &age_secs.to_be_bytes()]),
+            OffsetDateTime::from_unix_timestamp(NOW - age_secs).unwrap(),
+            FrequencyHz(khz * 1000),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(spotter).unwrap(),
+            comment,
+            None,
+        )
+    }
+
+    fn cache() -> SpotCache {
+        let mut cache = SpotCache::new(100);
+        // Oldest first.
+        cache.push(spot("K1ABC", "G4ABC", 14_025, 4 * SECS_PER_DAY, "CW"));
+        cache.push(spot("K1ABC/P", "DL1XX", 7_074, 2 * SECS_PER_DAY, "FT8 -10"));
+        cache.push(spot("VK2ABC", "G4ABC", 14_200, 3_600, "SSB"));
+        cache.push(spot("K1ABC", "DL1XX", 21_074, 600, "ft8 loud"));
+        cache.push(spot("JA1ZZ", "G4ABCD", 14_074, 60, ""));
+        cache
+    }
+
+    fn run(query: DxQuery) -> Vec<String> {
+        let cache = cache();
+        let now = OffsetDateTime::from_unix_timestamp(NOW).unwrap();
+        cache
+            .query(&query, now)
+            .take(query.limit())
+            .map(|spot| format!("{}@{}", spot.dx, spot.freq))
+            .collect()
+    }
+
+    #[test]
+    fn matches_calls_by_prefix_anywhere_or_exactly() {
+        let prefix = DxQuery {
+            dx: Some(CallMatch::Prefix("K1ABC".into())),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(prefix), ["K1ABC@21074", "K1ABC/P@7074", "K1ABC@14025"]);
+
+        let exact = DxQuery {
+            dx: Some(CallMatch::Exact("K1ABC".into())),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(exact), ["K1ABC@21074", "K1ABC@14025"]);
+
+        let anywhere = DxQuery {
+            dx: Some(CallMatch::Anywhere("ABC".into())),
+            count: Some(2),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(anywhere), ["K1ABC@21074", "VK2ABC@14200"]);
+
+        let by = DxQuery {
+            spotter: Some(CallMatch::Exact("G4ABC".into())),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(by), ["VK2ABC@14200", "K1ABC@14025"]);
+    }
+
+    #[test]
+    fn combines_band_days_and_info() {
+        let on_20m = DxQuery {
+            freq: Some(FreqSelector::Band(Band::Meter20)),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(on_20m), ["JA1ZZ@14074", "VK2ABC@14200", "K1ABC@14025"]);
+
+        let recent_20m = DxQuery {
+            freq: Some(FreqSelector::Band(Band::Meter20)),
+            days: Some(DayRange { from: 0, to: 1 }),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(recent_20m), ["JA1ZZ@14074", "VK2ABC@14200"]);
+
+        let older = DxQuery {
+            days: Some(DayRange { from: 1, to: 3 }),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(older), ["K1ABC/P@7074"]);
+
+        let ft8 = DxQuery {
+            info: Some("FT8".into()),
+            freq: Some(FreqSelector::Range(
+                FrequencyHz(7_000_000),
+                FrequencyHz(21_450_000),
+            )),
+            ..DxQuery::default()
+        };
+        assert_eq!(run(ft8), ["K1ABC@21074", "K1ABC/P@7074"]);
+    }
+
+    #[test]
+    fn displays_canonical_arguments() {
+        let query = DxQuery {
+            count: Some(25),
+            dx: Some(CallMatch::Exact("K1ABC".into())),
+            spotter: Some(CallMatch::Anywhere("ABC".into())),
+            freq: Some(FreqSelector::Band(Band::Meter20)),
+            days: Some(DayRange { from: 0, to: 3 }),
+            info: Some("FT8".into()),
+        };
+        assert_eq!(
+            query.to_string(),
+            "25 on 20m K1ABC by *ABC day 3 info FT8 exact"
+        );
+        assert_eq!(DxQuery::default().to_string(), "");
+    }
+}
@@ -1,10 +1,325 @@
-use crate::spot::Spot;
+//! Token-bucket rate limiting for spots, announcements and commands.
+//!
+//! Each `(key, class)` pair owns a bucket holding up to
+//! [`Budget::capacity`] tokens, refilled one token every
+//! [`Budget::refill_every`]. Callers pass the current time in milliseconds
+//! from an arbitrary fixed origin so tests can drive the clock directly.
 
-#[derive(Debug, Clone, Default)]
-pub struct RateLimiter;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use dxcluster_types::{Callsign, NodeId};
+
+/// Buckets beyond this count trigger pruning of idle (full) buckets.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Who a bucket belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RateKey {
+    Spotter(Callsign),
+    Ip(IpAddr),
+    Peer(NodeId),
+}
+
+/// Kind of submission being limited; each has its own budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    Spot,
+    Announce,
+    Command,
+}
+
+impl RateClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateClass::Spot => "spots",
+            RateClass::Announce => "announcements",
+            RateClass::Command => "commands",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// Burst size: how many submissions are allowed back to back.
+    pub capacity: u32,
+    /// Time to regain one token.
+    pub refill_every: Duration,
+}
+
+impl Budget {
+    pub const fn new(capacity: u32, refill_every: Duration) -> Self {
+        Budget {
+            capacity,
+            refill_every,
+        }
+    }
+}
+
+/// Budgets per [`RateClass`]; `None` leaves a class unlimited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimits {
+    pub spots: Option<Budget>,
+    pub announces: Option<Budget>,
+    pub commands: Option<Budget>,
+}
+
+impl RateLimits {
+    /// No limits for any class.
+    pub const fn unlimited() -> Self {
+        RateLimits {
+            spots: None,
+            announces: None,
+            commands: None,
+        }
+    }
+
+    pub fn budget(&self, class: RateClass) -> Option<Budget> {
+        match class {
+            RateClass::Spot => self.spots,
+            RateClass::Announce => self.announces,
+            RateClass::Command => self.commands,
+        }
+    }
+}
+
+impl Default for RateLimits {
+    /// Limits suited to a single user: 10 spots then one every 6 seconds,
+    /// 3 announcements then one every 20 seconds, 30 commands then one per
+    /// second.
+    fn default() -> Self {
+        RateLimits {
+            spots: Some(Budget::new(10, Duration::from_secs(6))),
+            announces: Some(Budget::new(3, Duration::from_secs(20))),
+            commands: Some(Budget::new(30, Duration::from_secs(1))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("too many {}, try again in {}s", .class.as_str(), .retry_after.as_millis().div_ceil(1000).max(1))]
+pub struct RateLimited {
+    pub class: RateClass,
+    pub key: RateKey,
+    /// Time until the exhausted bucket regains a token.
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    tokens: u32,
+    last_refill_ms: u64,
+}
+
+impl Bucket {
+    fn refill(&mut self, budget: Budget, now_ms: u64) {
+        let step = (budget.refill_every.as_millis() as u64).max(1);
+        let elapsed = now_ms.saturating_sub(self.last_refill_ms);
+        let gained = elapsed / step;
+        if gained == 0 {
+            return;
+        }
+        let tokens = u64::from(self.tokens) + gained;
+        if tokens >= u64::from(budget.capacity) {
+            self.tokens = budget.capacity;
+            self.last_refill_ms = now_ms;
+        } else {
+            self.tokens = tokens as u32;
+            self.last_refill_ms += gained * step;
+        }
+    }
+
+    fn retry_after(&self, budget: Budget, now_ms: u64) -> Duration {
+        let step = (budget.refill_every.as_millis() as u64).max(1);
+        let waited = now_ms.saturating_sub(self.last_refill_ms);
+        Duration::from_millis(step.saturating_sub(waited))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: HashMap<(RateKey, RateClass), Bucket>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(RateLimits::default())
+    }
+}
 
 impl RateLimiter {
-    pub fn check(&self, _spot: &Spot) -> bool {
-        true
+    pub fn new(limits: RateLimits) -> Self {
+        RateLimiter {
+            limits,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    /// Take one `class` token from every key's bucket. Either all buckets
+    /// are charged or, if any is empty, none are and the first exhausted key
+    /// is reported.
+    pub fn check(
+        &mut self,
+        keys: &[RateKey],
+        class: RateClass,
+        now_ms: u64,
+    ) -> Result<(), RateLimited> {
+        let Some(budget) = self.limits.budget(class) else {
+            return Ok(());
+        };
+        if self.buckets.len() > PRUNE_THRESHOLD {
+            self.prune(now_ms);
+        }
+
+        for key in keys {
+            let bucket = self.buckets.entry((key.clone(), class)).or_insert(Bucket {
+                tokens: budget.capacity,
+                last_refill_ms: now_ms,
+            });
+            bucket.refill(budget, now_ms);
+            if bucket.tokens == 0 {
+                return Err(RateLimited {
+                    class,
+                    key: key.clone(),
+                    retry_after: bucket.retry_after(budget, now_ms),
+                });
+            }
+        }
+        for key in keys {
+            if let Some(bucket) = self.buckets.get_mut(&(key.clone(), class)) {
+                bucket.tokens -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop buckets that have refilled completely; they behave exactly like
+    /// missing ones.
+    pub fn prune(&mut self, now_ms: u64) {
+        let limits = &self.limits;
+        self.buckets.retain(|(_, class), bucket| {
+            let Some(budget) = limits.budget(*class) else {
+                return false;
+            };
+            bucket.refill(budget, now_ms);
+            bucket.tokens < budget.capacity
+        });
+    }
+
+    /// Number of tracked buckets.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spotter(call: &str) -> RateKey {
+        RateKey::Spotter(Callsign::parse_loose(call).unwrap())
+    }
+
+    fn limiter(capacity: u32, refill_secs: u64) -> RateLimiter {
+        RateLimiter::new(RateLimits {
+            spots: Some(Budget::new(capacity, Duration::from_secs(refill_secs))),
+            ..RateLimits::unlimited()
+        })
+    }
+
+    #[test]
+    fn allows_burst_then_throttles() {
+        let mut limiter = limiter(3, 10);
+        let key = [spotter("G4ABC")];
+        for _ in 0..3 {
+            assert!(limiter.check(&key, RateClass::Spot, 0).is_ok());
+        }
+        let err = limiter.check(&key, RateClass::Spot, 4_000).unwrap_err();
+        assert_eq!(err.class, RateClass::Spot);
+        assert_eq!(err.retry_after, Duration::from_secs(6));
+        assert_eq!(err.to_string(), "too many spots, try again in 6s");
+    }
+
+    #[test]
+    fn refills_one_token_per_interval() {
+        let mut limiter = limiter(2, 10);
+        let key = [spotter("G4ABC")];
+        limiter.check(&key, RateClass::Spot, 0).unwrap();
+        limiter.check(&key, RateClass::Spot, 0).unwrap();
+        assert!(limiter.check(&key, RateClass::Spot, 9_999).is_err());
+        assert!(limiter.check(&key, RateClass::Spot, 10_000).is_ok());
+        assert!(limiter.check(&key, RateClass::Spot, 15_000).is_err());
+        // A long pause refills only up to the burst size.
+        assert!(limiter.check(&key, RateClass::Spot, 100_000).is_ok());
+        assert!(limiter.check(&key, RateClass::Spot, 100_000).is_ok());
+        assert!(limiter.check(&key, RateClass::Spot, 100_000).is_err());
+    }
+
+    #[test]
+    fn keys_and_classes_are_independent() {
+        let mut limiter = RateLimiter::new(RateLimits {
+            spots: Some(Budget::new(1, Duration::from_secs(60))),
+            commands: Some(Budget::new(1, Duration::from_secs(60))),
+            announces: None,
+        });
+        let a = [spotter("G4ABC")];
+        let b = [spotter("K1ABC")];
+        limiter.check(&a, RateClass::Spot, 0).unwrap();
+        assert!(limiter.check(&a, RateClass::Spot, 0).is_err());
+        assert!(limiter.check(&b, RateClass::Spot, 0).is_ok());
+        assert!(limiter.check(&a, RateClass::Command, 0).is_ok());
+        for _ in 0..10 {
+            assert!(limiter.check(&a, RateClass::Announce, 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn charges_all_keys_or_none() {
+        let mut limiter = limiter(1, 60);
+        let ip = RateKey::Ip("192.0.2.1".parse().unwrap());
+        limiter
+            .check(&[spotter("G4ABC"), ip.clone()], RateClass::Spot, 0)
+            .unwrap();
+
+        // Same IP, different callsign: refused on the IP bucket, and the new
+        // callsign's token is not spent.
+        let err = limiter
+            .check(&[spotter("K1ABC"), ip.clone()], RateClass::Spot, 0)
+            .unwrap_err();
+        assert_eq!(err.key, ip);
+        assert!(
+            limiter
+                .check(&[spotter("K1ABC")], RateClass::Spot, 0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn prune_drops_refilled_buckets() {
+        let mut limiter = limiter(2, 10);
+        limiter
+            .check(
+                &[RateKey::Peer(NodeId("GB7DJK".into()))],
+                RateClass::Spot,
+                0,
+            )
+            .unwrap();
+        limiter
+            .check(&[spotter("G4ABC")], RateClass::Spot, 5_000)
+            .unwrap();
+        limiter.prune(12_000);
+        assert_eq!(limiter.len(), 1);
+        limiter.prune(20_000);
+        assert!(limiter.is_empty());
     }
 }
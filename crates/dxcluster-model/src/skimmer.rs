@@ -0,0 +1,277 @@
+//! Consolidation of raw skimmer spots.
+//!
+//! A busy station is reported by many skimmers, each several times a minute,
+//! on slightly different frequencies. [`SkimmerAggregator`] groups reports
+//! by DX call and frequency bucket, and releases one spot per group once
+//! enough distinct skimmers have heard it within a sliding window. A report
+//! joins a group in the bucket either side of its own, so a station near a
+//! bucket edge is not split in two. A single skimmer's busted decode never
+//! reaches the threshold on its own.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use dxcluster_types::Callsign;
+
+use crate::spot::Spot;
+
+/// When reports of one station become a spot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkimmerRules {
+    /// Distinct skimmers that must report a station within `window`.
+    pub confirmations: usize,
+    /// How far back reports count towards confirmation.
+    pub window: Duration,
+    /// Width of the frequency buckets reports are grouped by. Reports in
+    /// neighbouring buckets share a group.
+    pub bucket_hz: u64,
+    /// After a spot is released, further reports of the station are absorbed
+    /// for this long.
+    pub suppress: Duration,
+}
+
+impl Default for SkimmerRules {
+    fn default() -> Self {
+        Self {
+            confirmations: 2,
+            window: Duration::from_secs(5 * 60),
+            bucket_hz: 500,
+            suppress: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Report {
+    skimmer: Callsign,
+    seen: u64,
+    spot: Spot,
+}
+
+#[derive(Debug, Default)]
+struct Group {
+    reports: VecDeque<Report>,
+    suppressed_until: Option<u64>,
+}
+
+impl Group {
+    fn prune(&mut self, cutoff: u64) {
+        while self
+            .reports
+            .front()
+            .is_some_and(|report| report.seen < cutoff)
+        {
+            self.reports.pop_front();
+        }
+    }
+
+    fn is_idle(&self, now: u64) -> bool {
+        self.reports.is_empty() && self.suppressed_until.is_none_or(|until| until <= now)
+    }
+}
+
+#[derive(Debug)]
+pub struct SkimmerAggregator {
+    rules: SkimmerRules,
+    groups: HashMap<(Callsign, u64), Group>,
+    last_sweep: u64,
+}
+
+impl SkimmerAggregator {
+    pub fn new(rules: SkimmerRules) -> Self {
+        Self {
+            rules,
+            groups: HashMap::new(),
+            last_sweep: 0,
+        }
+    }
+
+    pub fn rules(&self) -> &SkimmerRules {
+        &self.rules
+    }
+
+    /// Stations with reports or a suppression period pending.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Record a skimmer report seen at `now` (Unix seconds). Returns the
+    /// consolidated spot when this report brings its station to the required
+    /// number of distinct skimmers outside a suppression period: the
+    /// strongest report in the window, so its SNR is the best heard.
+    pub fn offer(&mut self, spot: Spot, now: u64) -> Option<Spot> {
+        let window = self.rules.window.as_secs();
+        if now.saturating_sub(self.last_sweep) >= window {
+            self.sweep(now);
+        }
+
+        let bucket = self.bucket(&spot);
+        let key = [bucket, bucket.saturating_sub(1), bucket.saturating_add(1)]
+            .into_iter()
+            .map(|bucket| (spot.dx.clone(), bucket))
+            .find(|key| self.groups.contains_key(key))
+            .unwrap_or((spot.dx.clone(), bucket));
+        let group = self.groups.entry(key).or_default();
+        group.prune(now.saturating_sub(window));
+        group.reports.push_back(Report {
+            skimmer: spot.spotter.clone(),
+            seen: now,
+            spot,
+        });
+
+        if group.suppressed_until.is_some_and(|until| now < until) {
+            return None;
+        }
+        let skimmers: HashSet<&Callsign> =
+            group.reports.iter().map(|report| &report.skimmer).collect();
+        if skimmers.len() < self.rules.confirmations.max(1) {
+            return None;
+        }
+
+        group.suppressed_until = Some(now.saturating_add(self.rules.suppress.as_secs()));
+        group
+            .reports
+            .iter()
+            .max_by_key(|report| (report.spot.snr_db, report.seen))
+            .map(|report| report.spot.clone())
+    }
+
+    /// Forget reports older than the window and finished suppression periods.
+    pub fn sweep(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(self.rules.window.as_secs());
+        self.groups.retain(|_, group| {
+            group.prune(cutoff);
+            !group.is_idle(now)
+        });
+        self.last_sweep = now;
+    }
+
+    /// Nearest multiple of the bucket width to the spot's frequency.
+    fn bucket(&self, spot: &Spot) -> u64 {
+        let width = self.rules.bucket_hz.max(1);
+        spot.freq.0.saturating_add(width / 2) / width
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use dxcluster_types::{FrequencyHz, SpotId};
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn report(skimmer: &str, dx: &str, hz: u64, snr: i16) -> Spot {
+        let mut spot = Spot::new_local(
+            SpotId::hash_components(&[skimmer.as_bytes(), dx.as_bytes(), &hz.to_be_bytes()]),
+            OffsetDateTime::from_unix_timestamp(NOW as i64).unwrap(),
+            FrequencyHz(hz),
+            Callsign::parse_loose(dx).unwrap(),
+            Callsign::parse_loose(skimmer).unwrap(),
+            "CW",
+            None,
+        );
+        spot.snr_db = Some(snr);
+        spot
+    }
+
+    fn aggregator(confirmations: usize) -> SkimmerAggregator {
+        SkimmerAggregator::new(SkimmerRules {
+            confirmations,
+            ..SkimmerRules::default()
+        })
+    }
+
+    #[test]
+    fn releases_strongest_report_after_distinct_confirmations() {
+        let mut agg = aggregator(3);
+        assert_eq!(
+            agg.offer(report("KM3T", "K1ABC", 14_025_000, 12), NOW),
+            None
+        );
+        // The same skimmer again does not count twice.
+        assert_eq!(
+            agg.offer(report("KM3T", "K1ABC", 14_025_100, 30), NOW + 10),
+            None
+        );
+        assert_eq!(
+            agg.offer(report("W3OA", "K1ABC", 14_024_900, 18), NOW + 20),
+            None
+        );
+
+        let spot = agg
+            .offer(report("DK9IP", "K1ABC", 14_025_000, 9), NOW + 30)
+            .expect("third skimmer confirms");
+        assert_eq!(spot.spotter.as_str(), "KM3T");
+        assert_eq!(spot.snr_db, Some(30));
+    }
+
+    #[test]
+    fn suppresses_follow_ups_until_the_period_ends() {
+        let mut agg = aggregator(2);
+        agg.offer(report("KM3T", "K1ABC", 14_025_000, 12), NOW);
+        assert!(
+            agg.offer(report("W3OA", "K1ABC", 14_025_000, 15), NOW + 5)
+                .is_some()
+        );
+        assert_eq!(
+            agg.offer(report("DK9IP", "K1ABC", 14_025_000, 20), NOW + 60),
+            None
+        );
+
+        // Still active after the suppression period: released again.
+        let later = NOW + 5 + 10 * 60;
+        agg.offer(report("KM3T", "K1ABC", 14_025_000, 10), later);
+        assert!(
+            agg.offer(report("W3OA", "K1ABC", 14_025_000, 11), later + 1)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn groups_by_call_and_frequency_within_the_window() {
+        let mut agg = aggregator(2);
+        agg.offer(report("KM3T", "K1ABC", 14_025_000, 12), NOW);
+        // Another call, another bucket, or too late: no confirmation.
+        assert_eq!(
+            agg.offer(report("W3OA", "K1ABD", 14_025_000, 12), NOW + 1),
+            None
+        );
+        assert_eq!(
+            agg.offer(report("W3OA", "K1ABC", 14_030_000, 12), NOW + 2),
+            None
+        );
+        assert_eq!(agg.len(), 3);
+        assert_eq!(
+            agg.offer(report("W3OA", "K1ABC", 14_025_000, 12), NOW + 400),
+            None
+        );
+        assert_eq!(agg.len(), 1);
+
+        agg.sweep(NOW + 2_000);
+        assert!(agg.is_empty());
+    }
+
+    #[test]
+    fn reports_straddling_a_bucket_edge_confirm_each_other() {
+        let mut agg = aggregator(2);
+        // 14025.2 kHz rounds to 14025.0 and 14025.3 kHz to 14025.5.
+        agg.offer(report("KM3T", "K1ABC", 14_025_200, 12), NOW);
+        let spot = agg
+            .offer(report("W3OA", "K1ABC", 14_025_300, 15), NOW + 5)
+            .expect("neighbouring bucket confirms");
+        assert_eq!(spot.spotter.as_str(), "W3OA");
+        assert_eq!(agg.len(), 1);
+
+        // Follow-ups in either bucket fall under the same suppression.
+        assert_eq!(
+            agg.offer(report("DK9IP", "K1ABC", 14_024_900, 20), NOW + 10),
+            None
+        );
+    }
+}
@@ -1,13 +1,75 @@
-use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use std::fmt;
+use std::net::IpAddr;
+
+use dxcluster_types::{Band, Callsign, FrequencyHz, Locator, Mode, NodeId, SpotId};
 
 #[cfg(feature = "time")]
 pub type Timestamp = time::OffsetDateTime;
 #[cfg(not(feature = "time"))]
 pub type Timestamp = i64;
 
+/// Who or what produced a spot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum SpotKind {
+    /// Entered by an operator.
+    #[default]
+    Human,
+    /// A CW or RTTY skimmer, e.g. from the Reverse Beacon Network.
+    Skimmer,
+    /// A digital-mode decoder such as an FT8 monitor.
+    Digital,
+}
+
+impl SpotKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpotKind::Human => "human",
+            SpotKind::Skimmer => "skimmer",
+            SpotKind::Digital => "digital",
+        }
+    }
+
+    /// The kind named by `word`, ignoring case.
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "human" => Some(SpotKind::Human),
+            "skimmer" => Some(SpotKind::Skimmer),
+            "digital" => Some(SpotKind::Digital),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SpotKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A DX spot.
+///
+/// With the `serde` feature a spot serializes as a JSON object with the
+/// field names below; `ts` is integer Unix seconds and optional fields are
+/// `null` when unset. Optional fields and `kind` may be left out when
+/// deserializing:
+///
+/// ```json
+/// {"spot_id":"9f86d0...","ts":1700000000,"freq":14074000,"dx":"DL1ABC",
+///  "spotter":"K1XYZ","comment":"FT8 -12dB","origin":"GB7DJK","hop":1,
+///  "mode":"FT8","band":"20m","snr_db":-12,"wpm":null,
+///  "spotter_locator":"FN42","spotter_ip":null,"spotter_node":null,
+///  "kind":"digital"}
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spot {
     pub spot_id: SpotId,
+    #[cfg_attr(feature = "serde", serde(with = "serde_unix_seconds"))]
     pub ts: Timestamp,
     pub freq: FrequencyHz,
     pub dx: Callsign,
@@ -15,9 +77,32 @@ pub struct Spot {
     pub comment: String,
     pub origin: Option<NodeId>,
     pub hop: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: Option<Mode>,
+    /// Band of `freq`, kept alongside it so it is not recomputed per query.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub band: Option<Band>,
+    /// Signal-to-noise ratio reported by a skimmer or decoder.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub snr_db: Option<i16>,
+    /// CW speed in words per minute.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub wpm: Option<u16>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spotter_locator: Option<Locator>,
+    /// Address the spotter connected from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spotter_ip: Option<IpAddr>,
+    /// Node the spotter was logged into.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spotter_node: Option<NodeId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: SpotKind,
 }
 
 impl Spot {
+    /// A human spot with no metadata beyond the band of `freq` and the mode
+    /// inferred from `comment` and `freq`.
     pub fn new_local(
         spot_id: SpotId,
         ts: Timestamp,
@@ -27,15 +112,131 @@ impl Spot {
         comment: impl Into<String>,
         origin: Option<NodeId>,
     ) -> Self {
+        let comment = comment.into();
         Spot {
             spot_id,
             ts,
+            mode: Mode::infer(freq, &comment),
+            band: Band::from_frequency(freq),
             freq,
             dx,
             spotter,
-            comment: comment.into(),
+            comment,
             origin,
             hop: 0,
+            snr_db: None,
+            wpm: None,
+            spotter_locator: None,
+            spotter_ip: None,
+            spotter_node: None,
+            kind: SpotKind::Human,
         }
     }
 }
+
+#[cfg(feature = "time")]
+pub(crate) fn unix_seconds(ts: &Timestamp) -> i64 {
+    ts.unix_timestamp()
+}
+
+#[cfg(not(feature = "time"))]
+pub(crate) fn unix_seconds(ts: &Timestamp) -> i64 {
+    *ts
+}
+
+#[cfg(feature = "serde")]
+mod serde_unix_seconds {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Timestamp;
+
+    pub fn serialize<S: Serializer>(ts: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(ts.unix_timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let secs = i64::deserialize(deserializer)?;
+        Timestamp::from_unix_timestamp(secs).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use dxcluster_types::{Callsign, FrequencyHz, Locator, NodeId, SpotId};
+    use serde_json::json;
+
+    use super::*;
+
+    fn spot() -> Spot {
+        let mut spot = Spot::new_local(
+            SpotId([0x01; 32]),
+            Timestamp::from_unix_timestamp(1_700_000_000).unwrap(),
+            FrequencyHz(14_074_000),
+            Callsign::parse_loose("DL1ABC").unwrap(),
+            Callsign::parse_loose("K1XYZ").unwrap(),
+            "FT8 -12dB",
+            Some(NodeId("GB7DJK".into())),
+        );
+        spot.hop = 1;
+        spot.snr_db = Some(-12);
+        spot.spotter_locator = Some(Locator::parse("FN42").unwrap());
+        spot.kind = SpotKind::Digital;
+        spot
+    }
+
+    #[test]
+    fn serializes_to_documented_json() {
+        let value = serde_json::to_value(spot()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "spot_id": "01".repeat(32),
+                "ts": 1_700_000_000,
+                "freq": 14_074_000,
+                "dx": "DL1ABC",
+                "spotter": "K1XYZ",
+                "comment": "FT8 -12dB",
+                "origin": "GB7DJK",
+                "hop": 1,
+                "mode": "FT8",
+                "band": "20m",
+                "snr_db": -12,
+                "wpm": null,
+                "spotter_locator": "FN42",
+                "spotter_ip": null,
+                "spotter_node": null,
+                "kind": "digital",
+            })
+        );
+        assert_eq!(serde_json::from_value::<Spot>(value).unwrap(), spot());
+    }
+
+    #[test]
+    fn metadata_may_be_omitted() {
+        let value = json!({
+            "spot_id": "01".repeat(32),
+            "ts": 1_700_000_000,
+            "freq": 14_074_000,
+            "dx": "DL1ABC",
+            "spotter": "K1XYZ",
+            "comment": "",
+            "origin": null,
+            "hop": 0,
+        });
+        let spot: Spot = serde_json::from_value(value).unwrap();
+        assert_eq!(spot.mode, None);
+        assert_eq!(spot.kind, SpotKind::Human);
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let mut value = serde_json::to_value(spot()).unwrap();
+        value["dx"] = json!("");
+        assert!(serde_json::from_value::<Spot>(value).is_err());
+
+        let mut value = serde_json::to_value(spot()).unwrap();
+        value["spot_id"] = json!("not-hex");
+        assert!(serde_json::from_value::<Spot>(value).is_err());
+    }
+}
@@ -1,11 +1,18 @@
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use anyhow::Context;
 use clap::Parser;
+use dxcluster_model::{
+    CtyTable, Filter, FilterRule, FilterType, FuzzyWindow, Policy, RateLimits, SharedCtyTable,
+    SkimmerRules,
+};
 use dxcluster_node::{
-    Node, NodeConfig, PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode,
+    DEFAULT_DEDUPE_CAPACITY, DEFAULT_SPOT_CACHE_SIZE, Node, NodeConfig, PeerOptions,
+    PeerRetryPolicy, UpstreamConfig, UpstreamMode, UserOptions,
 };
-use dxcluster_types::NodeId;
+use dxcluster_types::{Callsign, NodeId, Region};
 
 #[derive(Debug, Parser)]
 #[command(name = "dxcluster-node-bin", about = "Run a DX cluster node")]
@@ -19,6 +26,22 @@ struct Args {
     /// Outbound peer addresses to connect to (repeatable).
     #[arg(long = "peer", value_name = "ADDR")]
     peers: Vec<String>,
+    /// Skimmer feed addresses to ingest spots from, e.g.
+    /// `telnet.reversebeacon.net:7000` (repeatable).
+    #[arg(long = "skimmer", value_name = "ADDR")]
+    skimmers: Vec<String>,
+    /// Callsign to log into skimmer feeds with; defaults to the node id.
+    #[arg(long, value_name = "CALL")]
+    skimmer_call: Option<String>,
+    /// Distinct skimmers that must hear a station before it is spotted.
+    #[arg(long, default_value_t = SkimmerRules::default().confirmations)]
+    skimmer_confirmations: usize,
+    /// Window in which skimmer confirmations are counted.
+    #[arg(long, value_name = "MINUTES", default_value_t = 5)]
+    skimmer_window_mins: u64,
+    /// How long further skimmer reports of a spotted station are held back.
+    #[arg(long, value_name = "MINUTES", default_value_t = 10)]
+    skimmer_suppress_mins: u64,
     /// Local node identifier used in peer handshakes.
     #[arg(long, default_value = "local")]
     node_id: String,
@@ -31,12 +54,72 @@ struct Args {
     /// Heartbeat interval for peer links (ms).
     #[arg(long, default_value_t = 10_000)]
     peer_heartbeat_ms: u64,
+    /// Spots received from peers with more hops than this are discarded;
+    /// also the `H<n>` budget of DXSpider links.
+    #[arg(long, default_value_t = 16)]
+    peer_max_hops: u32,
     /// Optional auth token to present to outbound peers.
     #[arg(long)]
     peer_auth_token: Option<String>,
     /// Optional auth token required from inbound peers.
     #[arg(long)]
     peer_expected_token: Option<String>,
+    /// Filter line applied to spots forwarded to peers, e.g.
+    /// `reject/spots on vhf` (repeatable).
+    #[arg(long = "peer-filter", value_name = "LINE")]
+    peer_filters: Vec<String>,
+    /// Registered user that must log in with a password (repeatable).
+    #[arg(long = "user", value_name = "CALL=PASSWORD")]
+    users: Vec<String>,
+    /// Callsign refused at login (repeatable).
+    #[arg(long = "ban", value_name = "CALL")]
+    banned: Vec<String>,
+    /// Callsign that receives sysop announcements (repeatable).
+    #[arg(long = "sysop", value_name = "CALL")]
+    sysops: Vec<String>,
+    /// Spotter whose spots are refused (repeatable).
+    #[arg(long = "ban-spotter", value_name = "CALL")]
+    banned_spotters: Vec<String>,
+    /// Station that may not be spotted (repeatable).
+    #[arg(long = "ban-dx", value_name = "CALL")]
+    banned_dx: Vec<String>,
+    /// Node whose spots are refused (repeatable).
+    #[arg(long = "ban-node", value_name = "NODE")]
+    banned_nodes: Vec<String>,
+    /// Word refused in spot comments (repeatable).
+    #[arg(long = "bad-word", value_name = "WORD")]
+    bad_words: Vec<String>,
+    /// Accept spots where the spotter spots themselves.
+    #[arg(long)]
+    allow_self_spots: bool,
+    /// IARU region (1, 2 or 3) whose band plan locally submitted spots must
+    /// fit.
+    #[arg(long)]
+    region: Option<Region>,
+    /// Country file used for DXCC filters, reloaded on SIGHUP. `cty.dat` is
+    /// accepted, but only `cty.csv` carries the entity numbers that
+    /// `call_dxcc`/`by_dxcc` need.
+    #[arg(long, value_name = "PATH")]
+    cty_file: Option<PathBuf>,
+    /// Disable per-user and per-peer submission rate limits.
+    #[arg(long)]
+    no_rate_limit: bool,
+    /// Number of recent spots kept for `sh/dx`.
+    #[arg(long, default_value_t = DEFAULT_SPOT_CACHE_SIZE)]
+    spot_cache_size: usize,
+    /// Drop cached spots older than this many minutes.
+    #[arg(long, value_name = "MINUTES")]
+    spot_max_age_mins: Option<u64>,
+    /// Most spot ids remembered for duplicate suppression.
+    #[arg(long, default_value_t = DEFAULT_DEDUPE_CAPACITY)]
+    dedupe_capacity: usize,
+    /// Treat the same DX call from another spotter within this many kHz as a
+    /// duplicate.
+    #[arg(long, value_name = "KHZ")]
+    fuzzy_dedupe_khz: Option<u64>,
+    /// Time window for `--fuzzy-dedupe-khz`.
+    #[arg(long, value_name = "MINUTES", default_value_t = 5)]
+    fuzzy_dedupe_mins: u64,
 }
 
 #[tokio::main]
@@ -44,22 +127,128 @@ async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
     let args = Args::parse();
 
-    let peer_options = PeerOptions {
+    let spot_filter = args
+        .peer_filters
+        .iter()
+        .map(|line| {
+            let rule =
+                FilterRule::parse(line).with_context(|| format!("invalid filter: {line}"))?;
+            anyhow::ensure!(
+                rule.kind == FilterType::Spots,
+                "peer filters must be spot filters: {line}"
+            );
+            Ok(rule)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut peer_options = PeerOptions {
         heartbeat_interval: Duration::from_millis(args.peer_heartbeat_ms),
         expected_auth_token: args.peer_expected_token.clone(),
+        spot_filter: Filter::new(spot_filter),
+        max_hops: args.peer_max_hops,
         ..PeerOptions::default()
     };
+    if args.no_rate_limit {
+        peer_options.rate_limits = RateLimits::unlimited();
+    }
     let peer_retry = PeerRetryPolicy {
         base_delay: Duration::from_millis(args.peer_retry_base_ms),
         max_delay: Duration::from_millis(args.peer_retry_max_ms),
     };
 
+    let passwords = args
+        .users
+        .iter()
+        .map(|entry| {
+            let (call, password) = entry
+                .split_once('=')
+                .with_context(|| format!("expected CALL=PASSWORD, got {entry}"))?;
+            let call = Callsign::parse_strict(call)
+                .with_context(|| format!("invalid user callsign: {call}"))?;
+            Ok((call, password.to_string()))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let banned = args
+        .banned
+        .iter()
+        .map(|call| {
+            Callsign::parse_loose(call).with_context(|| format!("invalid banned callsign: {call}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let sysops = args
+        .sysops
+        .iter()
+        .map(|call| {
+            Callsign::parse_loose(call).with_context(|| format!("invalid sysop callsign: {call}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let banned_spotters = args
+        .banned_spotters
+        .iter()
+        .map(|call| {
+            Callsign::parse_loose(call).with_context(|| format!("invalid spotter callsign: {call}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let banned_dx = args
+        .banned_dx
+        .iter()
+        .map(|call| {
+            Callsign::parse_loose(call).with_context(|| format!("invalid dx callsign: {call}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let policy = Policy {
+        banned_spotters,
+        banned_dx,
+        banned_nodes: args.banned_nodes.into_iter().map(NodeId).collect(),
+        bad_words: args.bad_words,
+        allow_self_spots: args.allow_self_spots,
+        ..Policy::default()
+    };
+
+    let countries = match &args.cty_file {
+        Some(path) => {
+            let table = CtyTable::load(path)
+                .with_context(|| format!("failed to load country file {}", path.display()))?;
+            warn_without_dxcc_numbers(&table, path);
+            SharedCtyTable::new(table)
+        }
+        None => SharedCtyTable::default(),
+    };
+
     let config = NodeConfig {
         user_listen: args.user_listen,
         peer_listen: args.peer_listen,
         node_id: NodeId(args.node_id),
         peer_options,
         peer_retry,
+        user_options: UserOptions {
+            passwords,
+            banned,
+            sysops,
+            rate_limits: if args.no_rate_limit {
+                RateLimits::unlimited()
+            } else {
+                RateLimits::default()
+            },
+        },
+        policy,
+        countries,
+        region: args.region,
+        spot_cache_size: args.spot_cache_size,
+        spot_max_age: args
+            .spot_max_age_mins
+            .map(|mins| Duration::from_secs(mins * 60)),
+        dedupe_capacity: args.dedupe_capacity,
+        fuzzy_dedupe: args.fuzzy_dedupe_khz.map(|khz| FuzzyWindow {
+            freq_hz: khz * 1_000,
+            window: Duration::from_secs(args.fuzzy_dedupe_mins * 60),
+        }),
+        skimmer_rules: SkimmerRules {
+            confirmations: args.skimmer_confirmations,
+            window: Duration::from_secs(args.skimmer_window_mins * 60),
+            suppress: Duration::from_secs(args.skimmer_suppress_mins * 60),
+            ..SkimmerRules::default()
+        },
     };
 
     let mut builder = Node::builder(config);
@@ -71,7 +260,66 @@ async fn main() -> anyhow::Result<()> {
             auth_token: args.peer_auth_token.clone(),
         });
     }
+    for addr in args.skimmers {
+        builder = builder.with_upstream(UpstreamConfig {
+            addr,
+            mode: UpstreamMode::Skimmer,
+            login_callsign: args.skimmer_call.clone(),
+            auth_token: None,
+        });
+    }
 
-    let _handle = builder.spawn().await?;
+    let handle = builder.spawn().await?;
+    wait_for_shutdown(handle.countries(), args.cty_file.as_deref()).await?;
+    handle.shutdown().await;
     Ok(())
 }
+
+/// Run until Ctrl-C, reloading the country file on SIGHUP.
+#[cfg(unix)]
+async fn wait_for_shutdown(
+    countries: &SharedCtyTable,
+    cty_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = signal(SignalKind::hangup()).context("failed to listen for SIGHUP")?;
+    loop {
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => return Ok(result?),
+            _ = hangup.recv() => {
+                let Some(path) = cty_file else {
+                    continue;
+                };
+                match countries.reload(path) {
+                    Ok(()) => {
+                        tracing::info!(path = %path.display(), "reloaded country file");
+                        warn_without_dxcc_numbers(&countries.current(), path);
+                    }
+                    Err(err) => {
+                        tracing::warn!(path = %path.display(), %err, "country file reload failed; keeping the current table");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown(
+    _countries: &SharedCtyTable,
+    _cty_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    Ok(tokio::signal::ctrl_c().await?)
+}
+
+/// A `cty.dat` resolves entities but carries no DXCC numbers, so DXCC
+/// filters would silently match nothing.
+fn warn_without_dxcc_numbers(table: &CtyTable, path: &Path) {
+    if !table.has_dxcc_numbers() {
+        tracing::warn!(
+            path = %path.display(),
+            "country file has no DXCC entity numbers; call_dxcc/by_dxcc filters need cty.csv"
+        );
+    }
+}
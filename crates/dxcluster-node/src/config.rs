@@ -1,7 +1,17 @@
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::time::Duration;
 
-use dxcluster_types::NodeId;
+use dxcluster_model::{
+    Budget, Filter, FuzzyWindow, Policy, RateLimits, SharedCtyTable, SkimmerRules,
+};
+use dxcluster_types::{Callsign, NodeId, Region};
+
+/// Default for [`NodeConfig::spot_cache_size`].
+pub const DEFAULT_SPOT_CACHE_SIZE: usize = 256;
+
+/// Default for [`NodeConfig::dedupe_capacity`].
+pub const DEFAULT_DEDUPE_CAPACITY: usize = 100_000;
 
 #[derive(Debug, Clone)]
 pub struct NodeConfig {
@@ -10,6 +20,71 @@ pub struct NodeConfig {
     pub node_id: NodeId,
     pub peer_options: PeerOptions,
     pub peer_retry: PeerRetryPolicy,
+    pub user_options: UserOptions,
+    /// Rules every spot must pass, whether submitted by a user or received
+    /// from a peer.
+    pub policy: Policy,
+    /// IARU region whose band plan applies to spots submitted on this node;
+    /// `None` accepts any region's allocations. Spots relayed by peers, which
+    /// may come from anywhere, are checked against [`Policy::region`] only,
+    /// and a region set there takes precedence.
+    pub region: Option<Region>,
+    /// Country table used for `call_dxcc`/`by_dxcc` filters. Keep a clone to
+    /// reload the country file while the node runs.
+    pub countries: SharedCtyTable,
+    /// Number of recent spots kept for `sh/dx` and similar queries.
+    pub spot_cache_size: usize,
+    /// Spots older than this are dropped from the cache; `None` keeps them
+    /// until they are pushed out by newer spots.
+    pub spot_max_age: Option<Duration>,
+    /// Most spot ids remembered for duplicate suppression; the oldest are
+    /// forgotten first once the table is full.
+    pub dedupe_capacity: usize,
+    /// Also treat the same DX call from another spotter on a nearby
+    /// frequency as a duplicate.
+    pub fuzzy_dedupe: Option<FuzzyWindow>,
+    /// Confirmations needed before a skimmer feed's report of a station
+    /// becomes a spot, and how long repeats are then held back.
+    pub skimmer_rules: SkimmerRules,
+}
+
+impl NodeConfig {
+    /// Configuration listening for users on `user_listen` with defaults for
+    /// everything else and no peer listener.
+    pub fn new(user_listen: SocketAddr, node_id: NodeId) -> Self {
+        Self {
+            user_listen,
+            peer_listen: None,
+            node_id,
+            peer_options: PeerOptions::default(),
+            peer_retry: PeerRetryPolicy::default(),
+            user_options: UserOptions::default(),
+            policy: Policy::default(),
+            region: None,
+            countries: SharedCtyTable::default(),
+            spot_cache_size: DEFAULT_SPOT_CACHE_SIZE,
+            spot_max_age: None,
+            dedupe_capacity: DEFAULT_DEDUPE_CAPACITY,
+            fuzzy_dedupe: None,
+            skimmer_rules: SkimmerRules::default(),
+        }
+    }
+}
+
+/// Login rules and limits for user sessions.
+#[derive(Debug, Clone, Default)]
+pub struct UserOptions {
+    /// Registered callsigns and their passwords. These users are prompted for
+    /// a password after entering their callsign; everyone else logs in with
+    /// the callsign alone. A call without its own entry uses its base call's,
+    /// so `G4ABC/P` needs `G4ABC`'s password.
+    pub passwords: HashMap<Callsign, String>,
+    /// Callsigns that are refused at login, with any prefix or suffix.
+    pub banned: HashSet<Callsign>,
+    /// Callsigns that receive `ann sysop` announcements.
+    pub sysops: HashSet<Callsign>,
+    /// Per-callsign and per-IP budgets for spots, announcements and commands.
+    pub rate_limits: RateLimits,
 }
 
 #[derive(Debug, Clone)]
@@ -18,6 +93,34 @@ pub struct PeerOptions {
     pub capabilities: Vec<String>,
     pub heartbeat_interval: Duration,
     pub expected_auth_token: Option<String>,
+    /// Node callsigns allowed to log in on inbound DXSpider links; any other
+    /// callsign is refused at the `login: ` prompt.
+    pub allowed_nodes: HashSet<Callsign>,
+    /// Filter applied to spots before they are forwarded to a peer.
+    pub spot_filter: Filter,
+    /// Protocol spoken by peers connecting to `peer_listen`.
+    pub dialect: PeerDialect,
+    /// Spots and announcements received with a hop count above this are
+    /// discarded. DXSpider links share the same budget: outgoing frames are
+    /// sent with `H<max_hops - hop>` and incoming `H<n>` counters read back
+    /// as hop `max_hops - n`.
+    pub max_hops: u32,
+    /// Per-peer budgets for spots and announcements received on a link.
+    pub rate_limits: RateLimits,
+}
+
+/// Node-to-node protocol spoken on a peer link.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PeerDialect {
+    /// Native pipe-separated frames (`HELLO|...`, `SPOT|...`).
+    #[default]
+    Native,
+    /// DXSpider `PCxx` frames. Inbound links are prompted for the remote
+    /// node's callsign, which must be in [`PeerOptions::allowed_nodes`]. The
+    /// dialect has no way to present a token, so inbound links are refused
+    /// when [`PeerOptions::expected_auth_token`] is set.
+    #[cfg(feature = "compat_dxspider")]
+    DxSpider,
 }
 
 impl Default for PeerOptions {
@@ -27,6 +130,15 @@ impl Default for PeerOptions {
             capabilities: vec!["spots-v1".to_string(), "heartbeat".to_string()],
             heartbeat_interval: Duration::from_secs(10),
             expected_auth_token: None,
+            allowed_nodes: HashSet::new(),
+            spot_filter: Filter::default(),
+            dialect: PeerDialect::default(),
+            max_hops: 16,
+            rate_limits: RateLimits {
+                spots: Some(Budget::new(600, Duration::from_millis(100))),
+                announces: Some(Budget::new(100, Duration::from_secs(1))),
+                commands: None,
+            },
         }
     }
 }
@@ -50,6 +162,14 @@ impl Default for PeerRetryPolicy {
 pub enum UpstreamMode {
     Telnet,
     Peer,
+    /// Reverse Beacon Network style skimmer feed, logging in as
+    /// `login_callsign` (or the node id when unset). Spots are injected as
+    /// if submitted on this node; nothing is sent back.
+    Skimmer,
+    /// Link to a DXSpider node, logging in as `login_callsign` (or the node
+    /// id when unset).
+    #[cfg(feature = "compat_dxspider")]
+    DxSpider,
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,129 @@
+//! DXSpider link handling for [`PeerSession`](crate::peer_session::PeerSession).
+//!
+//! The accepting node prompts for the connecting node's callsign and sends
+//! `PC18`. The connecting node answers with its `PC92` configuration and a
+//! `PC20` request, and the acceptor replies with its own configuration and
+//! `PC22`. Spots flow once either side has seen or sent `PC22`.
+
+use std::collections::HashSet;
+use std::io;
+use std::sync::{Arc, atomic::AtomicBool};
+
+use dxcluster_types::{Callsign, NodeId};
+use dxcluster_wire::PeerFrame;
+use dxcluster_wire::dxspider::{DEFAULT_HOPS, PROTOCOL_VERSION, PcConfig, PcFrame};
+use time::OffsetDateTime;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::sync::RwLock;
+
+use crate::config::PeerOptions;
+use crate::peer_session::{FrameSink, establish};
+use crate::state::NodeState;
+
+/// Perform the login exchange. When `login` is set this side connected and
+/// logs in with it; otherwise the remote is prompted and its callsign, which
+/// must be one of `allowed`, is returned as the remote node id.
+pub(crate) async fn open_link<R>(
+    reader: &mut R,
+    tx: &FrameSink,
+    login: Option<String>,
+    allowed: &HashSet<Callsign>,
+) -> io::Result<Option<NodeId>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    if let Some(login) = login {
+        tx.send_line(login).ok();
+        return Ok(None);
+    }
+
+    tx.send_line("login: ".to_string()).ok();
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "peer closed during login",
+        ));
+    }
+    let callsign = Callsign::parse_loose(line.trim())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    if !allowed.contains(&callsign) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{callsign} is not an allowed node"),
+        ));
+    }
+
+    tx.send_line(
+        PcFrame::LinkInit {
+            software: format!("dxcluster {} pc9x", env!("CARGO_PKG_VERSION")),
+            version: PROTOCOL_VERSION.to_string(),
+        }
+        .to_line(),
+    )
+    .ok();
+    Ok(Some(NodeId(callsign.as_str().to_string())))
+}
+
+/// Handle link-level `PCxx` frames and translate the rest into native frames
+/// for the shared frame handler. Unparseable and unsupported frames are
+/// ignored.
+pub(crate) async fn handle_line(
+    line: &str,
+    state: &NodeState,
+    remote_id: &Arc<RwLock<Option<NodeId>>>,
+    auth_ok: &AtomicBool,
+    initial_sync_sent: &AtomicBool,
+    tx: &FrameSink,
+    options: &PeerOptions,
+) -> Option<PeerFrame> {
+    match PcFrame::parse(line).ok()? {
+        PcFrame::LinkInit { .. } => {
+            tx.send_line(local_config(state.node_id()).to_line()).ok();
+            tx.send_line(PcFrame::ConfigRequest.to_line()).ok();
+            None
+        }
+        PcFrame::ConfigRequest => {
+            tx.send_line(local_config(state.node_id()).to_line()).ok();
+            tx.send_line(PcFrame::ConfigDone.to_line()).ok();
+            establish(state, auth_ok, initial_sync_sent, tx, options).await;
+            None
+        }
+        PcFrame::ConfigDone => {
+            establish(state, auth_ok, initial_sync_sent, tx, options).await;
+            None
+        }
+        // On outbound links the first configuration received is the remote
+        // node's own; later ones describe the rest of the network.
+        PcFrame::Config(config) if remote_id.read().await.is_none() => Some(PeerFrame::Hello {
+            node_id: NodeId(config.node),
+            version: PROTOCOL_VERSION.to_string(),
+        }),
+        // Spots and announcements whose hop counter has run out are not
+        // accepted or forwarded.
+        PcFrame::Spot(spot) if spot.hops == 0 => None,
+        PcFrame::Announce(ann) if ann.hops == 0 => None,
+        PcFrame::Talk(talk) if talk.hops == 0 => None,
+        frame => frame.to_peer_frame(options.max_hops),
+    }
+}
+
+/// Encode a native frame as the equivalent `PCxx` line, if there is one,
+/// translating hop counts against `max_hops`.
+pub(crate) fn encode(frame: &PeerFrame, local: &NodeId, max_hops: u32) -> Option<String> {
+    // The node never originates pings, so the remote callsign is not needed.
+    PcFrame::from_peer_frame(frame, local, "", max_hops).map(|frame| frame.to_line())
+}
+
+fn local_config(node_id: &NodeId) -> PcFrame {
+    let now = OffsetDateTime::now_utc();
+    let seconds =
+        u32::from(now.hour()) * 3600 + u32::from(now.minute()) * 60 + u32::from(now.second());
+    PcFrame::Config(PcConfig {
+        node: node_id.0.clone(),
+        time: seconds.to_string(),
+        kind: "C".to_string(),
+        entries: vec![format!("5{}:{PROTOCOL_VERSION}", node_id.0)],
+        hops: DEFAULT_HOPS,
+    })
+}
@@ -1,13 +1,19 @@
 //! Node runtime engine.
 
 pub mod config;
+#[cfg(feature = "compat_dxspider")]
+mod dxspider;
 pub mod error;
 pub mod node;
 pub mod peer_session;
 pub mod session;
+pub mod skimmer;
 pub mod state;
 pub mod upstream;
 
-pub use config::{NodeConfig, PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode};
+pub use config::{
+    DEFAULT_DEDUPE_CAPACITY, DEFAULT_SPOT_CACHE_SIZE, NodeConfig, PeerDialect, PeerOptions,
+    PeerRetryPolicy, UpstreamConfig, UpstreamMode, UserOptions,
+};
 pub use error::NodeError;
 pub use node::{Node, NodeBuilder, NodeHandle};
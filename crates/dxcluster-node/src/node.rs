@@ -1,12 +1,14 @@
-use dxcluster_model::Spot;
+use std::sync::Arc;
+
+use dxcluster_model::{Announcement, DedupeStats, DedupeTable, SharedCtyTable, Spot, SpotCache};
 use tokio::net::TcpListener;
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
-use crate::config::{NodeConfig, UpstreamConfig};
+use crate::config::{NodeConfig, UpstreamConfig, UserOptions};
 use crate::error::NodeError;
 use crate::session::UserSession;
-use crate::state::NodeState;
+use crate::state::{DEDUPE_TTL, NodeState};
 use crate::upstream::UpstreamHandle;
 
 /// Runtime entrypoint for embedding a DX Cluster node.
@@ -56,11 +58,26 @@ impl NodeBuilder {
 
     /// Spawn the node runtime and return a handle for control and inspection.
     pub async fn spawn(self) -> Result<NodeHandle, NodeError> {
-        let state = NodeState::new(self.config.node_id.clone());
+        let state = NodeState::new(self.config.node_id.clone())
+            .with_policy(self.config.policy.clone())
+            .with_region(self.config.region)
+            .with_countries(self.config.countries.clone())
+            .with_cache(spot_cache(&self.config))
+            .with_dedupe(dedupe_table(&self.config))
+            .with_skimmer_rules(self.config.skimmer_rules.clone())
+            .with_rate_limits(
+                self.config.user_options.rate_limits.clone(),
+                self.config.peer_options.rate_limits.clone(),
+            );
 
         let (shutdown, shutdown_rx) = broadcast::channel(8);
-        let user_task =
-            spawn_user_listener(self.config.user_listen, state.clone(), shutdown_rx).await?;
+        let user_task = spawn_user_listener(
+            self.config.user_listen,
+            state.clone(),
+            Arc::new(self.config.user_options.clone()),
+            shutdown_rx,
+        )
+        .await?;
 
         let mut tasks = vec![user_task];
 
@@ -110,11 +127,50 @@ impl NodeHandle {
     pub async fn recent_spots(&self, n: usize) -> Vec<Spot> {
         self.state.recent(n).await
     }
+
+    /// Make an announcement directly on the node, as if a user had sent it.
+    /// Returns `false` if it was a duplicate.
+    pub async fn inject_announcement(&self, announcement: Announcement) -> bool {
+        self.state.announce(announcement, None).await
+    }
+
+    /// Fetch the `n` most recent announcements, newest first.
+    pub async fn recent_announcements(&self, n: usize) -> Vec<Announcement> {
+        self.state.recent_announcements(n).await
+    }
+
+    /// Duplicate-suppression counters, for tuning the dedupe window.
+    pub async fn dedupe_stats(&self) -> DedupeStats {
+        self.state.dedupe_stats().await
+    }
+
+    /// Country table used by the node; [`SharedCtyTable::reload`] swaps in
+    /// an updated country file without a restart.
+    pub fn countries(&self) -> &SharedCtyTable {
+        self.state.countries()
+    }
+}
+
+fn dedupe_table(config: &NodeConfig) -> DedupeTable {
+    let table = DedupeTable::new(DEDUPE_TTL).with_capacity(config.dedupe_capacity);
+    match config.fuzzy_dedupe {
+        Some(fuzzy) => table.with_fuzzy(fuzzy),
+        None => table,
+    }
+}
+
+fn spot_cache(config: &NodeConfig) -> SpotCache {
+    let cache = SpotCache::new(config.spot_cache_size);
+    match config.spot_max_age {
+        Some(max_age) => cache.with_max_age(max_age),
+        None => cache,
+    }
 }
 
 async fn spawn_user_listener(
     addr: std::net::SocketAddr,
     state: NodeState,
+    user_options: Arc<UserOptions>,
     mut shutdown: broadcast::Receiver<()>,
 ) -> Result<JoinHandle<()>, NodeError> {
     let listener = TcpListener::bind(addr)
@@ -128,8 +184,9 @@ async fn spawn_user_listener(
                 }
                 accept_res = listener.accept() => {
                     match accept_res {
-                        Ok((stream, _addr)) => {
-                            let session = UserSession::new(stream, state.clone());
+                        Ok((stream, addr)) => {
+                            let session = UserSession::new(stream, state.clone(), user_options.clone())
+                                .with_remote_ip(addr.ip());
                             tokio::spawn(async move {
                                 if let Err(err) = session.run().await {
                                     tracing::warn!(?err, "user session terminated with error");
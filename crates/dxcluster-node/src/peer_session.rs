@@ -4,6 +4,7 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
 };
 
+use dxcluster_model::{Filter, RateClass};
 use dxcluster_types::NodeId;
 use dxcluster_wire::PeerFrame;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -11,14 +12,16 @@ use tokio::net::TcpStream;
 use tokio::sync::{RwLock, broadcast, mpsc};
 use tokio::time::{Duration, interval};
 
-use crate::config::PeerOptions;
-use crate::state::{NodeState, SpotAnnouncement};
+use crate::config::{PeerDialect, PeerOptions};
+use crate::state::{InsertOutcome, NodeState};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PeerSession {
     state: NodeState,
     options: PeerOptions,
     auth_token: Option<String>,
+    #[cfg(feature = "compat_dxspider")]
+    login: Option<String>,
 }
 
 impl PeerSession {
@@ -27,9 +30,19 @@ impl PeerSession {
             state,
             options,
             auth_token,
+            #[cfg(feature = "compat_dxspider")]
+            login: None,
         }
     }
 
+    /// Log in to the remote node as `callsign` before exchanging frames, as
+    /// DXSpider expects from the connecting side. Native links ignore this.
+    #[cfg(feature = "compat_dxspider")]
+    pub fn with_login(mut self, callsign: impl Into<String>) -> Self {
+        self.login = Some(callsign.into());
+        self
+    }
+
     pub async fn run(
         self,
         stream: TcpStream,
@@ -37,15 +50,26 @@ impl PeerSession {
     ) -> io::Result<()> {
         let (reader, writer) = tokio::io::split(stream);
         let mut reader = BufReader::new(reader);
-        let (tx, mut rx) = mpsc::unbounded_channel::<PeerFrame>();
+        let (line_tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let tx = FrameSink {
+            tx: line_tx,
+            dialect: self.options.dialect,
+            #[cfg(feature = "compat_dxspider")]
+            local: self.state.node_id().clone(),
+            #[cfg(feature = "compat_dxspider")]
+            max_hops: self.options.max_hops,
+        };
         let remote_id = Arc::new(RwLock::new(None::<NodeId>));
-        let auth_ok = Arc::new(AtomicBool::new(self.options.expected_auth_token.is_none()));
+        // DXSpider links become usable once the PC20/PC22 exchange completes.
+        let auth_ok = Arc::new(AtomicBool::new(
+            self.options.dialect == PeerDialect::Native
+                && self.options.expected_auth_token.is_none(),
+        ));
         let initial_sync_sent = Arc::new(AtomicBool::new(false));
 
         let writer_task = tokio::spawn(async move {
             let mut writer = writer;
-            while let Some(frame) = rx.recv().await {
-                let line = frame.to_line();
+            while let Some(line) = rx.recv().await {
                 if writer.write_all(line.as_bytes()).await.is_err() {
                     break;
                 }
@@ -55,26 +79,49 @@ impl PeerSession {
             }
         });
 
-        tx.send(PeerFrame::Hello {
-            node_id: self.state.node_id().clone(),
-            version: self.options.version.clone(),
-        })
-        .ok();
-        tx.send(PeerFrame::Capabilities {
-            values: self.options.capabilities.clone(),
-        })
-        .ok();
-        if let Some(token) = self.auth_token.clone() {
-            tx.send(PeerFrame::Auth { token }).ok();
+        match self.options.dialect {
+            PeerDialect::Native => {
+                tx.send(PeerFrame::Hello {
+                    node_id: self.state.node_id().clone(),
+                    version: self.options.version.clone(),
+                })
+                .ok();
+                tx.send(PeerFrame::Capabilities {
+                    values: self.options.capabilities.clone(),
+                })
+                .ok();
+                if let Some(token) = self.auth_token.clone() {
+                    tx.send(PeerFrame::Auth { token }).ok();
+                }
+            }
+            #[cfg(feature = "compat_dxspider")]
+            PeerDialect::DxSpider => {
+                // Inbound DXSpider nodes log in with a callsign only.
+                if self.login.is_none() && self.options.expected_auth_token.is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "DXSpider links cannot present an auth token",
+                    ));
+                }
+                let remote = crate::dxspider::open_link(
+                    &mut reader,
+                    &tx,
+                    self.login.clone(),
+                    &self.options.allowed_nodes,
+                )
+                .await?;
+                *remote_id.write().await = remote;
+            }
         }
 
         if auth_ok.load(Ordering::Relaxed) {
             initial_sync_sent.store(true, Ordering::Relaxed);
-            send_recent_spots(&self.state, &tx).await;
+            send_recent_spots(&self.state, &self.options.spot_filter, &tx).await;
         }
 
         let heartbeat_interval = self.options.heartbeat_interval.max(Duration::from_secs(1));
         let heartbeat_tx = tx.clone();
+        let heartbeat_state = self.state.clone();
         let mut heartbeat_shutdown = shutdown.resubscribe();
         tokio::spawn(async move {
             let mut ticker = interval(heartbeat_interval);
@@ -84,7 +131,8 @@ impl PeerSession {
                     _ = heartbeat_shutdown.recv() => break,
                     _ = ticker.tick() => {
                         counter += 1;
-                        if heartbeat_tx.send(PeerFrame::Heartbeat { nonce: counter.to_string() }).is_err() {
+                        let nonce = heartbeat_nonce(heartbeat_tx.dialect, &heartbeat_state, counter);
+                        if heartbeat_tx.send(PeerFrame::Heartbeat { nonce }).is_err() {
                             break;
                         }
                     }
@@ -94,8 +142,11 @@ impl PeerSession {
 
         let forward_tx = tx.clone();
         let mut spot_rx = self.state.subscribe_spots();
+        let mut announce_rx = self.state.subscribe_announcements();
         let forward_remote = remote_id.clone();
         let forward_auth = auth_ok.clone();
+        let forward_filter = self.options.spot_filter.clone();
+        let forward_countries = self.state.countries().clone();
         let mut forward_shutdown = shutdown.resubscribe();
         tokio::spawn(async move {
             loop {
@@ -106,16 +157,37 @@ impl PeerSession {
                             if !forward_auth.load(Ordering::Relaxed) {
                                 continue;
                             }
-                            if should_forward(&forward_remote, &announcement).await {
+                            if forward_filter
+                                .matches_with_dxcc(&announcement.spot, &forward_countries)
+                                && should_forward(&forward_remote, announcement.source.as_ref()).await
+                            {
                                 let mut spot = announcement.spot.clone();
                                 spot.hop = spot.hop.saturating_add(1);
-                                if forward_tx.send(PeerFrame::Spot { spot }).is_err() {
+                                if forward_tx.send(PeerFrame::Spot { spot: Box::new(spot) }).is_err() {
                                     break;
                                 }
                             }
                         }
                         Err(broadcast::error::RecvError::Closed) => break,
                         Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    },
+                    received = announce_rx.recv() => match received {
+                        Ok(event) => {
+                            if !forward_auth.load(Ordering::Relaxed)
+                                || !event.announcement.scope.is_networked()
+                                || !should_forward(&forward_remote, event.source.as_ref()).await
+                            {
+                                continue;
+                            }
+                            let mut announcement = event.announcement;
+                            announcement.hop = announcement.hop.saturating_add(1);
+                            let frame = PeerFrame::Announce { announcement: Box::new(announcement) };
+                            if forward_tx.send(frame).is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
                     }
                 }
             }
@@ -134,7 +206,22 @@ impl PeerSession {
                     if read == 0 {
                         break;
                     }
-                    if let Ok(frame) = PeerFrame::parse(&line) {
+                    let frame = match self.options.dialect {
+                        PeerDialect::Native => PeerFrame::parse(&line).ok(),
+                        #[cfg(feature = "compat_dxspider")]
+                        PeerDialect::DxSpider => {
+                            crate::dxspider::handle_line(
+                                &line,
+                                &state,
+                                &remote_id,
+                                &auth_ok,
+                                &initial_sync_sent,
+                                &tx,
+                                &self.options,
+                            ).await
+                        }
+                    };
+                    if let Some(frame) = frame {
                         handle_frame(
                             frame,
                             &state,
@@ -142,7 +229,7 @@ impl PeerSession {
                             &auth_ok,
                             &initial_sync_sent,
                             &tx,
-                            self.options.expected_auth_token.as_deref(),
+                            &self.options,
                         ).await?;
                     }
                 }
@@ -155,11 +242,50 @@ impl PeerSession {
     }
 }
 
-async fn should_forward(
-    remote_id: &Arc<RwLock<Option<NodeId>>>,
-    announcement: &SpotAnnouncement,
-) -> bool {
-    if let Some(source) = &announcement.source
+/// Outgoing half of a peer link. Frames are encoded for the link's dialect;
+/// frames without an equivalent in that dialect are silently dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct FrameSink {
+    tx: mpsc::UnboundedSender<String>,
+    dialect: PeerDialect,
+    #[cfg(feature = "compat_dxspider")]
+    local: NodeId,
+    #[cfg(feature = "compat_dxspider")]
+    max_hops: u32,
+}
+
+impl FrameSink {
+    pub(crate) fn send(&self, frame: PeerFrame) -> Result<(), mpsc::error::SendError<String>> {
+        let line = match self.dialect {
+            PeerDialect::Native => Some(frame.to_line()),
+            #[cfg(feature = "compat_dxspider")]
+            PeerDialect::DxSpider => crate::dxspider::encode(&frame, &self.local, self.max_hops),
+        };
+        match line {
+            Some(line) => self.send_line(line),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn send_line(&self, line: String) -> Result<(), mpsc::error::SendError<String>> {
+        self.tx.send(line)
+    }
+}
+
+#[cfg_attr(not(feature = "compat_dxspider"), allow(unused_variables))]
+fn heartbeat_nonce(dialect: PeerDialect, state: &NodeState, counter: u64) -> String {
+    match dialect {
+        PeerDialect::Native => counter.to_string(),
+        // DXSpider keepalives carry the local user count instead.
+        #[cfg(feature = "compat_dxspider")]
+        PeerDialect::DxSpider => state.online_users().len().to_string(),
+    }
+}
+
+/// Whether something received from `source` should go to the remote node,
+/// i.e. it did not come from there.
+async fn should_forward(remote_id: &Arc<RwLock<Option<NodeId>>>, source: Option<&NodeId>) -> bool {
+    if let Some(source) = source
         && let Some(remote) = remote_id.read().await.as_ref()
     {
         return source != remote;
@@ -167,12 +293,31 @@ async fn should_forward(
     true
 }
 
-async fn send_recent_spots(state: &NodeState, tx: &mpsc::UnboundedSender<PeerFrame>) {
+async fn send_recent_spots(state: &NodeState, filter: &Filter, tx: &FrameSink) {
     let recent_spots = state.recent(50).await;
-    for spot in recent_spots {
+    for spot in recent_spots
+        .into_iter()
+        .filter(|spot| filter.matches_with_dxcc(spot, state.countries()))
+    {
         let mut spot = spot.clone();
         spot.hop = spot.hop.saturating_add(1);
-        let _ = tx.send(PeerFrame::Spot { spot });
+        let _ = tx.send(PeerFrame::Spot {
+            spot: Box::new(spot),
+        });
+    }
+}
+
+/// Mark the link as authenticated and send the initial spot sync once.
+pub(crate) async fn establish(
+    state: &NodeState,
+    auth_ok: &AtomicBool,
+    initial_sync_sent: &AtomicBool,
+    tx: &FrameSink,
+    options: &PeerOptions,
+) {
+    auth_ok.store(true, Ordering::Relaxed);
+    if !initial_sync_sent.swap(true, Ordering::Relaxed) {
+        send_recent_spots(state, &options.spot_filter, tx).await;
     }
 }
 
@@ -182,8 +327,8 @@ async fn handle_frame(
     remote_id: &Arc<RwLock<Option<NodeId>>>,
     auth_ok: &Arc<AtomicBool>,
     initial_sync_sent: &Arc<AtomicBool>,
-    tx: &mpsc::UnboundedSender<PeerFrame>,
-    expected_auth_token: Option<&str>,
+    tx: &FrameSink,
+    options: &PeerOptions,
 ) -> io::Result<()> {
     match frame {
         PeerFrame::Hello { node_id, .. } => {
@@ -192,7 +337,7 @@ async fn handle_frame(
         }
         PeerFrame::Capabilities { .. } => {}
         PeerFrame::Auth { token } => {
-            if let Some(expected) = expected_auth_token
+            if let Some(expected) = options.expected_auth_token.as_deref()
                 && token != expected
             {
                 return Err(io::Error::new(
@@ -200,10 +345,7 @@ async fn handle_frame(
                     "auth failed",
                 ));
             }
-            auth_ok.store(true, Ordering::Relaxed);
-            if !initial_sync_sent.swap(true, Ordering::Relaxed) {
-                send_recent_spots(state, tx).await;
-            }
+            establish(state, auth_ok, initial_sync_sent, tx, options).await;
         }
         PeerFrame::Spot { mut spot } => {
             if !auth_ok.load(Ordering::Relaxed) {
@@ -217,11 +359,51 @@ async fn handle_frame(
             } else {
                 None
             };
+            if spot.hop > options.max_hops {
+                tracing::debug!(spot = ?spot.spot_id, hop = spot.hop, "dropping spot over hop limit");
+                return Ok(());
+            }
             if let Some(origin) = origin {
                 spot.origin = Some(origin);
             }
             let source = remote_id.read().await.clone();
-            state.insert_with_source(spot, source).await;
+            if let Some(peer) = &source
+                && let Err(limited) = state.check_peer_rate(peer, RateClass::Spot)
+            {
+                tracing::warn!(peer = %peer.0, %limited, "dropping spot from throttled peer");
+                return Ok(());
+            }
+            if let InsertOutcome::Rejected(reject) = state.insert_with_source(*spot, source).await {
+                tracing::debug!(code = reject.code(), %reject, "peer spot rejected by policy");
+            }
+        }
+        PeerFrame::Announce { mut announcement } => {
+            if !auth_ok.load(Ordering::Relaxed) {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "auth required",
+                ));
+            }
+            if !announcement.scope.is_networked() {
+                return Ok(());
+            }
+            if announcement.hop > options.max_hops {
+                tracing::debug!(from = %announcement.from, hop = announcement.hop, "dropping announcement over hop limit");
+                return Ok(());
+            }
+            let source = remote_id.read().await.clone();
+            if announcement.origin.is_none() {
+                announcement.origin = source.clone();
+            }
+            if let Some(peer) = &source
+                && let Err(limited) = state.check_peer_rate(peer, RateClass::Announce)
+            {
+                tracing::warn!(peer = %peer.0, %limited, "dropping announcement from throttled peer");
+                return Ok(());
+            }
+            if !state.announce(*announcement, source).await {
+                tracing::debug!("duplicate announcement from peer dropped");
+            }
         }
         PeerFrame::Heartbeat { .. } => {}
         PeerFrame::Ping { nonce } => {
@@ -1,31 +1,46 @@
+use std::collections::VecDeque;
 use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
 
-use dxcluster_model::{Filter, Spot};
+use dxcluster_model::{AnnounceScope, Announcement, FilterSet, RateClass, RateKey, Spot};
 use dxcluster_types::{Callsign, SpotId};
-use dxcluster_wire::format::{banner as format_banner, spot_user_line};
+use dxcluster_wire::format::{announce_user_line, banner as format_banner, spot_user_line};
+use dxcluster_wire::user::{
+    ClearTarget, format_login_prompt, format_password_prompt, format_welcome,
+};
 use dxcluster_wire::{ServerLine, UserCommand};
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::broadcast;
 
-use crate::state::NodeState;
+use crate::config::UserOptions;
+use crate::state::{ANNOUNCE_HISTORY, InsertOutcome, NodeState, OnlineUser};
 
-/// Default callsign applied to anonymous users until proper login flows
-/// are implemented.
-///
-/// The value must be valid even when the strict callsign feature is enabled
-/// (requires at least one digit), so we include a zero.
-const DEFAULT_CALLSIGN: &str = "N0CALL";
+/// Number of malformed callsigns a connection may enter before it is closed.
+const MAX_LOGIN_ATTEMPTS: usize = 3;
+
+/// Number of locally submitted spot and announcement ids remembered so their
+/// broadcast copy is not pushed back to the submitting user, who already
+/// received the echo.
+const OWN_SPOT_MEMORY: usize = 16;
+
+/// Announcements listed by `sh/ann` without a count.
+const DEFAULT_ANNOUNCE_COUNT: usize = 10;
 
 /// Telnet-style session for a single user connection.
 ///
-/// The session owns the TCP stream, reads user commands framed according to
-/// [`dxcluster_wire`] parsing rules, mutates shared [`NodeState`], and
-/// responds with formatted server lines. It keeps track of per-user filters
-/// and callsigns for spot attribution.
+/// The session owns the TCP stream, logs the user in with a validated
+/// callsign, reads user commands framed according to [`dxcluster_wire`]
+/// parsing rules, mutates shared [`NodeState`], and responds with formatted
+/// server lines. It keeps track of per-user filters and the logged-in
+/// callsign for spot attribution, and pushes live spots and announcements
+/// from the node's broadcast channels to the user as they arrive.
 pub struct UserSession<T> {
     stream: T,
     state: NodeState,
-    filter: Filter,
-    callsign: Callsign,
+    options: Arc<UserOptions>,
+    filters: FilterSet,
+    remote_ip: Option<IpAddr>,
 }
 
 impl<T> UserSession<T>
@@ -33,68 +48,242 @@ where
     T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
     /// Construct a new session wrapping a TCP stream and shared node state.
-    pub fn new(stream: T, state: NodeState) -> Self {
-        let callsign =
-            Callsign::parse_loose(DEFAULT_CALLSIGN).expect("default callsign should be valid");
+    pub fn new(stream: T, state: NodeState, options: Arc<UserOptions>) -> Self {
         Self {
             stream,
             state,
-            filter: Filter,
-            callsign,
+            options,
+            filters: FilterSet::default(),
+            remote_ip: None,
         }
     }
 
+    /// Record the client's address so submissions are also rate limited per
+    /// IP, not only per callsign.
+    pub fn with_remote_ip(mut self, ip: IpAddr) -> Self {
+        self.remote_ip = Some(ip);
+        self
+    }
+
     /// Run the session loop until the client disconnects or an IO error is
     /// encountered.
+    ///
+    /// The user is first asked for a callsign (and a password if the
+    /// callsign is registered); banned, malformed or already connected
+    /// callsigns are refused and the connection closed. Afterwards command
+    /// handling and live spot delivery share a single writer, so pushed
+    /// `DX de` lines never split a command response.
     pub async fn run(self) -> io::Result<()> {
         let UserSession {
             stream,
             state,
-            mut filter,
-            callsign,
+            options,
+            mut filters,
+            remote_ip,
         } = self;
 
         let (reader, mut writer) = tokio::io::split(stream);
-        let mut reader = BufReader::new(reader);
-        let mut line = String::new();
+        let mut lines = BufReader::new(reader).lines();
 
         write_line(
             &mut writer,
             ServerLine::Banner(format_banner(state.node_id().0.as_str())),
         )
         .await?;
+        let Some(online) = login(&mut lines, &mut writer, &state, &options).await? else {
+            return writer.shutdown().await;
+        };
+        let callsign = online.callsign().clone();
+        let is_sysop = options.sysops.contains(&callsign);
+        let rate_keys: Vec<RateKey> = std::iter::once(RateKey::Spotter(callsign.clone()))
+            .chain(remote_ip.map(RateKey::Ip))
+            .collect();
+
+        let mut spot_rx = state.subscribe_spots();
+        let mut own_spots = VecDeque::with_capacity(OWN_SPOT_MEMORY);
+        let mut announce_rx = state.subscribe_announcements();
+        let mut own_announcements = VecDeque::with_capacity(OWN_SPOT_MEMORY);
+
+        write_line(
+            &mut writer,
+            ServerLine::Banner(format_welcome(&callsign, state.node_id().0.as_str())),
+        )
+        .await?;
         write_line(&mut writer, ServerLine::Prompt).await?;
 
         loop {
-            line.clear();
-            let read = reader.read_line(&mut line).await?;
-            if read == 0 {
-                break;
-            }
+            tokio::select! {
+                read = lines.next_line() => {
+                    let Some(line) = read? else {
+                        break;
+                    };
 
-            match dxcluster_wire::parse::parse_line(&line) {
-                Ok(cmd) => {
-                    let responses = handle_command(&state, &mut filter, &callsign, cmd).await;
-                    for response in responses {
-                        write_line(&mut writer, response).await?;
+                    match dxcluster_wire::parse::parse_line(&line) {
+                        Ok(cmd) => {
+                            let class = match cmd {
+                                UserCommand::Dx { .. } => RateClass::Spot,
+                                UserCommand::Announce { .. } => RateClass::Announce,
+                                _ => RateClass::Command,
+                            };
+                            if let Err(limited) = state.check_user_rate(&rate_keys, class) {
+                                write_line(&mut writer, ServerLine::Message(format!("ERR: {limited}")))
+                                    .await?;
+                                write_line(&mut writer, ServerLine::Prompt).await?;
+                                continue;
+                            }
+                            let responses =
+                                handle_command(&state, &mut filters, &callsign, is_sysop, remote_ip, cmd).await;
+                            for response in responses {
+                                match &response {
+                                    ServerLine::Spot(spot) if class == RateClass::Spot => {
+                                        remember_own(&mut own_spots, spot.spot_id.clone());
+                                    }
+                                    ServerLine::Announce(announcement) if class == RateClass::Announce => {
+                                        remember_own(&mut own_announcements, announcement.id.clone());
+                                    }
+                                    _ => {}
+                                }
+                                write_line(&mut writer, response).await?;
+                            }
+                        }
+                        Err(err) => {
+                            write_line(&mut writer, ServerLine::Message(format!("ERR: {err}")))
+                                .await?;
+                        }
                     }
+
+                    write_line(&mut writer, ServerLine::Prompt).await?;
                 }
-                Err(err) => {
-                    write_line(&mut writer, ServerLine::Message(format!("ERR: {err}"))).await?;
+                received = spot_rx.recv() => match received {
+                    Ok(announcement) => {
+                        let spot = announcement.spot;
+                        if let Some(idx) = own_spots.iter().position(|id| *id == spot.spot_id) {
+                            own_spots.remove(idx);
+                            continue;
+                        }
+                        if filters.matches_spot_with_dxcc(&spot, state.countries()) {
+                            write_line(&mut writer, ServerLine::Spot(Box::new(spot))).await?;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "user session lagged behind live spots");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                received = announce_rx.recv() => match received {
+                    Ok(event) => {
+                        let announcement = event.announcement;
+                        if let Some(idx) = own_announcements.iter().position(|id| *id == announcement.id) {
+                            own_announcements.remove(idx);
+                            continue;
+                        }
+                        if is_sysop || announcement.scope != AnnounceScope::Sysop {
+                            write_line(&mut writer, ServerLine::Announce(Box::new(announcement))).await?;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "user session lagged behind announcements");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
-
-            write_line(&mut writer, ServerLine::Prompt).await?;
         }
 
         Ok(())
     }
 }
 
+/// Prompt for and validate a callsign, returning the online registration on
+/// success or `None` if the connection should be closed.
+async fn login<R, W>(
+    lines: &mut Lines<R>,
+    writer: &mut W,
+    state: &NodeState,
+    options: &UserOptions,
+) -> io::Result<Option<OnlineUser>>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut callsign = None;
+    for _ in 0..MAX_LOGIN_ATTEMPTS {
+        write_raw(writer, &format_login_prompt()).await?;
+        let Some(input) = lines.next_line().await? else {
+            return Ok(None);
+        };
+        match Callsign::parse_strict(&input) {
+            Ok(parsed) => {
+                callsign = Some(parsed);
+                break;
+            }
+            Err(err) => {
+                let message = format!("Sorry, {} is not a valid callsign: {err}", input.trim());
+                write_line(writer, ServerLine::Message(message)).await?;
+            }
+        }
+    }
+    let Some(callsign) = callsign else {
+        return Ok(None);
+    };
+
+    // Bans and passwords follow the operator, whatever prefix or suffix they
+    // log in with.
+    let base = callsign.base_call();
+    if options.banned.contains(&callsign) || options.banned.contains(&base) {
+        let message = format!("Sorry, {callsign} is not allowed to connect");
+        write_line(writer, ServerLine::Message(message)).await?;
+        return Ok(None);
+    }
+
+    if let Some(expected) = options
+        .passwords
+        .get(&callsign)
+        .or_else(|| options.passwords.get(&base))
+    {
+        write_raw(writer, &format_password_prompt()).await?;
+        let Some(password) = lines.next_line().await? else {
+            return Ok(None);
+        };
+        if !passwords_match(password.trim(), expected) {
+            write_line(writer, ServerLine::Message("Sorry, wrong password".into())).await?;
+            return Ok(None);
+        }
+    }
+
+    let Some(online) = state.register_user(&callsign) else {
+        let message = format!("Sorry, {callsign} is already connected");
+        write_line(writer, ServerLine::Message(message)).await?;
+        return Ok(None);
+    };
+    tracing::info!(%callsign, "user logged in");
+    Ok(Some(online))
+}
+
+/// Compare a password in time that depends only on the expected length, so
+/// the reply does not reveal how much of a guess was right.
+fn passwords_match(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    let mut diff = given.len() ^ expected.len();
+    for (index, byte) in expected.iter().enumerate() {
+        let guess = given.get(index).copied().unwrap_or(0);
+        diff |= usize::from(guess ^ byte);
+    }
+    std::hint::black_box(diff) == 0
+}
+
+fn remember_own(own: &mut VecDeque<SpotId>, id: SpotId) {
+    if own.len() == OWN_SPOT_MEMORY {
+        own.pop_front();
+    }
+    own.push_back(id);
+}
+
 async fn handle_command(
     state: &NodeState,
-    filter: &mut Filter,
+    filters: &mut FilterSet,
     callsign: &Callsign,
+    is_sysop: bool,
+    remote_ip: Option<IpAddr>,
     cmd: UserCommand,
 ) -> Vec<ServerLine> {
     match cmd {
@@ -104,13 +293,9 @@ async fn handle_command(
             comment,
         } => {
             let ts = time::OffsetDateTime::now_utc();
-            let spot_id = SpotId::hash_components(&[
-                dx.as_str().as_bytes(),
-                &frequency.0.to_be_bytes(),
-                &ts.unix_timestamp().to_be_bytes(),
-            ]);
+            let spot_id = SpotId::canonical(&dx, callsign, frequency, ts.unix_timestamp());
             let origin = Some(state.node_id().clone());
-            let spot = Spot::new_local(
+            let mut spot = Spot::new_local(
                 spot_id,
                 ts,
                 frequency,
@@ -119,33 +304,103 @@ async fn handle_command(
                 comment,
                 origin,
             );
-            state.insert(spot.clone()).await;
-            vec![ServerLine::Spot(spot)]
+            spot.spotter_ip = remote_ip;
+            spot.spotter_node = Some(state.node_id().clone());
+            match state.insert(spot.clone()).await {
+                InsertOutcome::Inserted => vec![ServerLine::Spot(Box::new(spot))],
+                InsertOutcome::Duplicate => {
+                    vec![ServerLine::Message("Duplicate spot, not sent".to_string())]
+                }
+                InsertOutcome::Rejected(reject) => vec![ServerLine::Message(format!(
+                    "ERR: spot rejected ({}): {reject}",
+                    reject.code()
+                ))],
+            }
+        }
+        UserCommand::Announce { scope, text } => {
+            let announcement = Announcement::new_local(
+                time::OffsetDateTime::now_utc(),
+                callsign.clone(),
+                text,
+                scope,
+                Some(state.node_id().clone()),
+            );
+            if state.announce(announcement.clone(), None).await {
+                vec![ServerLine::Announce(Box::new(announcement))]
+            } else {
+                vec![ServerLine::Message(
+                    "Duplicate announcement, not sent".to_string(),
+                )]
+            }
         }
         UserCommand::Show(show) => match show {
-            dxcluster_wire::user::ShowCommand::Dx => state
-                .recent(10)
+            dxcluster_wire::user::ShowCommand::Dx(query) => state
+                .query(&query, |spot| {
+                    filters.matches_spot_with_dxcc(spot, state.countries())
+                })
+                .await
+                .into_iter()
+                .map(|spot| ServerLine::Spot(Box::new(spot)))
+                .collect(),
+            dxcluster_wire::user::ShowCommand::Announce(count) => state
+                .recent_announcements(ANNOUNCE_HISTORY)
                 .await
                 .into_iter()
-                .filter(|spot| filter.matches(spot))
-                .map(ServerLine::Spot)
+                .filter(|announcement| is_sysop || announcement.scope != AnnounceScope::Sysop)
+                .take(count.unwrap_or(DEFAULT_ANNOUNCE_COUNT))
+                .map(|announcement| ServerLine::Announce(Box::new(announcement)))
                 .collect(),
             dxcluster_wire::user::ShowCommand::Filters => {
-                vec![ServerLine::Message(
-                    "Filters: accepting all spots".to_string(),
-                )]
+                if filters.is_empty() {
+                    return vec![ServerLine::Message(
+                        "Filters: accepting all spots".to_string(),
+                    )];
+                }
+                std::iter::once(ServerLine::Message("Filters:".to_string()))
+                    .chain(
+                        filters
+                            .rules()
+                            .map(|rule| ServerLine::Message(format!("  {rule}"))),
+                    )
+                    .collect()
             }
         },
+        UserCommand::Filter(rule) => {
+            let message = format!("Filter set: {rule}");
+            filters.push(rule);
+            vec![ServerLine::Message(message)]
+        }
+        UserCommand::ClearFilter { kind, target } => {
+            let (cleared, what) = match target {
+                ClearTarget::Slot(slot) => (
+                    filters.clear_slot(kind, slot),
+                    format!("{kind} filter {slot}"),
+                ),
+                ClearTarget::All => (filters.clear(kind), format!("all {kind} filters")),
+            };
+            let message = if cleared {
+                format!("Cleared {what}")
+            } else {
+                format!("No {what} to clear")
+            };
+            vec![ServerLine::Message(message)]
+        }
         UserCommand::Heartbeat => vec![ServerLine::Message("PONG".into())],
         UserCommand::Raw(raw) => vec![ServerLine::Message(format!("Unknown command: {raw}"))],
     }
 }
 
+async fn write_raw<W: AsyncWrite + Unpin>(writer: &mut W, text: &str) -> io::Result<()> {
+    writer.write_all(text.as_bytes()).await?;
+    writer.flush().await
+}
+
 async fn write_line<W: AsyncWrite + Unpin>(writer: &mut W, line: ServerLine) -> io::Result<()> {
     let rendered = match line {
         ServerLine::Banner(text) => text,
         ServerLine::Prompt => dxcluster_wire::user::format_prompt(),
         ServerLine::Spot(spot) => spot_user_line(&spot),
+        ServerLine::Announce(announcement) => announce_user_line(&announcement),
         ServerLine::Message(text) => text,
     };
 
@@ -0,0 +1,106 @@
+//! Ingest spots from a Reverse Beacon Network style skimmer feed.
+
+use std::io;
+
+use dxcluster_wire::skimmer::parse_skimmer_line;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+
+use crate::state::{InsertOutcome, NodeState};
+
+/// Longest line read from a feed. Skimmer spots are under 80 characters;
+/// longer lines are discarded rather than buffered.
+const MAX_LINE_LEN: usize = 1024;
+
+/// A logged-in connection to a skimmer feed. `DX de` lines it sends count
+/// towards the node's skimmer confirmations, and consolidated spots are
+/// inserted as spots from this node; banners, prompts and unparseable lines
+/// are skipped.
+#[derive(Debug, Clone)]
+pub struct SkimmerFeed {
+    state: NodeState,
+    login: String,
+}
+
+impl SkimmerFeed {
+    pub fn new(state: NodeState, login: impl Into<String>) -> Self {
+        Self {
+            state,
+            login: login.into(),
+        }
+    }
+
+    pub async fn run(
+        self,
+        stream: TcpStream,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        // Feeds prompt for a callsign without a newline; like DXSpider links
+        // we answer straight away rather than waiting for the prompt.
+        writer
+            .write_all(format!("{}\r\n", self.login).as_bytes())
+            .await?;
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                read = read_capped_line(&mut reader, &mut line) => {
+                    if read? == 0 {
+                        break;
+                    }
+                    // Telnet negotiation bytes are not valid UTF-8.
+                    let text = String::from_utf8_lossy(&line);
+                    let now = time::OffsetDateTime::now_utc();
+                    let Ok(report) = parse_skimmer_line(&text, now) else {
+                        continue;
+                    };
+                    let Some(mut spot) = self.state.confirm_skimmer_spot(report).await else {
+                        continue;
+                    };
+                    spot.origin = Some(self.state.node_id().clone());
+                    if let InsertOutcome::Rejected(reject) = self.state.insert(spot).await {
+                        tracing::debug!(%reject, "skimmer spot rejected");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read one line into `line`, returning the number of bytes consumed (0 at
+/// end of stream). A line longer than [`MAX_LINE_LEN`] is skipped up to its
+/// newline and `line` left empty.
+async fn read_capped_line<R>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut read = (&mut *reader)
+        .take(MAX_LINE_LEN as u64)
+        .read_until(b'\n', line)
+        .await?;
+    if read < MAX_LINE_LEN || line.last() == Some(&b'\n') {
+        return Ok(read);
+    }
+    line.clear();
+    loop {
+        let buf = reader.fill_buf().await?;
+        if buf.is_empty() {
+            return Ok(read);
+        }
+        let (len, done) = match buf.iter().position(|byte| *byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (buf.len(), false),
+        };
+        reader.consume(len);
+        read += len;
+        if done {
+            return Ok(read);
+        }
+    }
+}
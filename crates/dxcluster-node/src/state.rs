@@ -1,47 +1,248 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use dxcluster_model::{Spot, SpotCache};
-use dxcluster_types::NodeId;
+use dxcluster_model::{
+    AnnounceCache, Announcement, DedupeResult, DedupeStats, DedupeTable, DxQuery, Policy,
+    PolicyReject, RateClass, RateKey, RateLimited, RateLimiter, RateLimits, SharedCtyTable,
+    SkimmerAggregator, SkimmerRules, Spot, SpotCache,
+};
+use dxcluster_types::{Callsign, NodeId, Region};
 use tokio::sync::{Mutex, broadcast};
 
+use crate::config::DEFAULT_SPOT_CACHE_SIZE;
+
 #[derive(Debug, Clone)]
 pub struct SpotAnnouncement {
     pub spot: Spot,
     pub source: Option<NodeId>,
 }
 
+#[derive(Debug, Clone)]
+pub struct AnnouncementEvent {
+    pub announcement: Announcement,
+    pub source: Option<NodeId>,
+}
+
+/// Result of offering a spot to [`NodeState::insert`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertOutcome {
+    Inserted,
+    /// The spot id was already seen within [`DEDUPE_TTL`], or the fuzzy
+    /// window matched another spotter's report.
+    Duplicate,
+    /// The node's [`Policy`] refused the spot.
+    Rejected(PolicyReject),
+}
+
+/// How long a spot id is remembered for duplicate suppression.
+pub const DEDUPE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of recent announcements kept for `sh/ann`.
+pub const ANNOUNCE_HISTORY: usize = 100;
+
 #[derive(Debug, Clone)]
 pub struct NodeState {
     node_id: NodeId,
     cache: Arc<Mutex<SpotCache>>,
+    dedupe: Arc<Mutex<DedupeTable>>,
+    skimmers: Arc<Mutex<SkimmerAggregator>>,
+    announcements: Arc<Mutex<AnnounceCache>>,
+    policy: Arc<Policy>,
+    region: Option<Region>,
+    countries: SharedCtyTable,
+    user_limiter: Arc<std::sync::Mutex<RateLimiter>>,
+    peer_limiter: Arc<std::sync::Mutex<RateLimiter>>,
+    started: Instant,
     spot_tx: broadcast::Sender<SpotAnnouncement>,
+    announce_tx: broadcast::Sender<AnnouncementEvent>,
+    online: Arc<std::sync::Mutex<HashSet<Callsign>>>,
 }
 
 impl NodeState {
     pub fn new(node_id: NodeId) -> Self {
         let (spot_tx, _) = broadcast::channel(256);
+        let (announce_tx, _) = broadcast::channel(64);
         Self {
             node_id,
-            cache: Arc::new(Mutex::new(SpotCache::new(256))),
+            cache: Arc::new(Mutex::new(SpotCache::new(DEFAULT_SPOT_CACHE_SIZE))),
+            dedupe: Arc::new(Mutex::new(DedupeTable::new(DEDUPE_TTL))),
+            skimmers: Arc::new(Mutex::new(SkimmerAggregator::new(SkimmerRules::default()))),
+            announcements: Arc::new(Mutex::new(AnnounceCache::new(ANNOUNCE_HISTORY, DEDUPE_TTL))),
+            policy: Arc::new(Policy::default()),
+            region: None,
+            countries: SharedCtyTable::default(),
+            user_limiter: Arc::new(std::sync::Mutex::new(RateLimiter::default())),
+            peer_limiter: Arc::new(std::sync::Mutex::new(RateLimiter::new(
+                RateLimits::unlimited(),
+            ))),
+            started: Instant::now(),
             spot_tx,
+            announce_tx,
+            online: Arc::new(std::sync::Mutex::new(HashSet::new())),
         }
     }
 
-    pub async fn insert(&self, spot: Spot) {
-        self.insert_with_source(spot, None).await;
+    /// Replace the acceptance policy applied by [`NodeState::insert`].
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Band plan region for spots submitted on this node. Spots from peers
+    /// are only checked against the policy's own region.
+    pub fn with_region(mut self, region: Option<Region>) -> Self {
+        self.region = region;
+        self
+    }
+
+    pub fn with_countries(mut self, countries: SharedCtyTable) -> Self {
+        self.countries = countries;
+        self
+    }
+
+    /// Country table for DXCC lookups; reloads are seen immediately.
+    pub fn countries(&self) -> &SharedCtyTable {
+        &self.countries
+    }
+
+    /// Replace the spot cache, e.g. with a larger one or one with a maximum
+    /// age.
+    pub fn with_cache(mut self, cache: SpotCache) -> Self {
+        self.cache = Arc::new(Mutex::new(cache));
+        self
+    }
+
+    /// Replace the duplicate-suppression table, e.g. with a capacity or a
+    /// fuzzy window.
+    pub fn with_dedupe(mut self, dedupe: DedupeTable) -> Self {
+        self.dedupe = Arc::new(Mutex::new(dedupe));
+        self
+    }
+
+    pub fn with_skimmer_rules(mut self, rules: SkimmerRules) -> Self {
+        self.skimmers = Arc::new(Mutex::new(SkimmerAggregator::new(rules)));
+        self
+    }
+
+    /// Replace the rate limits applied to users and to peer links.
+    pub fn with_rate_limits(mut self, users: RateLimits, peers: RateLimits) -> Self {
+        self.user_limiter = Arc::new(std::sync::Mutex::new(RateLimiter::new(users)));
+        self.peer_limiter = Arc::new(std::sync::Mutex::new(RateLimiter::new(peers)));
+        self
+    }
+
+    /// Charge a user submission against the budgets of every key (typically
+    /// the callsign and remote IP).
+    pub fn check_user_rate(&self, keys: &[RateKey], class: RateClass) -> Result<(), RateLimited> {
+        let mut limiter = self
+            .user_limiter
+            .lock()
+            .expect("rate limiter lock poisoned");
+        limiter.check(keys, class, self.clock_ms())
+    }
+
+    /// Charge a frame received from `peer` against the peer budgets.
+    pub fn check_peer_rate(&self, peer: &NodeId, class: RateClass) -> Result<(), RateLimited> {
+        let mut limiter = self
+            .peer_limiter
+            .lock()
+            .expect("rate limiter lock poisoned");
+        limiter.check(&[RateKey::Peer(peer.clone())], class, self.clock_ms())
+    }
+
+    fn clock_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub async fn insert(&self, spot: Spot) -> InsertOutcome {
+        self.insert_with_source(spot, None).await
     }
 
-    pub async fn insert_with_source(&self, spot: Spot, source: Option<NodeId>) {
+    /// Store and announce `spot` if it passes the node's [`Policy`] and its
+    /// id was not seen within [`DEDUPE_TTL`], so copies arriving over several
+    /// links are only accepted once. Local spots (no `source`) must also fit
+    /// the node's region.
+    pub async fn insert_with_source(&self, spot: Spot, source: Option<NodeId>) -> InsertOutcome {
+        let now = time::OffsetDateTime::now_utc();
+        let accepted = match self.region {
+            Some(region) if source.is_none() => self.policy.accept_in(&spot, now, region),
+            _ => self.policy.accept(&spot, now),
+        };
+        if let Err(reject) = accepted {
+            return InsertOutcome::Rejected(reject);
+        }
+        let mut dedupe = self.dedupe.lock().await;
+        let seen_at = now.unix_timestamp().max(0) as u64;
+        if dedupe.check_spot(&spot, seen_at) == DedupeResult::Duplicate {
+            return InsertOutcome::Duplicate;
+        }
         let mut cache = self.cache.lock().await;
         cache.push(spot.clone());
+        cache.expire(now);
         let _ = self.spot_tx.send(SpotAnnouncement { spot, source });
+        InsertOutcome::Inserted
+    }
+
+    /// Count a skimmer feed report towards its station's confirmations,
+    /// returning the consolidated spot to [`NodeState::insert`] once enough
+    /// skimmers agree.
+    pub async fn confirm_skimmer_spot(&self, spot: Spot) -> Option<Spot> {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp().max(0) as u64;
+        self.skimmers.lock().await.offer(spot, now)
+    }
+
+    /// Store and broadcast `announcement` unless the same sender made the
+    /// same announcement within [`DEDUPE_TTL`]. Returns `false` for such
+    /// duplicates, including copies arriving over several links.
+    pub async fn announce(&self, announcement: Announcement, source: Option<NodeId>) -> bool {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp().max(0) as u64;
+        let mut announcements = self.announcements.lock().await;
+        if !announcements.push(announcement.clone(), now) {
+            return false;
+        }
+        let _ = self.announce_tx.send(AnnouncementEvent {
+            announcement,
+            source,
+        });
+        true
+    }
+
+    /// The `n` most recent announcements, newest first.
+    pub async fn recent_announcements(&self, n: usize) -> Vec<Announcement> {
+        let announcements = self.announcements.lock().await;
+        announcements.recent(n).cloned().collect()
+    }
+
+    pub fn subscribe_announcements(&self) -> broadcast::Receiver<AnnouncementEvent> {
+        self.announce_tx.subscribe()
+    }
+
+    /// Duplicate-suppression counters since the node started.
+    pub async fn dedupe_stats(&self) -> DedupeStats {
+        self.dedupe.lock().await.stats()
     }
 
     pub async fn recent(&self, n: usize) -> Vec<Spot> {
-        let cache = self.cache.lock().await;
+        let mut cache = self.cache.lock().await;
+        cache.expire(time::OffsetDateTime::now_utc());
         cache.recent(n).cloned().collect()
     }
 
+    /// Up to [`DxQuery::limit`] cached spots matching `query` and `accept`,
+    /// newest first.
+    pub async fn query(&self, query: &DxQuery, accept: impl Fn(&Spot) -> bool) -> Vec<Spot> {
+        let now = time::OffsetDateTime::now_utc();
+        let mut cache = self.cache.lock().await;
+        cache.expire(now);
+        cache
+            .query(query, now)
+            .filter(|spot| accept(spot))
+            .take(query.limit())
+            .cloned()
+            .collect()
+    }
+
     pub fn node_id(&self) -> &NodeId {
         &self.node_id
     }
@@ -49,4 +250,45 @@ impl NodeState {
     pub fn subscribe_spots(&self) -> broadcast::Receiver<SpotAnnouncement> {
         self.spot_tx.subscribe()
     }
+
+    /// Mark `callsign` as logged in, or return `None` if a session for it is
+    /// already active. The callsign is released when the returned guard is
+    /// dropped.
+    pub fn register_user(&self, callsign: &Callsign) -> Option<OnlineUser> {
+        let mut online = self.online.lock().expect("online users lock poisoned");
+        if !online.insert(callsign.clone()) {
+            return None;
+        }
+        Some(OnlineUser {
+            online: self.online.clone(),
+            callsign: callsign.clone(),
+        })
+    }
+
+    /// Callsigns of users currently logged in.
+    pub fn online_users(&self) -> Vec<Callsign> {
+        let online = self.online.lock().expect("online users lock poisoned");
+        online.iter().cloned().collect()
+    }
+}
+
+/// Registration of a logged-in user, released on drop.
+#[derive(Debug)]
+pub struct OnlineUser {
+    online: Arc<std::sync::Mutex<HashSet<Callsign>>>,
+    callsign: Callsign,
+}
+
+impl OnlineUser {
+    pub fn callsign(&self) -> &Callsign {
+        &self.callsign
+    }
+}
+
+impl Drop for OnlineUser {
+    fn drop(&mut self) {
+        if let Ok(mut online) = self.online.lock() {
+            online.remove(&self.callsign);
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use std::io;
 use std::time::Duration;
 
 use tokio::net::TcpStream;
@@ -5,8 +6,9 @@ use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
-use crate::config::{PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode};
+use crate::config::{PeerDialect, PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode};
 use crate::peer_session::PeerSession;
+use crate::skimmer::SkimmerFeed;
 use crate::state::NodeState;
 
 #[derive(Debug)]
@@ -24,29 +26,66 @@ impl UpstreamHandle {
     ) -> Vec<Self> {
         configs
             .iter()
-            .filter(|config| matches!(config.mode, UpstreamMode::Peer))
-            .map(|config| {
+            .filter_map(|config| {
+                let login = config
+                    .login_callsign
+                    .clone()
+                    .unwrap_or_else(|| state.node_id().0.clone());
+                let link = match config.mode {
+                    UpstreamMode::Telnet => return None,
+                    UpstreamMode::Peer => Link::Peer(Box::new(PeerSession::new(
+                        state.clone(),
+                        PeerOptions {
+                            dialect: PeerDialect::Native,
+                            ..peer_options.clone()
+                        },
+                        config.auth_token.clone(),
+                    ))),
+                    UpstreamMode::Skimmer => Link::Skimmer(SkimmerFeed::new(state.clone(), login)),
+                    #[cfg(feature = "compat_dxspider")]
+                    UpstreamMode::DxSpider => Link::Peer(Box::new(
+                        PeerSession::new(
+                            state.clone(),
+                            PeerOptions {
+                                dialect: PeerDialect::DxSpider,
+                                ..peer_options.clone()
+                            },
+                            None,
+                        )
+                        .with_login(login),
+                    )),
+                };
                 let addr = config.addr.clone();
-                let auth_token = config.auth_token.clone();
-                let state = state.clone();
-                let options = peer_options.clone();
                 let retry = retry.clone();
                 let mut shutdown_rx = shutdown.subscribe();
                 let task = tokio::spawn(async move {
-                    run_peer_connector(addr, state, options, auth_token, retry, &mut shutdown_rx)
-                        .await;
+                    run_connector(addr, link, retry, &mut shutdown_rx).await;
                 });
-                UpstreamHandle { task }
+                Some(UpstreamHandle { task })
             })
             .collect()
     }
 }
 
-async fn run_peer_connector(
+/// What runs over an upstream connection.
+#[derive(Debug, Clone)]
+enum Link {
+    Peer(Box<PeerSession>),
+    Skimmer(SkimmerFeed),
+}
+
+impl Link {
+    async fn run(&self, stream: TcpStream, shutdown: broadcast::Receiver<()>) -> io::Result<()> {
+        match self {
+            Link::Peer(session) => PeerSession::clone(session).run(stream, shutdown).await,
+            Link::Skimmer(feed) => feed.clone().run(stream, shutdown).await,
+        }
+    }
+}
+
+async fn run_connector(
     addr: String,
-    state: NodeState,
-    options: PeerOptions,
-    auth_token: Option<String>,
+    link: Link,
     retry: PeerRetryPolicy,
     shutdown: &mut broadcast::Receiver<()>,
 ) {
@@ -59,14 +98,13 @@ async fn run_peer_connector(
         match connect {
             Ok(stream) => {
                 attempt = 0;
-                let session = PeerSession::new(state.clone(), options.clone(), auth_token.clone());
-                if let Err(err) = session.run(stream, shutdown.resubscribe()).await {
-                    tracing::warn!(?err, addr, "peer session ended");
+                if let Err(err) = link.run(stream, shutdown.resubscribe()).await {
+                    tracing::warn!(?err, addr, "upstream session ended");
                 }
             }
             Err(err) => {
                 attempt += 1;
-                tracing::warn!(?err, addr, attempt, "peer connect failed");
+                tracing::warn!(?err, addr, attempt, "upstream connect failed");
             }
         }
 
@@ -0,0 +1,450 @@
+#![cfg(feature = "compat_dxspider")]
+
+use std::collections::HashSet;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use dxcluster_model::Spot;
+use dxcluster_node::{
+    Node, NodeConfig, PeerDialect, PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode,
+};
+use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use dxcluster_wire::PeerFrame;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::time::{sleep, timeout};
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind temp port");
+    let addr = listener.local_addr().expect("addr");
+    drop(listener);
+    addr
+}
+
+fn make_spot(dx: &str) -> Spot {
+    let ts = time::OffsetDateTime::now_utc().replace_second(0).unwrap();
+    Spot::new_local(
+        SpotId::hash_components(&[dx.as_bytes()]),
+        ts,
+        FrequencyHz(14_025_000),
+        Callsign::parse_loose(dx).expect("dx callsign"),
+        Callsign::parse_loose("N0CALL").expect("spotter callsign"),
+        "from node",
+        None,
+    )
+}
+
+/// Current UTC date and time in DXSpider's `5-Mar-2024` / `1253Z` format.
+fn pc_now() -> (String, String) {
+    let now = time::OffsetDateTime::now_utc();
+    let month = &format!("{:?}", now.month())[..3];
+    (
+        format!("{}-{month}-{}", now.day(), now.year()),
+        format!("{:02}{:02}Z", now.hour(), now.minute()),
+    )
+}
+
+/// Read lines until one starts with `prefix`, skipping keepalives.
+async fn expect_frame(reader: &mut BufReader<OwnedReadHalf>, prefix: &str) -> String {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let mut line = String::new();
+            assert_ne!(reader.read_line(&mut line).await.expect("read"), 0);
+            if line.starts_with(prefix) {
+                return line.trim_end().to_string();
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("expected {prefix} frame"))
+}
+
+async fn send(writer: &mut OwnedWriteHalf, line: &str) {
+    writer
+        .write_all(format!("{line}\n").as_bytes())
+        .await
+        .expect("write frame");
+}
+
+async fn wait_for_dx(handle: &dxcluster_node::NodeHandle, dx: &str) -> Spot {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let spots = handle.recent_spots(20).await;
+            if let Some(spot) = spots.into_iter().find(|spot| spot.dx.as_str() == dx) {
+                return spot;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("spot should arrive")
+}
+
+#[tokio::test]
+async fn upstream_link_to_dxspider_node() {
+    let fake = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind fake node");
+    let fake_addr = fake.local_addr().unwrap();
+
+    let config = NodeConfig {
+        peer_retry: PeerRetryPolicy {
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(200),
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("GB7AAA".into()))
+    };
+    let handle = Node::builder(config)
+        .with_upstream(UpstreamConfig {
+            addr: fake_addr.to_string(),
+            mode: UpstreamMode::DxSpider,
+            login_callsign: None,
+            auth_token: None,
+        })
+        .spawn()
+        .await
+        .expect("spawn node");
+
+    let (stream, _) = fake.accept().await.expect("accept node");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut login = String::new();
+    reader.read_line(&mut login).await.unwrap();
+    assert_eq!(login.trim(), "GB7AAA");
+
+    send(
+        &mut writer,
+        "PC18^DXSpider Version: 1.57 Build: 500 pc9x^5457^~",
+    )
+    .await;
+    let config = expect_frame(&mut reader, "PC92^").await;
+    assert!(config.starts_with("PC92^GB7AAA^"), "unexpected {config}");
+    expect_frame(&mut reader, "PC20^").await;
+
+    send(&mut writer, "PC92^GB7DJK^67445^C^5GB7DJK:5457^H99^").await;
+    send(&mut writer, "PC22^").await;
+
+    let (date, time) = pc_now();
+
+    send(
+        &mut writer,
+        &format!("PC61^7025.0^K1ABC^{date}^{time}^cq dx^G4XYZ^GB7DJK^192.0.2.1^H96^~"),
+    )
+    .await;
+    let spot = wait_for_dx(&handle, "K1ABC").await;
+    assert_eq!(spot.origin, Some(NodeId("GB7DJK".into())));
+    assert_eq!(spot.freq, FrequencyHz(7_025_000));
+
+    send(&mut writer, "PC51^GB7AAA^GB7DJK^1^").await;
+    assert_eq!(
+        expect_frame(&mut reader, "PC51^").await,
+        "PC51^GB7DJK^GB7AAA^0^~"
+    );
+
+    handle.inject_spot(make_spot("W1AW")).await;
+    let forwarded = expect_frame(&mut reader, "PC11^").await;
+    assert!(
+        forwarded.starts_with("PC11^14025.0^W1AW^"),
+        "unexpected {forwarded}"
+    );
+    // One hop out of the default budget of 16.
+    assert!(forwarded.ends_with("^N0CALL^GB7AAA^H15^~"));
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn dxspider_node_connects_to_peer_listener() {
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            dialect: PeerDialect::DxSpider,
+            allowed_nodes: HashSet::from([Callsign::parse_loose("GB7DJK").unwrap()]),
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("GB7AAA".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    handle.inject_spot(make_spot("JA1XYZ")).await;
+
+    let stream = TcpStream::connect(peer_listen).await.expect("connect");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    expect_frame(&mut reader, "login:").await;
+    send(&mut writer, "gb7djk").await;
+    let init = expect_frame(&mut reader, "PC18^").await;
+    assert!(init.contains("pc9x"));
+
+    send(&mut writer, "PC92^GB7DJK^67445^C^5GB7DJK:5457^H99^").await;
+    send(&mut writer, "PC20^").await;
+    expect_frame(&mut reader, "PC92^GB7AAA^").await;
+    expect_frame(&mut reader, "PC22^").await;
+
+    // The initial sync follows the handshake.
+    let synced = expect_frame(&mut reader, "PC11^").await;
+    assert!(synced.contains("^JA1XYZ^"), "unexpected {synced}");
+
+    let (date, time) = pc_now();
+
+    send(
+        &mut writer,
+        &format!("PC11^21074.0^VK2ABC^{date}^{time}^ft8^G4XYZ^GB7DJK^H10^~"),
+    )
+    .await;
+    let spot = wait_for_dx(&handle, "VK2ABC").await;
+    assert_eq!(spot.hop, 6);
+
+    // A counter above the node's hop budget counts as a fresh spot; an
+    // exhausted one is dropped.
+    send(
+        &mut writer,
+        &format!("PC11^14074.0^ZL1AAA^{date}^{time}^ft8^G4XYZ^GB7DJK^H0^~"),
+    )
+    .await;
+    send(
+        &mut writer,
+        &format!("PC11^14074.0^ZL2BBB^{date}^{time}^ft8^G4XYZ^GB7DJK^H27^~"),
+    )
+    .await;
+    assert_eq!(wait_for_dx(&handle, "ZL2BBB").await.hop, 0);
+    let spots = handle.recent_spots(20).await;
+    assert!(spots.iter().all(|spot| spot.dx.as_str() != "ZL1AAA"));
+
+    handle.shutdown().await;
+}
+
+/// Read native frames until a spot of `dx` arrives.
+async fn expect_native_spot(reader: &mut BufReader<OwnedReadHalf>, dx: &str) -> Spot {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let mut line = String::new();
+            assert_ne!(reader.read_line(&mut line).await.expect("read"), 0);
+            if let Ok(PeerFrame::Spot { spot }) = PeerFrame::parse(line.trim_end())
+                && spot.dx.as_str() == dx
+            {
+                return *spot;
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("expected spot of {dx}"))
+}
+
+#[tokio::test]
+async fn hop_counts_carry_across_dialects() {
+    let native = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind native node");
+    let native_addr = native.local_addr().unwrap();
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            dialect: PeerDialect::DxSpider,
+            allowed_nodes: HashSet::from([Callsign::parse_loose("GB7DJK").unwrap()]),
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("GB7AAA".into()))
+    };
+    let handle = Node::builder(config)
+        .with_upstream(UpstreamConfig {
+            addr: native_addr.to_string(),
+            mode: UpstreamMode::Peer,
+            login_callsign: None,
+            auth_token: None,
+        })
+        .spawn()
+        .await
+        .expect("spawn node");
+
+    let (stream, _) = native.accept().await.expect("accept node");
+    let (native_reader, mut native_writer) = stream.into_split();
+    let mut native_reader = BufReader::new(native_reader);
+    expect_frame(&mut native_reader, "CAPS|").await;
+    send(&mut native_writer, "HELLO|NATIVE|1").await;
+
+    let stream = TcpStream::connect(peer_listen).await.expect("connect");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    expect_frame(&mut reader, "login:").await;
+    send(&mut writer, "GB7DJK").await;
+    expect_frame(&mut reader, "PC18^").await;
+    send(&mut writer, "PC92^GB7DJK^67445^C^5GB7DJK:5457^H99^").await;
+    send(&mut writer, "PC20^").await;
+    expect_frame(&mut reader, "PC22^").await;
+
+    // DXSpider -> native: H10 under a budget of 16 is hop 6, one more on
+    // the way out.
+    let (date, time) = pc_now();
+    send(
+        &mut writer,
+        &format!("PC11^21074.0^VK2ABC^{date}^{time}^ft8^G4XYZ^GB7DJK^H10^~"),
+    )
+    .await;
+    assert_eq!(
+        expect_native_spot(&mut native_reader, "VK2ABC").await.hop,
+        7
+    );
+
+    // Native -> DXSpider: hop 3 goes out as hop 4, i.e. H12.
+    let mut spot = make_spot("JA1XYZ");
+    spot.hop = 3;
+    send(
+        &mut native_writer,
+        &PeerFrame::Spot {
+            spot: Box::new(spot),
+        }
+        .to_line(),
+    )
+    .await;
+    let forwarded = timeout(Duration::from_secs(3), async {
+        loop {
+            let line = expect_frame(&mut reader, "PC11^").await;
+            if line.contains("^JA1XYZ^") {
+                return line;
+            }
+        }
+    })
+    .await
+    .expect("spot should be relayed");
+    assert!(
+        forwarded.ends_with("^NATIVE^H12^~"),
+        "unexpected {forwarded}"
+    );
+
+    handle.shutdown().await;
+}
+
+/// Read user lines until one contains `text`.
+async fn expect_user_line(reader: &mut BufReader<OwnedReadHalf>, text: &str) {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let mut line = String::new();
+            assert_ne!(reader.read_line(&mut line).await.expect("read"), 0);
+            if line.contains(text) {
+                return;
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("expected {text:?}"))
+}
+
+#[tokio::test]
+async fn pc93_announcements_reach_local_users() {
+    let user_listen = ephemeral_addr();
+    let peer_listen = ephemeral_addr();
+    let mut config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            dialect: PeerDialect::DxSpider,
+            allowed_nodes: HashSet::from([Callsign::parse_loose("GB7DJK").unwrap()]),
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(user_listen, NodeId("GB7AAA".into()))
+    };
+    config
+        .user_options
+        .sysops
+        .insert(Callsign::parse_strict("G4SYS").unwrap());
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let user = TcpStream::connect(user_listen).await.expect("connect user");
+    let (user_reader, mut user_writer) = user.into_split();
+    let mut user_reader = BufReader::new(user_reader);
+    send(&mut user_writer, "G4SYS").await;
+    expect_user_line(&mut user_reader, "G4SYS").await;
+
+    let stream = TcpStream::connect(peer_listen).await.expect("connect");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    expect_frame(&mut reader, "login:").await;
+    send(&mut writer, "GB7DJK").await;
+    expect_frame(&mut reader, "PC18^").await;
+    send(&mut writer, "PC92^GB7DJK^67445^C^5GB7DJK:5457^H99^").await;
+    send(&mut writer, "PC20^").await;
+    expect_frame(&mut reader, "PC22^").await;
+
+    send(
+        &mut writer,
+        "PC93^GB7DJK^43210^*^G1TLH^GB7DJK^qrv 6m tonight^H10^~",
+    )
+    .await;
+    expect_user_line(&mut user_reader, "To ALL de G1TLH: qrv 6m tonight").await;
+
+    send(
+        &mut writer,
+        "PC93^GB7DJK^43211^SYSOP^G1TLH^GB7DJK^link down^H10^~",
+    )
+    .await;
+    expect_user_line(&mut user_reader, "To SYSOP de G1TLH: link down").await;
+
+    handle.shutdown().await;
+}
+
+/// Read until the node closes the link, failing on any `PCxx` frame.
+async fn expect_refused(reader: &mut BufReader<OwnedReadHalf>) {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            assert!(!line.starts_with("PC"), "unexpected {line}");
+        }
+    })
+    .await
+    .expect("link should be closed");
+}
+
+#[tokio::test]
+async fn unknown_dxspider_nodes_are_refused() {
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            dialect: PeerDialect::DxSpider,
+            allowed_nodes: HashSet::from([Callsign::parse_loose("GB7DJK").unwrap()]),
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("GB7AAA".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let stream = TcpStream::connect(peer_listen).await.expect("connect");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    expect_frame(&mut reader, "login:").await;
+    send(&mut writer, "GB7XXX").await;
+    expect_refused(&mut reader).await;
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn dxspider_links_refused_when_a_token_is_expected() {
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            dialect: PeerDialect::DxSpider,
+            allowed_nodes: HashSet::from([Callsign::parse_loose("GB7DJK").unwrap()]),
+            expected_auth_token: Some("secret".to_string()),
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("GB7AAA".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let stream = TcpStream::connect(peer_listen).await.expect("connect");
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    send(&mut writer, "GB7DJK").await;
+    expect_refused(&mut reader).await;
+
+    handle.shutdown().await;
+}
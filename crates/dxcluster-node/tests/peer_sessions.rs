@@ -1,11 +1,11 @@
 use std::net::{SocketAddr, TcpListener};
 use std::time::Duration;
 
-use dxcluster_model::Spot;
+use dxcluster_model::{AnnounceScope, Announcement, FuzzyWindow, Spot};
 use dxcluster_node::{
     Node, NodeConfig, PeerOptions, PeerRetryPolicy, UpstreamConfig, UpstreamMode,
 };
-use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use dxcluster_types::{Callsign, FrequencyHz, NodeId, Region, SpotId};
 use tokio::time::{sleep, timeout};
 
 fn ephemeral_addr() -> SocketAddr {
@@ -23,16 +23,15 @@ fn make_spot(origin: &str, dx: &str, comment: &str) -> Spot {
         comment.as_bytes(),
         &ts.unix_timestamp().to_be_bytes(),
     ]);
-    Spot {
+    Spot::new_local(
         spot_id,
         ts,
-        freq: FrequencyHz(14_074_000),
-        dx: Callsign::parse_loose(dx).expect("dx callsign"),
-        spotter: Callsign::parse_loose("N0CALL").expect("spotter callsign"),
-        comment: comment.to_string(),
-        origin: Some(NodeId(origin.to_string())),
-        hop: 0,
-    }
+        FrequencyHz(14_074_000),
+        Callsign::parse_loose(dx).expect("dx callsign"),
+        Callsign::parse_loose("N0CALL").expect("spotter callsign"),
+        comment,
+        Some(NodeId(origin.to_string())),
+    )
 }
 
 async fn wait_for_dx(handle: &dxcluster_node::NodeHandle, dx: &str) {
@@ -65,20 +64,17 @@ async fn peer_links_exchange_spots() {
     };
 
     let config_b = NodeConfig {
-        user_listen: node_b_addr,
         peer_listen: Some(peer_listen_b),
-        node_id: NodeId("node-b".into()),
         peer_options: peer_options.clone(),
         peer_retry: peer_retry.clone(),
+        ..NodeConfig::new(node_b_addr, NodeId("node-b".into()))
     };
     let handle_b = Node::builder(config_b).spawn().await.expect("spawn B");
 
     let config_a = NodeConfig {
-        user_listen: node_a_addr,
-        peer_listen: None,
-        node_id: NodeId("node-a".into()),
         peer_options: peer_options.clone(),
         peer_retry: peer_retry.clone(),
+        ..NodeConfig::new(node_a_addr, NodeId("node-a".into()))
     };
     let handle_a = Node::builder(config_a)
         .with_upstream(UpstreamConfig {
@@ -104,3 +100,297 @@ async fn peer_links_exchange_spots() {
     handle_a.shutdown().await;
     handle_b.shutdown().await;
 }
+
+async fn count_dx(handle: &dxcluster_node::NodeHandle, dx: &str) -> usize {
+    let spots = handle.recent_spots(100).await;
+    spots.iter().filter(|spot| spot.dx.as_str() == dx).count()
+}
+
+#[tokio::test]
+async fn meshed_nodes_suppress_duplicate_spots() {
+    let peer_options = PeerOptions {
+        heartbeat_interval: Duration::from_millis(200),
+        ..PeerOptions::default()
+    };
+    let peer_retry = PeerRetryPolicy {
+        base_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(200),
+    };
+    let names = ["node-a", "node-b", "node-c"];
+    let peer_addrs: Vec<SocketAddr> = names.iter().map(|_| ephemeral_addr()).collect();
+
+    // Link the three nodes in a ring so every spot has two paths home.
+    let mut handles = Vec::new();
+    for (idx, name) in names.iter().enumerate() {
+        let config = NodeConfig {
+            peer_listen: Some(peer_addrs[idx]),
+            peer_options: peer_options.clone(),
+            peer_retry: peer_retry.clone(),
+            ..NodeConfig::new(ephemeral_addr(), NodeId((*name).into()))
+        };
+        let handle = Node::builder(config)
+            .with_upstream(UpstreamConfig {
+                addr: peer_addrs[(idx + 1) % names.len()].to_string(),
+                mode: UpstreamMode::Peer,
+                login_callsign: None,
+                auth_token: None,
+            })
+            .spawn()
+            .await
+            .expect("spawn node");
+        handles.push(handle);
+    }
+    sleep(Duration::from_millis(300)).await;
+
+    handles[0]
+        .inject_spot(make_spot("node-a", "K1ABC", "meshed"))
+        .await;
+    for handle in &handles {
+        wait_for_dx(handle, "K1ABC").await;
+    }
+    sleep(Duration::from_millis(300)).await;
+    for handle in &handles {
+        assert_eq!(count_dx(handle, "K1ABC").await, 1);
+    }
+
+    for handle in handles {
+        handle.shutdown().await;
+    }
+}
+
+#[tokio::test]
+async fn peer_spots_over_hop_limit_are_dropped() {
+    use dxcluster_wire::PeerFrame;
+    use tokio::io::AsyncWriteExt;
+
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            max_hops: 3,
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let mut stream = tokio::net::TcpStream::connect(peer_listen)
+        .await
+        .expect("connect peer");
+    let mut far = make_spot("node-x", "W1AW", "too far");
+    far.hop = 4;
+    let mut near = make_spot("node-x", "K2XYZ", "close enough");
+    near.hop = 3;
+    let hello = PeerFrame::Hello {
+        node_id: NodeId("node-x".into()),
+        version: "1".into(),
+    };
+    for frame in [
+        hello,
+        PeerFrame::Spot {
+            spot: Box::new(far),
+        },
+        PeerFrame::Spot {
+            spot: Box::new(near),
+        },
+    ] {
+        stream
+            .write_all(format!("{}\n", frame.to_line()).as_bytes())
+            .await
+            .expect("write frame");
+    }
+
+    wait_for_dx(&handle, "K2XYZ").await;
+    assert_eq!(count_dx(&handle, "W1AW").await, 0);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn node_region_does_not_apply_to_relayed_spots() {
+    use dxcluster_wire::PeerFrame;
+    use tokio::io::AsyncWriteExt;
+
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        region: Some(Region::R1),
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let mut stream = tokio::net::TcpStream::connect(peer_listen)
+        .await
+        .expect("connect peer");
+    // 7.250 MHz is only allocated outside region 1.
+    let mut phone = make_spot("node-x", "K1ABC", "region 2 phone");
+    phone.freq = FrequencyHz(7_250_000);
+    let hello = PeerFrame::Hello {
+        node_id: NodeId("node-x".into()),
+        version: "1".into(),
+    };
+    for frame in [
+        hello,
+        PeerFrame::Spot {
+            spot: Box::new(phone),
+        },
+    ] {
+        stream
+            .write_all(format!("{}\n", frame.to_line()).as_bytes())
+            .await
+            .expect("write frame");
+    }
+
+    wait_for_dx(&handle, "K1ABC").await;
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn fuzzy_dedupe_drops_other_spotters_nearby() {
+    let config = NodeConfig {
+        fuzzy_dedupe: Some(FuzzyWindow {
+            freq_hz: 1_000,
+            window: Duration::from_secs(300),
+        }),
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let first = make_spot("node-a", "K1ABC", "first");
+    let mut nearby = make_spot("node-b", "K1ABC", "nearby");
+    nearby.spotter = Callsign::parse_loose("G4ABC").expect("spotter callsign");
+    nearby.freq = FrequencyHz(14_074_500);
+    handle.inject_spot(first).await;
+    handle.inject_spot(nearby).await;
+
+    assert_eq!(count_dx(&handle, "K1ABC").await, 1);
+    let stats = handle.dedupe_stats().await;
+    assert_eq!((stats.fresh, stats.duplicate), (1, 1));
+
+    handle.shutdown().await;
+}
+
+async fn wait_for_announcement(handle: &dxcluster_node::NodeHandle, text: &str) {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let announcements = handle.recent_announcements(20).await;
+            if announcements.iter().any(|ann| ann.text == text) {
+                break;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("announcement should propagate");
+}
+
+fn make_announcement(from: &str, text: &str, scope: AnnounceScope) -> Announcement {
+    Announcement::new_local(
+        time::OffsetDateTime::now_utc(),
+        Callsign::parse_loose(from).expect("callsign"),
+        text,
+        scope,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn full_announcements_cross_peer_links() {
+    let peer_listen_b = ephemeral_addr();
+    let peer_retry = PeerRetryPolicy {
+        base_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(200),
+    };
+    let config_b = NodeConfig {
+        peer_listen: Some(peer_listen_b),
+        peer_retry: peer_retry.clone(),
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-b".into()))
+    };
+    let handle_b = Node::builder(config_b).spawn().await.expect("spawn B");
+    let config_a = NodeConfig {
+        peer_retry,
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle_a = Node::builder(config_a)
+        .with_upstream(UpstreamConfig {
+            addr: peer_listen_b.to_string(),
+            mode: UpstreamMode::Peer,
+            login_callsign: None,
+            auth_token: None,
+        })
+        .spawn()
+        .await
+        .expect("spawn A");
+
+    // Spots flow once the link is up; use one to wait for it.
+    handle_a
+        .inject_spot(make_spot("node-a", "K1ABC", "link up"))
+        .await;
+    wait_for_dx(&handle_b, "K1ABC").await;
+
+    let local = make_announcement("G4ABC", "local only", AnnounceScope::Local);
+    let full = make_announcement("G4ABC", "contest this weekend", AnnounceScope::Full);
+    assert!(handle_a.inject_announcement(local).await);
+    assert!(handle_a.inject_announcement(full).await);
+    wait_for_announcement(&handle_b, "contest this weekend").await;
+
+    let received = handle_b.recent_announcements(20).await;
+    assert_eq!(received.len(), 1, "{received:?}");
+    assert_eq!(received[0].scope, AnnounceScope::Full);
+    assert_eq!(received[0].hop, 1);
+    assert_eq!(received[0].origin, Some(NodeId("node-a".into())));
+
+    // B does not send it back to A.
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(handle_a.recent_announcements(20).await.len(), 2);
+
+    handle_a.shutdown().await;
+    handle_b.shutdown().await;
+}
+
+#[tokio::test]
+async fn peer_announcements_over_hop_limit_are_dropped() {
+    use dxcluster_wire::PeerFrame;
+    use tokio::io::AsyncWriteExt;
+
+    let peer_listen = ephemeral_addr();
+    let config = NodeConfig {
+        peer_listen: Some(peer_listen),
+        peer_options: PeerOptions {
+            max_hops: 3,
+            ..PeerOptions::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let mut stream = tokio::net::TcpStream::connect(peer_listen)
+        .await
+        .expect("connect peer");
+    let mut far = make_announcement("G4ABC", "too far", AnnounceScope::Full);
+    far.hop = 4;
+    let mut near = make_announcement("G4ABC", "close enough", AnnounceScope::Full);
+    near.hop = 3;
+    let local = make_announcement("G4ABC", "not for peers", AnnounceScope::Local);
+    let hello = PeerFrame::Hello {
+        node_id: NodeId("node-x".into()),
+        version: "1".into(),
+    };
+    let frames = [far, local, near].map(|announcement| PeerFrame::Announce {
+        announcement: Box::new(announcement),
+    });
+    for frame in std::iter::once(hello).chain(frames) {
+        stream
+            .write_all(format!("{}\n", frame.to_line()).as_bytes())
+            .await
+            .expect("write frame");
+    }
+
+    wait_for_announcement(&handle, "close enough").await;
+    let received = handle.recent_announcements(20).await;
+    assert_eq!(received.len(), 1, "{received:?}");
+    assert_eq!(received[0].origin, Some(NodeId("node-x".into())));
+
+    handle.shutdown().await;
+}
@@ -0,0 +1,202 @@
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use dxcluster_model::{SkimmerRules, Spot, SpotKind};
+use dxcluster_node::{Node, NodeConfig, UpstreamConfig, UpstreamMode};
+use dxcluster_types::{Mode, NodeId};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+
+/// Greeting of a skimmer feed up to the callsign prompt, which has no
+/// newline.
+const GREETING: &str = "\r\nWelcome to the Reverse Beacon Network.\r\n\r\nPlease enter your call: ";
+
+/// Captured after login; times are rewritten to the current minute.
+const TRANSCRIPT: &[&str] = &[
+    "\r\nHello N0CALL, this is RELAY node.\r\n",
+    "N0CALL de RELAY 05-Mar-2024 1234Z >\r\n",
+    "DX de KM3T-#:    14025.0  K1ABC          CW    18 dB  24 WPM  CQ      {time}\x07\x07\r\n",
+    "DX de W3OA-#:    14074.0  DL1XYZ         FT8  -12 dB          CQ      {time}\x07\x07\r\n",
+    "DX de W3OA-#:    14025.1  K1ABC          CW    25 dB  24 WPM  CQ      {time}\x07\x07\r\n",
+    "WWV de VE7CC <18>:   SFI=150, A=5, K=1, No Storms -> No Storms\r\n",
+    "DX de DK9IP-#:   14085.5  OH2XX          RTTY   7 dB  45 BPS  CQ      {time}\x07\x07\r\n",
+    "DX de KM3T-#:    14025.0  K1ABC          CW    20 dB  24 WPM  CQ      {time}\x07\x07\r\n",
+];
+
+fn ephemeral_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind temp port");
+    let addr = listener.local_addr().expect("addr");
+    drop(listener);
+    addr
+}
+
+/// Serve one connection: greet, check the login and replay the transcript.
+async fn fake_feed(listener: tokio::net::TcpListener) -> String {
+    let (stream, _) = listener.accept().await.expect("accept feed client");
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(GREETING.as_bytes()).await.expect("greet");
+
+    let mut login = String::new();
+    BufReader::new(reader)
+        .read_line(&mut login)
+        .await
+        .expect("read login");
+
+    let now = time::OffsetDateTime::now_utc();
+    let time = format!("{:02}{:02}Z", now.hour(), now.minute());
+    for line in TRANSCRIPT {
+        writer
+            .write_all(line.replace("{time}", &time).as_bytes())
+            .await
+            .expect("replay line");
+    }
+    // Keep the connection open until the node has read everything.
+    sleep(Duration::from_millis(500)).await;
+    login
+}
+
+async fn wait_for_spots(handle: &dxcluster_node::NodeHandle, n: usize) -> Vec<Spot> {
+    timeout(Duration::from_secs(3), async {
+        loop {
+            let spots = handle.recent_spots(20).await;
+            if spots.len() >= n {
+                return spots;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("skimmer spots should arrive")
+}
+
+/// Spawn a node logged into a fake feed, returning the node and the feed's
+/// view of the login.
+async fn spawn_with_feed(
+    skimmer_rules: SkimmerRules,
+) -> (dxcluster_node::NodeHandle, JoinHandle<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind feed");
+    let feed_addr = listener.local_addr().expect("feed addr");
+    let feed = tokio::spawn(fake_feed(listener));
+
+    let config = NodeConfig {
+        skimmer_rules,
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config)
+        .with_upstream(UpstreamConfig {
+            addr: feed_addr.to_string(),
+            mode: UpstreamMode::Skimmer,
+            login_callsign: Some("N0CALL".into()),
+            auth_token: None,
+        })
+        .spawn()
+        .await
+        .expect("spawn node");
+    (handle, feed)
+}
+
+#[tokio::test]
+async fn skimmer_feed_spots_are_injected() {
+    let (handle, feed) = spawn_with_feed(SkimmerRules {
+        confirmations: 1,
+        ..SkimmerRules::default()
+    })
+    .await;
+
+    let spots = wait_for_spots(&handle, 3).await;
+    sleep(Duration::from_millis(200)).await;
+    let spots_after = handle.recent_spots(20).await;
+    // Later K1ABC reports fall in the suppression period.
+    assert_eq!(spots_after.len(), 3, "{spots_after:?}");
+
+    let by_dx = |dx: &str| {
+        spots
+            .iter()
+            .find(|spot| spot.dx.as_str() == dx)
+            .unwrap_or_else(|| panic!("missing {dx}"))
+    };
+    let cw = by_dx("K1ABC");
+    assert_eq!(cw.spotter.as_str(), "KM3T");
+    assert_eq!(cw.mode, Some(Mode::Cw));
+    assert_eq!((cw.snr_db, cw.wpm), (Some(18), Some(24)));
+    assert_eq!(cw.kind, SpotKind::Skimmer);
+    assert_eq!(cw.origin, Some(NodeId("node-a".into())));
+    assert_eq!(by_dx("DL1XYZ").kind, SpotKind::Digital);
+    assert_eq!(by_dx("OH2XX").mode, Some(Mode::Rtty));
+
+    assert_eq!(feed.await.expect("feed task").trim(), "N0CALL");
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn skimmer_reports_need_confirmation() {
+    let (handle, feed) = spawn_with_feed(SkimmerRules::default()).await;
+
+    let spots = wait_for_spots(&handle, 1).await;
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(handle.recent_spots(20).await.len(), 1);
+
+    // Only K1ABC was heard by two skimmers; the stronger report wins.
+    let spot = &spots[0];
+    assert_eq!(spot.dx.as_str(), "K1ABC");
+    assert_eq!(spot.spotter.as_str(), "W3OA");
+    assert_eq!(spot.snr_db, Some(25));
+
+    feed.await.expect("feed task");
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn overlong_feed_lines_are_skipped() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind feed");
+    let feed_addr = listener.local_addr().expect("feed addr");
+    let feed = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.expect("accept feed client");
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(GREETING.as_bytes()).await.expect("greet");
+        let mut login = String::new();
+        BufReader::new(reader)
+            .read_line(&mut login)
+            .await
+            .expect("read login");
+
+        let junk = vec![b'x'; 256 * 1024];
+        writer.write_all(&junk).await.expect("write junk");
+        let now = time::OffsetDateTime::now_utc();
+        let time = format!("{:02}{:02}Z", now.hour(), now.minute());
+        writer
+            .write_all(format!("\r\n{}", TRANSCRIPT[2].replace("{time}", &time)).as_bytes())
+            .await
+            .expect("write spot");
+        sleep(Duration::from_millis(500)).await;
+    });
+
+    let config = NodeConfig {
+        skimmer_rules: SkimmerRules {
+            confirmations: 1,
+            ..SkimmerRules::default()
+        },
+        ..NodeConfig::new(ephemeral_addr(), NodeId("node-a".into()))
+    };
+    let handle = Node::builder(config)
+        .with_upstream(UpstreamConfig {
+            addr: feed_addr.to_string(),
+            mode: UpstreamMode::Skimmer,
+            login_callsign: Some("N0CALL".into()),
+            auth_token: None,
+        })
+        .spawn()
+        .await
+        .expect("spawn node");
+
+    let spots = wait_for_spots(&handle, 1).await;
+    assert_eq!(spots[0].dx.as_str(), "K1ABC");
+
+    feed.await.expect("feed task");
+    handle.shutdown().await;
+}
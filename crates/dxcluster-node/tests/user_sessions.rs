@@ -1,9 +1,12 @@
 use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
 
-use dxcluster_node::{Node, NodeConfig, PeerOptions, PeerRetryPolicy};
-use dxcluster_types::NodeId;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use dxcluster_model::{Budget, CtyTable, RateLimits, SharedCtyTable};
+use dxcluster_node::{Node, NodeConfig};
+use dxcluster_types::{Callsign, NodeId, Region};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
+use tokio::time::timeout;
 
 fn ephemeral_addr() -> SocketAddr {
     let listener = TcpListener::bind("127.0.0.1:0").expect("bind temp port");
@@ -29,22 +32,41 @@ async fn read_line(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>) -> St
     buf
 }
 
+/// Read an unterminated prompt such as `login: `.
+async fn read_prompt(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>, prompt: &str) {
+    let mut buf = vec![0u8; prompt.len()];
+    reader.read_exact(&mut buf).await.expect("read prompt");
+    assert_eq!(String::from_utf8_lossy(&buf), prompt);
+}
+
+async fn login_client(
+    addr: SocketAddr,
+    callsign: &str,
+) -> (
+    BufReader<tokio::net::tcp::OwnedReadHalf>,
+    tokio::net::tcp::OwnedWriteHalf,
+) {
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer
+        .write_all(format!("{callsign}\n").as_bytes())
+        .await
+        .expect("write callsign");
+    let welcome = read_line(&mut reader).await;
+    assert!(welcome.contains(callsign), "unexpected welcome {welcome:?}");
+    read_line(&mut reader).await; // prompt
+    (reader, writer)
+}
+
 #[tokio::test]
 async fn user_can_submit_and_query_spot() {
     let addr = ephemeral_addr();
-    let config = NodeConfig {
-        user_listen: addr,
-        peer_listen: None,
-        node_id: NodeId("test-node".into()),
-        peer_options: PeerOptions::default(),
-        peer_retry: PeerRetryPolicy::default(),
-    };
+    let config = NodeConfig::new(addr, NodeId("test-node".into()));
 
     let handle = Node::builder(config).spawn().await.expect("spawn node");
 
-    let (mut reader1, mut writer1) = connect_client(addr).await;
-    read_line(&mut reader1).await; // banner
-    read_line(&mut reader1).await; // prompt
+    let (mut reader1, mut writer1) = login_client(addr, "G4ABC").await;
 
     writer1
         .write_all(b"DX K1ABC 14074 first spot\n")
@@ -52,13 +74,11 @@ async fn user_can_submit_and_query_spot() {
         .expect("write dx command");
 
     let spot_line = read_line(&mut reader1).await;
-    assert!(spot_line.contains("DX de N0CALL"));
+    assert!(spot_line.contains("DX de G4ABC"));
     assert!(spot_line.contains("K1ABC"));
     read_line(&mut reader1).await; // prompt
 
-    let (mut reader2, mut writer2) = connect_client(addr).await;
-    read_line(&mut reader2).await; // banner
-    read_line(&mut reader2).await; // prompt
+    let (mut reader2, mut writer2) = login_client(addr, "DL1XYZ").await;
 
     writer2.write_all(b"SH/DX\n").await.expect("write sh/dx");
 
@@ -73,19 +93,10 @@ async fn user_can_submit_and_query_spot() {
 #[tokio::test]
 async fn heartbeat_and_filters_reported() {
     let addr = ephemeral_addr();
-    let config = NodeConfig {
-        user_listen: addr,
-        peer_listen: None,
-        node_id: NodeId("test-node".into()),
-        peer_options: PeerOptions::default(),
-        peer_retry: PeerRetryPolicy::default(),
-    };
+    let config = NodeConfig::new(addr, NodeId("test-node".into()));
 
     let handle = Node::builder(config).spawn().await.expect("spawn node");
-    let (mut reader, mut writer) = connect_client(addr).await;
-
-    read_line(&mut reader).await; // banner
-    read_line(&mut reader).await; // prompt
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
 
     writer.write_all(b"PING\n").await.expect("write heartbeat");
 
@@ -104,3 +115,496 @@ async fn heartbeat_and_filters_reported() {
 
     handle.shutdown().await;
 }
+
+#[tokio::test]
+async fn live_spots_pushed_to_other_users() {
+    let addr = ephemeral_addr();
+    let config = NodeConfig::new(addr, NodeId("test-node".into()));
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let (mut watcher, _watcher_writer) = login_client(addr, "DL1XYZ").await;
+
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+
+    writer
+        .write_all(b"DX W1AW 7025 live push\n")
+        .await
+        .expect("write dx command");
+    let echo = read_line(&mut reader).await;
+    assert!(echo.contains("W1AW"));
+    read_line(&mut reader).await; // prompt
+
+    let pushed = timeout(Duration::from_secs(3), read_line(&mut watcher))
+        .await
+        .expect("spot should be pushed");
+    assert!(pushed.contains("DX de"));
+    assert!(pushed.contains("W1AW"));
+    assert!(pushed.contains("live push"));
+
+    writer.write_all(b"PING\n").await.expect("write heartbeat");
+    let pong = read_line(&mut reader).await;
+    assert!(
+        pong.contains("PONG"),
+        "submitter should not receive its own spot twice, got {pong:?}"
+    );
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn user_filters_apply_to_live_spots() {
+    let addr = ephemeral_addr();
+    let config = NodeConfig::new(addr, NodeId("test-node".into()));
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let (mut watcher, mut watcher_writer) = login_client(addr, "DL1XYZ").await;
+
+    watcher_writer
+        .write_all(b"accept/spots on 20m and not call W1\n")
+        .await
+        .expect("write filter");
+    let added = read_line(&mut watcher).await;
+    assert!(added.contains("accept/spots 1 on 20m and not call W1"));
+    read_line(&mut watcher).await; // prompt
+
+    watcher_writer
+        .write_all(b"SH/FILTERS\n")
+        .await
+        .expect("write sh/filters");
+    assert!(read_line(&mut watcher).await.contains("Filters"));
+    let rendered = read_line(&mut watcher).await;
+    assert_eq!(rendered.trim(), "accept/spots 1 on 20m and not call W1");
+    read_line(&mut watcher).await; // prompt
+
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+    for command in [
+        "DX K1ABC 7025 forty\n",
+        "DX W1AW 14025 filtered call\n",
+        "DX K2XYZ 14025 wanted\n",
+    ] {
+        writer
+            .write_all(command.as_bytes())
+            .await
+            .expect("write dx command");
+        read_line(&mut reader).await; // echo
+        read_line(&mut reader).await; // prompt
+    }
+
+    let pushed = timeout(Duration::from_secs(3), read_line(&mut watcher))
+        .await
+        .expect("matching spot should be pushed");
+    assert!(pushed.contains("K2XYZ"), "unexpected push {pushed:?}");
+
+    watcher_writer
+        .write_all(b"clear/spots 1\n")
+        .await
+        .expect("write clear");
+    assert!(
+        read_line(&mut watcher)
+            .await
+            .contains("Cleared spots filter 1")
+    );
+    read_line(&mut watcher).await; // prompt
+
+    watcher_writer
+        .write_all(b"SH/FILTERS\n")
+        .await
+        .expect("write sh/filters");
+    assert!(
+        read_line(&mut watcher)
+            .await
+            .contains("accepting all spots")
+    );
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn login_validates_callsigns_and_passwords() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config
+        .user_options
+        .passwords
+        .insert(Callsign::parse_loose("G4ABC").unwrap(), "hunter2".into());
+    config
+        .user_options
+        .banned
+        .insert(Callsign::parse_loose("K1BAD").unwrap());
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    // Malformed callsign is refused and the prompt repeated.
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer.write_all(b"not-a-call\n").await.unwrap();
+    assert!(
+        read_line(&mut reader)
+            .await
+            .contains("not a valid callsign")
+    );
+    read_prompt(&mut reader, "login: ").await;
+
+    // Registered callsign must supply the right password.
+    writer.write_all(b"g4abc\n").await.unwrap();
+    read_prompt(&mut reader, "password: ").await;
+    writer.write_all(b"wrong\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("wrong password"));
+    assert_eq!(read_line(&mut reader).await, "", "connection should close");
+
+    // Banned callsign is refused.
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer.write_all(b"K1BAD\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("not allowed"));
+    assert_eq!(read_line(&mut reader).await, "", "connection should close");
+
+    // Prefixes and suffixes do not get around a ban or a password.
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer.write_all(b"EA8/K1BAD/P\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("not allowed"));
+    assert_eq!(read_line(&mut reader).await, "", "connection should close");
+
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer.write_all(b"G4ABC/P\n").await.unwrap();
+    read_prompt(&mut reader, "password: ").await;
+    writer.write_all(b"hunter\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("wrong password"));
+    assert_eq!(read_line(&mut reader).await, "", "connection should close");
+
+    // Correct password logs in and spots carry the callsign.
+    let (mut reader, mut writer) = connect_client(addr).await;
+    read_line(&mut reader).await; // banner
+    read_prompt(&mut reader, "login: ").await;
+    writer.write_all(b"G4ABC\n").await.unwrap();
+    read_prompt(&mut reader, "password: ").await;
+    writer.write_all(b"hunter2\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("Hello G4ABC"));
+    read_line(&mut reader).await; // prompt
+    writer.write_all(b"DX K1ABC 14025 tnx\n").await.unwrap();
+    assert!(read_line(&mut reader).await.contains("DX de G4ABC"));
+    read_line(&mut reader).await; // prompt
+
+    // A second session with the same callsign is rejected.
+    let (mut dup_reader, mut dup_writer) = connect_client(addr).await;
+    read_line(&mut dup_reader).await; // banner
+    read_prompt(&mut dup_reader, "login: ").await;
+    dup_writer.write_all(b"G4ABC\n").await.unwrap();
+    read_prompt(&mut dup_reader, "password: ").await;
+    dup_writer.write_all(b"hunter2\n").await.unwrap();
+    assert!(
+        read_line(&mut dup_reader)
+            .await
+            .contains("already connected")
+    );
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn policy_rejections_reported_to_user() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config.policy.bad_words = vec!["lid".into()];
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+
+    for (command, code) in [
+        ("DX G4ABC/P 14025 me again\n", "self_spot"),
+        ("DX K1ABC 12345 between bands\n", "out_of_band"),
+        ("DX K1ABC 14025 what a lid\n", "bad_word"),
+    ] {
+        writer.write_all(command.as_bytes()).await.unwrap();
+        let reply = read_line(&mut reader).await;
+        assert!(
+            reply.starts_with("ERR: spot rejected") && reply.contains(code),
+            "unexpected reply {reply:?} to {command:?}"
+        );
+        read_line(&mut reader).await; // prompt
+    }
+    assert!(handle.recent_spots(10).await.is_empty());
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn node_region_applies_to_submitted_spots() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config.region = Some(Region::R1);
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+
+    writer
+        .write_all(b"DX K1ABC 7250 region 2 phone\n")
+        .await
+        .unwrap();
+    let reply = read_line(&mut reader).await;
+    assert!(
+        reply.starts_with("ERR: spot rejected") && reply.contains("out_of_band"),
+        "unexpected reply {reply:?}"
+    );
+    read_line(&mut reader).await; // prompt
+
+    writer
+        .write_all(b"DX K1ABC 7150 region 1 cw\n")
+        .await
+        .unwrap();
+    read_line(&mut reader).await; // spot echo
+    assert_eq!(handle.recent_spots(10).await.len(), 1);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn spot_submissions_are_rate_limited() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config.user_options.rate_limits = RateLimits {
+        spots: Some(Budget::new(2, Duration::from_secs(60))),
+        ..RateLimits::unlimited()
+    };
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+
+    for dx in ["K1ABC", "K2ABC"] {
+        writer
+            .write_all(format!("DX {dx} 14025 tnx\n").as_bytes())
+            .await
+            .unwrap();
+        assert!(read_line(&mut reader).await.contains("DX de G4ABC"));
+        read_line(&mut reader).await; // prompt
+    }
+    writer.write_all(b"DX K3ABC 14025 tnx\n").await.unwrap();
+    let reply = read_line(&mut reader).await;
+    assert!(
+        reply.starts_with("ERR: too many spots, try again in"),
+        "unexpected reply {reply:?}"
+    );
+    read_line(&mut reader).await; // prompt
+
+    // Other commands have their own budget.
+    writer.write_all(b"SH/DX 5\n").await.unwrap();
+    assert!(!read_line(&mut reader).await.starts_with("ERR"));
+
+    let spots = handle.recent_spots(10).await;
+    assert_eq!(spots.len(), 2);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn dxcc_filters_follow_country_file_reloads() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config.countries = SharedCtyTable::new(
+        CtyTable::parse_csv(
+            "G,England,223,EU,14,27,52.77,1.47,0.0,G M;\n\
+             K,United States,291,NA,5,8,37.53,91.67,5.0,K W;\n",
+        )
+        .expect("country file"),
+    );
+
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    let (mut watcher, mut watcher_writer) = login_client(addr, "DL1XYZ").await;
+    watcher_writer
+        .write_all(b"accept/spots call_dxcc 291\n")
+        .await
+        .expect("write filter");
+    read_line(&mut watcher).await; // filter added
+    read_line(&mut watcher).await; // prompt
+
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+    let mut submit = async |dx: &str| {
+        writer
+            .write_all(format!("DX {dx} 14025 tnx\n").as_bytes())
+            .await
+            .expect("write dx command");
+        read_line(&mut reader).await; // echo
+        read_line(&mut reader).await; // prompt
+    };
+
+    submit("G3AAA").await;
+    submit("K1ABC").await;
+    let pushed = timeout(Duration::from_secs(3), read_line(&mut watcher))
+        .await
+        .expect("matching spot should be pushed");
+    assert!(pushed.contains("K1ABC"), "unexpected push {pushed:?}");
+
+    handle.countries().replace(
+        CtyTable::parse_csv(
+            "K,Test Entity,999,NA,5,8,37.53,91.67,5.0,K;\n\
+             W,United States,291,NA,5,8,37.53,91.67,5.0,W;\n",
+        )
+        .expect("country file"),
+    );
+    submit("K2ABC").await;
+    submit("W1XYZ").await;
+    let pushed = timeout(Duration::from_secs(3), read_line(&mut watcher))
+        .await
+        .expect("matching spot should be pushed");
+    assert!(pushed.contains("W1XYZ"), "unexpected push {pushed:?}");
+
+    handle.shutdown().await;
+}
+
+/// Lines up to, not including, the next prompt.
+async fn read_until_prompt(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>) -> Vec<String> {
+    let mut lines = Vec::new();
+    loop {
+        let line = read_line(reader).await;
+        if line.trim_end() == ">" {
+            return lines;
+        }
+        lines.push(line);
+    }
+}
+
+#[tokio::test]
+async fn sh_dx_arguments_select_cached_spots() {
+    let addr = ephemeral_addr();
+    let config = NodeConfig::new(addr, NodeId("test-node".into()));
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+    let (mut reader, mut writer) = login_client(addr, "G4ABC").await;
+
+    for spot in [
+        "DX K1ABC 14025 cw",
+        "DX K1ABC/P 7074 FT8 loud",
+        "DX VK2ABC 14200 ssb",
+        "DX JA1ZZ 21074 ft8",
+    ] {
+        writer
+            .write_all(format!("{spot}\n").as_bytes())
+            .await
+            .unwrap();
+        read_until_prompt(&mut reader).await;
+    }
+
+    let mut query = async |line: &str| {
+        writer
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .unwrap();
+        read_until_prompt(&mut reader)
+            .await
+            .into_iter()
+            .map(|line| {
+                line.split_whitespace()
+                    .nth(4)
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        query("sh/dx").await,
+        ["JA1ZZ", "VK2ABC", "K1ABC/P", "K1ABC"]
+    );
+    assert_eq!(query("sh/dx 2").await, ["JA1ZZ", "VK2ABC"]);
+    assert_eq!(query("sh/dx on 20m").await, ["VK2ABC", "K1ABC"]);
+    assert_eq!(query("sh/dx 14000/14100").await, ["K1ABC"]);
+    assert_eq!(query("sh/dx K1ABC").await, ["K1ABC/P", "K1ABC"]);
+    assert_eq!(query("sh/dx K1ABC exact").await, ["K1ABC"]);
+    assert_eq!(query("sh/dx *ABC 2").await, ["VK2ABC", "K1ABC/P"]);
+    assert_eq!(
+        query("sh/dx info ft8 by G4ABC day 1").await,
+        ["JA1ZZ", "K1ABC/P"]
+    );
+    assert_eq!(query("sh/dx by DL1XYZ").await, Vec::<String>::new());
+
+    // "ERR: invalid sh/dx query: bad band or range ..."
+    assert_eq!(query("sh/dx on 11m").await, ["bad"]);
+
+    handle.shutdown().await;
+}
+
+#[tokio::test]
+async fn announcements_pushed_and_listed() {
+    let addr = ephemeral_addr();
+    let mut config = NodeConfig::new(addr, NodeId("test-node".into()));
+    config
+        .user_options
+        .sysops
+        .insert(Callsign::parse_strict("G4SYS").unwrap());
+    let handle = Node::builder(config).spawn().await.expect("spawn node");
+
+    let (mut watcher, mut watcher_writer) = login_client(addr, "DL1XYZ").await;
+    let (mut sysop, mut sysop_writer) = login_client(addr, "G4SYS").await;
+    let (mut reader, mut writer) = login_client(addr, "M0ABC").await;
+
+    writer
+        .write_all(b"ann qrv 6m tonight\n")
+        .await
+        .expect("write ann");
+    assert_eq!(
+        read_until_prompt(&mut reader).await,
+        ["To LOCAL de M0ABC: qrv 6m tonight\n"]
+    );
+    for user in [&mut watcher, &mut sysop] {
+        let pushed = timeout(Duration::from_secs(3), read_line(user))
+            .await
+            .expect("announcement should be pushed");
+        assert_eq!(pushed, "To LOCAL de M0ABC: qrv 6m tonight\n");
+    }
+
+    writer
+        .write_all(b"ann sysop link down\n")
+        .await
+        .expect("write ann sysop");
+    read_until_prompt(&mut reader).await;
+    let pushed = timeout(Duration::from_secs(3), read_line(&mut sysop))
+        .await
+        .expect("sysop announcement should be pushed");
+    assert_eq!(pushed, "To SYSOP de M0ABC: link down\n");
+
+    writer
+        .write_all(b"ann qrv 6m tonight\n")
+        .await
+        .expect("write repeat");
+    assert_eq!(
+        read_until_prompt(&mut reader).await,
+        ["Duplicate announcement, not sent\n"]
+    );
+
+    // Sysop announcements are neither pushed nor listed to other users.
+    watcher_writer
+        .write_all(b"sh/ann\n")
+        .await
+        .expect("write sh/ann");
+    assert_eq!(
+        read_until_prompt(&mut watcher).await,
+        ["To LOCAL de M0ABC: qrv 6m tonight\n"]
+    );
+    sysop_writer
+        .write_all(b"sh/ann 5\n")
+        .await
+        .expect("write sh/ann 5");
+    assert_eq!(
+        read_until_prompt(&mut sysop).await,
+        [
+            "To SYSOP de M0ABC: link down\n",
+            "To LOCAL de M0ABC: qrv 6m tonight\n"
+        ]
+    );
+
+    writer.write_all(b"PING\n").await.expect("write heartbeat");
+    let pong = read_line(&mut reader).await;
+    assert!(
+        pong.contains("PONG"),
+        "sender should not receive its own announcement twice, got {pong:?}"
+    );
+
+    handle.shutdown().await;
+}
@@ -1,14 +1,18 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
+use crate::error::BandError;
 use crate::frequency::FrequencyHz;
 
 /// Amateur radio bands recognized by `dxcluster-types`.
 ///
 /// The ranges and display labels for each band are centralized in
 /// [`Band::definitions`], which is used for both formatting and frequency
-/// classification.
+/// classification. Allocations that differ between IARU regions are in
+/// [`Band::definitions_for`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Band {
+    Meter2200,
+    Meter630,
     Meter160,
     Meter80,
     Meter60,
@@ -20,9 +24,60 @@ pub enum Band {
     Meter12,
     Meter10,
     Meter6,
+    Meter4,
     Meter2,
     Meter1_25,
     Centimeter70,
+    Centimeter23,
+    Centimeter13,
+    Centimeter9,
+    Centimeter6,
+    Centimeter3,
+    Centimeter1_2,
+}
+
+/// IARU region, selecting which band plan applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// Europe, Africa, the Middle East and northern Asia.
+    R1,
+    /// The Americas.
+    R2,
+    /// Asia-Pacific.
+    R3,
+}
+
+impl Region {
+    pub const ALL: [Region; 3] = [Region::R1, Region::R2, Region::R3];
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Region::R1 => "R1",
+            Region::R2 => "R2",
+            Region::R3 => "R3",
+        })
+    }
+}
+
+impl FromStr for Region {
+    type Err = BandError;
+
+    /// Accepts `1`, `R1` or `region1`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        let number = lower
+            .strip_prefix("region")
+            .or_else(|| lower.strip_prefix('r'))
+            .unwrap_or(&lower);
+        match number.trim() {
+            "1" => Ok(Region::R1),
+            "2" => Ok(Region::R2),
+            "3" => Ok(Region::R3),
+            _ => Err(BandError::UnknownRegion(s.trim().to_string())),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -34,117 +89,175 @@ pub struct BandDefinition {
 }
 
 impl BandDefinition {
+    const fn new(band: Band, label: &'static str, low_hz: u64, high_hz: u64) -> Self {
+        BandDefinition {
+            band,
+            label,
+            low_hz,
+            high_hz,
+        }
+    }
+
     pub const fn contains(&self, hz: u64) -> bool {
         hz >= self.low_hz && hz <= self.high_hz
     }
 }
 
-const BAND_DEFINITIONS: [BandDefinition; 14] = [
-    BandDefinition {
-        band: Band::Meter160,
-        label: "160m",
-        low_hz: 1_800_000,
-        high_hz: 2_000_000,
-    },
-    BandDefinition {
-        band: Band::Meter80,
-        label: "80m",
-        low_hz: 3_500_000,
-        high_hz: 4_000_000,
-    },
-    BandDefinition {
-        band: Band::Meter60,
-        label: "60m",
-        low_hz: 5_351_500,
-        high_hz: 5_366_500,
-    },
-    BandDefinition {
-        band: Band::Meter40,
-        label: "40m",
-        low_hz: 7_000_000,
-        high_hz: 7_300_000,
-    },
-    BandDefinition {
-        band: Band::Meter30,
-        label: "30m",
-        low_hz: 10_100_000,
-        high_hz: 10_150_000,
-    },
-    BandDefinition {
-        band: Band::Meter20,
-        label: "20m",
-        low_hz: 14_000_000,
-        high_hz: 14_350_000,
-    },
-    BandDefinition {
-        band: Band::Meter17,
-        label: "17m",
-        low_hz: 18_068_000,
-        high_hz: 18_168_000,
-    },
-    BandDefinition {
-        band: Band::Meter15,
-        label: "15m",
-        low_hz: 21_000_000,
-        high_hz: 21_450_000,
-    },
-    BandDefinition {
-        band: Band::Meter12,
-        label: "12m",
-        low_hz: 24_890_000,
-        high_hz: 24_990_000,
-    },
-    BandDefinition {
-        band: Band::Meter10,
-        label: "10m",
-        low_hz: 28_000_000,
-        high_hz: 29_700_000,
-    },
-    BandDefinition {
-        band: Band::Meter6,
-        label: "6m",
-        low_hz: 50_000_000,
-        high_hz: 54_000_000,
-    },
-    BandDefinition {
-        band: Band::Meter2,
-        label: "2m",
-        low_hz: 144_000_000,
-        high_hz: 148_000_000,
-    },
-    BandDefinition {
-        band: Band::Meter1_25,
-        label: "1.25m",
-        low_hz: 222_000_000,
-        high_hz: 225_000_000,
-    },
-    BandDefinition {
-        band: Band::Centimeter70,
-        label: "70cm",
-        low_hz: 420_000_000,
-        high_hz: 450_000_000,
-    },
+/// Every band, each spanning the widest allocation of any region.
+const BAND_DEFINITIONS: [BandDefinition; 23] = [
+    BandDefinition::new(Band::Meter2200, "2200m", 135_700, 137_800),
+    BandDefinition::new(Band::Meter630, "630m", 472_000, 479_000),
+    BandDefinition::new(Band::Meter160, "160m", 1_800_000, 2_000_000),
+    BandDefinition::new(Band::Meter80, "80m", 3_500_000, 4_000_000),
+    BandDefinition::new(Band::Meter60, "60m", 5_330_500, 5_406_400),
+    BandDefinition::new(Band::Meter40, "40m", 7_000_000, 7_300_000),
+    BandDefinition::new(Band::Meter30, "30m", 10_100_000, 10_150_000),
+    BandDefinition::new(Band::Meter20, "20m", 14_000_000, 14_350_000),
+    BandDefinition::new(Band::Meter17, "17m", 18_068_000, 18_168_000),
+    BandDefinition::new(Band::Meter15, "15m", 21_000_000, 21_450_000),
+    BandDefinition::new(Band::Meter12, "12m", 24_890_000, 24_990_000),
+    BandDefinition::new(Band::Meter10, "10m", 28_000_000, 29_700_000),
+    BandDefinition::new(Band::Meter6, "6m", 50_000_000, 54_000_000),
+    BandDefinition::new(Band::Meter4, "4m", 70_000_000, 70_500_000),
+    BandDefinition::new(Band::Meter2, "2m", 144_000_000, 148_000_000),
+    BandDefinition::new(Band::Meter1_25, "1.25m", 222_000_000, 225_000_000),
+    BandDefinition::new(Band::Centimeter70, "70cm", 420_000_000, 450_000_000),
+    BandDefinition::new(Band::Centimeter23, "23cm", 1_240_000_000, 1_300_000_000),
+    BandDefinition::new(Band::Centimeter13, "13cm", 2_300_000_000, 2_450_000_000),
+    BandDefinition::new(Band::Centimeter9, "9cm", 3_300_000_000, 3_500_000_000),
+    BandDefinition::new(Band::Centimeter6, "6cm", 5_650_000_000, 5_925_000_000),
+    BandDefinition::new(Band::Centimeter3, "3cm", 10_000_000_000, 10_500_000_000),
+    BandDefinition::new(Band::Centimeter1_2, "1.2cm", 24_000_000_000, 24_250_000_000),
+];
+
+const REGION1_DEFINITIONS: [BandDefinition; 21] = [
+    BandDefinition::new(Band::Meter2200, "2200m", 135_700, 137_800),
+    BandDefinition::new(Band::Meter630, "630m", 472_000, 479_000),
+    BandDefinition::new(Band::Meter160, "160m", 1_810_000, 2_000_000),
+    BandDefinition::new(Band::Meter80, "80m", 3_500_000, 3_800_000),
+    BandDefinition::new(Band::Meter60, "60m", 5_351_500, 5_366_500),
+    BandDefinition::new(Band::Meter40, "40m", 7_000_000, 7_200_000),
+    BandDefinition::new(Band::Meter30, "30m", 10_100_000, 10_150_000),
+    BandDefinition::new(Band::Meter20, "20m", 14_000_000, 14_350_000),
+    BandDefinition::new(Band::Meter17, "17m", 18_068_000, 18_168_000),
+    BandDefinition::new(Band::Meter15, "15m", 21_000_000, 21_450_000),
+    BandDefinition::new(Band::Meter12, "12m", 24_890_000, 24_990_000),
+    BandDefinition::new(Band::Meter10, "10m", 28_000_000, 29_700_000),
+    BandDefinition::new(Band::Meter6, "6m", 50_000_000, 52_000_000),
+    BandDefinition::new(Band::Meter4, "4m", 70_000_000, 70_500_000),
+    BandDefinition::new(Band::Meter2, "2m", 144_000_000, 146_000_000),
+    BandDefinition::new(Band::Centimeter70, "70cm", 430_000_000, 440_000_000),
+    BandDefinition::new(Band::Centimeter23, "23cm", 1_240_000_000, 1_300_000_000),
+    BandDefinition::new(Band::Centimeter13, "13cm", 2_300_000_000, 2_450_000_000),
+    BandDefinition::new(Band::Centimeter9, "9cm", 3_400_000_000, 3_475_000_000),
+    BandDefinition::new(Band::Centimeter6, "6cm", 5_650_000_000, 5_850_000_000),
+    BandDefinition::new(Band::Centimeter3, "3cm", 10_000_000_000, 10_500_000_000),
+];
+
+const REGION2_DEFINITIONS: [BandDefinition; 22] = [
+    BandDefinition::new(Band::Meter2200, "2200m", 135_700, 137_800),
+    BandDefinition::new(Band::Meter630, "630m", 472_000, 479_000),
+    BandDefinition::new(Band::Meter160, "160m", 1_800_000, 2_000_000),
+    BandDefinition::new(Band::Meter80, "80m", 3_500_000, 4_000_000),
+    BandDefinition::new(Band::Meter60, "60m", 5_330_500, 5_406_400),
+    BandDefinition::new(Band::Meter40, "40m", 7_000_000, 7_300_000),
+    BandDefinition::new(Band::Meter30, "30m", 10_100_000, 10_150_000),
+    BandDefinition::new(Band::Meter20, "20m", 14_000_000, 14_350_000),
+    BandDefinition::new(Band::Meter17, "17m", 18_068_000, 18_168_000),
+    BandDefinition::new(Band::Meter15, "15m", 21_000_000, 21_450_000),
+    BandDefinition::new(Band::Meter12, "12m", 24_890_000, 24_990_000),
+    BandDefinition::new(Band::Meter10, "10m", 28_000_000, 29_700_000),
+    BandDefinition::new(Band::Meter6, "6m", 50_000_000, 54_000_000),
+    BandDefinition::new(Band::Meter2, "2m", 144_000_000, 148_000_000),
+    BandDefinition::new(Band::Meter1_25, "1.25m", 222_000_000, 225_000_000),
+    BandDefinition::new(Band::Centimeter70, "70cm", 420_000_000, 450_000_000),
+    BandDefinition::new(Band::Centimeter23, "23cm", 1_240_000_000, 1_300_000_000),
+    BandDefinition::new(Band::Centimeter13, "13cm", 2_300_000_000, 2_450_000_000),
+    BandDefinition::new(Band::Centimeter9, "9cm", 3_300_000_000, 3_500_000_000),
+    BandDefinition::new(Band::Centimeter6, "6cm", 5_650_000_000, 5_925_000_000),
+    BandDefinition::new(Band::Centimeter3, "3cm", 10_000_000_000, 10_500_000_000),
+    BandDefinition::new(Band::Centimeter1_2, "1.2cm", 24_000_000_000, 24_250_000_000),
+];
+
+const REGION3_DEFINITIONS: [BandDefinition; 21] = [
+    BandDefinition::new(Band::Meter2200, "2200m", 135_700, 137_800),
+    BandDefinition::new(Band::Meter630, "630m", 472_000, 479_000),
+    BandDefinition::new(Band::Meter160, "160m", 1_800_000, 2_000_000),
+    BandDefinition::new(Band::Meter80, "80m", 3_500_000, 3_900_000),
+    BandDefinition::new(Band::Meter60, "60m", 5_351_500, 5_366_500),
+    BandDefinition::new(Band::Meter40, "40m", 7_000_000, 7_300_000),
+    BandDefinition::new(Band::Meter30, "30m", 10_100_000, 10_150_000),
+    BandDefinition::new(Band::Meter20, "20m", 14_000_000, 14_350_000),
+    BandDefinition::new(Band::Meter17, "17m", 18_068_000, 18_168_000),
+    BandDefinition::new(Band::Meter15, "15m", 21_000_000, 21_450_000),
+    BandDefinition::new(Band::Meter12, "12m", 24_890_000, 24_990_000),
+    BandDefinition::new(Band::Meter10, "10m", 28_000_000, 29_700_000),
+    BandDefinition::new(Band::Meter6, "6m", 50_000_000, 54_000_000),
+    BandDefinition::new(Band::Meter2, "2m", 144_000_000, 148_000_000),
+    BandDefinition::new(Band::Centimeter70, "70cm", 430_000_000, 440_000_000),
+    BandDefinition::new(Band::Centimeter23, "23cm", 1_240_000_000, 1_300_000_000),
+    BandDefinition::new(Band::Centimeter13, "13cm", 2_300_000_000, 2_450_000_000),
+    BandDefinition::new(Band::Centimeter9, "9cm", 3_300_000_000, 3_500_000_000),
+    BandDefinition::new(Band::Centimeter6, "6cm", 5_650_000_000, 5_850_000_000),
+    BandDefinition::new(Band::Centimeter3, "3cm", 10_000_000_000, 10_500_000_000),
+    BandDefinition::new(Band::Centimeter1_2, "1.2cm", 24_000_000_000, 24_050_000_000),
 ];
 
 impl Band {
+    /// All bands, with ranges covering every region's allocation.
     pub const fn definitions() -> &'static [BandDefinition] {
         &BAND_DEFINITIONS
     }
 
+    /// The bands and ranges allocated in `region`.
+    pub const fn definitions_for(region: Region) -> &'static [BandDefinition] {
+        match region {
+            Region::R1 => &REGION1_DEFINITIONS,
+            Region::R2 => &REGION2_DEFINITIONS,
+            Region::R3 => &REGION3_DEFINITIONS,
+        }
+    }
+
+    /// Band containing `freq` in any region.
     pub fn from_frequency(freq: FrequencyHz) -> Option<Self> {
-        let hz = freq.0;
+        find(Band::definitions(), freq)
+    }
+
+    /// Band containing `freq` under `region`'s band plan.
+    pub fn from_frequency_in(freq: FrequencyHz, region: Region) -> Option<Self> {
+        find(Band::definitions_for(region), freq)
+    }
+
+    /// Look up a band by its display label (e.g. `20m`), ignoring ASCII case.
+    pub fn from_label(label: &str) -> Option<Self> {
         Band::definitions()
             .iter()
-            .find_map(|definition| definition.contains(hz).then_some(definition.band))
+            .find(|definition| definition.label.eq_ignore_ascii_case(label))
+            .map(|definition| definition.band)
     }
 
     pub fn label(&self) -> &'static str {
+        self.definition().label
+    }
+
+    /// Range of this band across all regions.
+    pub fn definition(&self) -> &'static BandDefinition {
         Band::definitions()
             .iter()
             .find(|definition| definition.band == *self)
-            .map(|definition| definition.label)
             .expect("all Band variants have a definition")
     }
+
+    /// Whether `freq` lies in this band in any region.
+    pub fn contains(&self, freq: FrequencyHz) -> bool {
+        self.definition().contains(freq.0)
+    }
+}
+
+fn find(definitions: &[BandDefinition], freq: FrequencyHz) -> Option<Band> {
+    definitions
+        .iter()
+        .find_map(|definition| definition.contains(freq.0).then_some(definition.band))
 }
 
 impl fmt::Display for Band {
@@ -153,6 +266,14 @@ impl fmt::Display for Band {
     }
 }
 
+impl FromStr for Band {
+    type Err = BandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Band::from_label(s.trim()).ok_or_else(|| BandError::UnknownBand(s.trim().to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +300,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_label() {
+        for definition in Band::definitions() {
+            assert_eq!(Band::from_label(definition.label), Some(definition.band));
+            assert_eq!(
+                Band::from_label(&definition.label.to_uppercase()),
+                Some(definition.band)
+            );
+        }
+        assert!(Band::from_label("11m").is_none());
+    }
+
     #[test]
     fn covers_band_edges() {
         for definition in Band::definitions() {
@@ -197,4 +330,66 @@ mod tests {
             assert!(Band::from_frequency(above).is_none());
         }
     }
+
+    #[test]
+    fn regional_tables_are_within_global_ranges() {
+        for region in Region::ALL {
+            for definition in Band::definitions_for(region) {
+                let global = definition.band.definition();
+                assert_eq!(definition.label, global.label);
+                assert!(
+                    global.low_hz <= definition.low_hz && definition.high_hz <= global.high_hz,
+                    "{region} {} exceeds the global range",
+                    definition.label
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn classifies_by_region() {
+        let four_metres = FrequencyHz(70_200_000);
+        assert_eq!(Band::from_frequency(four_metres), Some(Band::Meter4));
+        assert_eq!(
+            Band::from_frequency_in(four_metres, Region::R1),
+            Some(Band::Meter4)
+        );
+        assert_eq!(Band::from_frequency_in(four_metres, Region::R2), None);
+
+        let us_channel = FrequencyHz(5_332_000);
+        assert_eq!(
+            Band::from_frequency_in(us_channel, Region::R2),
+            Some(Band::Meter60)
+        );
+        assert_eq!(Band::from_frequency_in(us_channel, Region::R1), None);
+
+        let phone = FrequencyHz(3_900_000);
+        assert_eq!(
+            Band::from_frequency_in(phone, Region::R2),
+            Some(Band::Meter80)
+        );
+        assert_eq!(Band::from_frequency_in(phone, Region::R1), None);
+
+        assert_eq!(
+            Band::from_frequency(FrequencyHz(136_000)),
+            Some(Band::Meter2200)
+        );
+        assert_eq!(
+            Band::from_frequency(FrequencyHz(10_368_100_000)),
+            Some(Band::Centimeter3)
+        );
+    }
+
+    #[test]
+    fn parses_regions_and_bands() {
+        assert_eq!("1".parse(), Ok(Region::R1));
+        assert_eq!("r2".parse(), Ok(Region::R2));
+        assert_eq!("Region3".parse(), Ok(Region::R3));
+        assert_eq!(
+            "4".parse::<Region>(),
+            Err(BandError::UnknownRegion("4".to_string()))
+        );
+        assert_eq!("23CM".parse(), Ok(Band::Centimeter23));
+        assert!("11m".parse::<Band>().is_err());
+    }
 }
@@ -0,0 +1,123 @@
+//! Mode sub-segments within each band, following the IARU band plans where
+//! the regions broadly agree. Beacon sub-bands, gaps and bands above 70cm
+//! are left unclassified.
+
+use crate::band::Band::{self, *};
+use crate::frequency::FrequencyHz;
+use SegmentKind::{Cw, Digital, Fm, Phone};
+
+/// Kind of traffic a band-plan segment is set aside for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    Cw,
+    Digital,
+    Phone,
+    Fm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub band: Band,
+    pub kind: SegmentKind,
+    /// Inclusive lower edge.
+    pub low_hz: u64,
+    /// Exclusive upper edge.
+    pub high_hz: u64,
+}
+
+impl Segment {
+    const fn new(band: Band, kind: SegmentKind, low_hz: u64, high_hz: u64) -> Self {
+        Segment {
+            band,
+            kind,
+            low_hz,
+            high_hz,
+        }
+    }
+
+    pub const fn all() -> &'static [Segment] {
+        &SEGMENTS
+    }
+
+    pub const fn contains(&self, freq: FrequencyHz) -> bool {
+        freq.0 >= self.low_hz && freq.0 < self.high_hz
+    }
+
+    /// Segment containing `freq`, if any.
+    pub fn find(freq: FrequencyHz) -> Option<&'static Segment> {
+        SEGMENTS.iter().find(|segment| segment.contains(freq))
+    }
+}
+
+const SEGMENTS: [Segment; 43] = [
+    Segment::new(Meter2200, Cw, 135_700, 137_800),
+    Segment::new(Meter630, Cw, 472_000, 475_000),
+    Segment::new(Meter630, Digital, 475_000, 479_001),
+    Segment::new(Meter160, Cw, 1_800_000, 1_838_000),
+    Segment::new(Meter160, Digital, 1_838_000, 1_843_000),
+    Segment::new(Meter160, Phone, 1_843_000, 2_000_001),
+    Segment::new(Meter80, Cw, 3_500_000, 3_570_000),
+    Segment::new(Meter80, Digital, 3_570_000, 3_600_000),
+    Segment::new(Meter80, Phone, 3_600_000, 4_000_001),
+    Segment::new(Meter60, Cw, 5_351_500, 5_354_000),
+    Segment::new(Meter60, Phone, 5_354_000, 5_366_501),
+    Segment::new(Meter40, Cw, 7_000_000, 7_040_000),
+    Segment::new(Meter40, Digital, 7_040_000, 7_060_000),
+    Segment::new(Meter40, Phone, 7_060_000, 7_300_001),
+    Segment::new(Meter30, Cw, 10_100_000, 10_130_000),
+    Segment::new(Meter30, Digital, 10_130_000, 10_150_001),
+    Segment::new(Meter20, Cw, 14_000_000, 14_070_000),
+    Segment::new(Meter20, Digital, 14_070_000, 14_099_000),
+    Segment::new(Meter20, Phone, 14_101_000, 14_350_001),
+    Segment::new(Meter17, Cw, 18_068_000, 18_095_000),
+    Segment::new(Meter17, Digital, 18_095_000, 18_111_000),
+    Segment::new(Meter17, Phone, 18_111_000, 18_168_001),
+    Segment::new(Meter15, Cw, 21_000_000, 21_070_000),
+    Segment::new(Meter15, Digital, 21_070_000, 21_150_000),
+    Segment::new(Meter15, Phone, 21_150_000, 21_450_001),
+    Segment::new(Meter12, Cw, 24_890_000, 24_915_000),
+    Segment::new(Meter12, Digital, 24_915_000, 24_931_000),
+    Segment::new(Meter12, Phone, 24_931_000, 24_990_001),
+    Segment::new(Meter10, Cw, 28_000_000, 28_070_000),
+    Segment::new(Meter10, Digital, 28_070_000, 28_190_000),
+    Segment::new(Meter10, Phone, 28_300_000, 29_510_000),
+    Segment::new(Meter10, Fm, 29_510_000, 29_700_001),
+    Segment::new(Meter6, Cw, 50_000_000, 50_100_000),
+    Segment::new(Meter6, Phone, 50_100_000, 50_300_000),
+    Segment::new(Meter6, Digital, 50_300_000, 50_400_000),
+    Segment::new(Meter6, Fm, 51_000_000, 54_000_001),
+    Segment::new(Meter4, Cw, 70_000_000, 70_100_000),
+    Segment::new(Meter4, Phone, 70_100_000, 70_250_000),
+    Segment::new(Meter2, Cw, 144_000_000, 144_150_000),
+    Segment::new(Meter2, Phone, 144_150_000, 144_400_000),
+    Segment::new(Meter2, Fm, 144_500_000, 148_000_001),
+    Segment::new(Centimeter70, Cw, 432_000_000, 432_100_000),
+    Segment::new(Centimeter70, Phone, 432_100_000, 432_400_000),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_lie_inside_their_band() {
+        for segment in Segment::all() {
+            let band = segment.band.definition();
+            assert!(
+                band.low_hz <= segment.low_hz && segment.high_hz <= band.high_hz + 1,
+                "{:?} segment {}..{} outside {}",
+                segment.kind,
+                segment.low_hz,
+                segment.high_hz,
+                band.label
+            );
+        }
+    }
+
+    #[test]
+    fn segments_do_not_overlap() {
+        for pair in Segment::all().windows(2) {
+            assert!(pair[0].high_hz <= pair[1].low_hz, "{pair:?}");
+        }
+    }
+}
@@ -2,6 +2,16 @@ use std::{fmt, str::FromStr};
 
 use crate::error::CallsignError;
 
+/// Longest base call accepted by strict parsing.
+pub const MAX_BASE_LEN: usize = 10;
+
+/// Letters that, with the numeral after them, name an entity rather than a
+/// call area: US territories such as `KH6` (Hawaii) and `KL7` (Alaska), and
+/// British ones such as `VP2E` (Anguilla) and `VP8` (Falklands).
+const NUMBERED_PREFIXES: [&str; 12] = [
+    "AH", "AL", "KH", "KL", "KP", "NH", "NL", "NP", "WH", "WL", "WP", "VP",
+];
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Callsign(String);
 
@@ -29,11 +39,13 @@ impl Callsign {
         }
 
         if strict || cfg!(feature = "strict_callsign") {
-            let has_digit = value.chars().any(|c| c.is_ascii_digit());
-            let all_valid = value.chars().all(|c| c.is_ascii_alphanumeric() || c == '/');
-            if !has_digit || !all_valid {
-                return Err(CallsignError::InvalidFormat);
+            if let Some(invalid) = value
+                .chars()
+                .find(|c| !c.is_ascii_alphanumeric() && *c != '/')
+            {
+                return Err(CallsignError::InvalidCharacter(invalid));
             }
+            CallsignParts::parse(value)?;
         }
 
         Ok(())
@@ -42,6 +54,18 @@ impl Callsign {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Split the callsign into operating prefix, base call and suffixes.
+    pub fn parts(&self) -> Result<CallsignParts, CallsignError> {
+        CallsignParts::parse(&self.0)
+    }
+
+    /// The base call without prefix, suffixes or SSID, e.g. `G4ABC` for
+    /// `EA8/G4ABC/P`, or the call itself when it does not decompose.
+    pub fn base_call(&self) -> Callsign {
+        self.parts()
+            .map_or_else(|_| self.clone(), |parts| Callsign(parts.base))
+    }
 }
 
 impl fmt::Display for Callsign {
@@ -57,3 +81,310 @@ impl FromStr for Callsign {
         Callsign::parse_loose(s)
     }
 }
+
+/// Designator written after the base call.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Suffix {
+    /// `/P`
+    Portable,
+    /// `/M`
+    Mobile,
+    /// `/MM`
+    MaritimeMobile,
+    /// `/AM`
+    AeronauticalMobile,
+    /// `/QRP`
+    Qrp,
+    /// A bare digit moving the station to another call area, as in `W1ABC/4`.
+    CallArea(u8),
+    /// Any other letters-only designator, such as `/A` or `/LH`.
+    Other(String),
+}
+
+impl Suffix {
+    fn parse(segment: &str) -> Option<Self> {
+        let suffix = match segment {
+            "P" => Suffix::Portable,
+            "M" => Suffix::Mobile,
+            "MM" => Suffix::MaritimeMobile,
+            "AM" => Suffix::AeronauticalMobile,
+            "QRP" => Suffix::Qrp,
+            _ if segment.len() == 1 && segment.as_bytes()[0].is_ascii_digit() => {
+                Suffix::CallArea(segment.as_bytes()[0] - b'0')
+            }
+            _ if segment.chars().all(|c| c.is_ascii_alphabetic()) => {
+                Suffix::Other(segment.to_string())
+            }
+            _ => return None,
+        };
+        Some(suffix)
+    }
+}
+
+impl fmt::Display for Suffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Suffix::Portable => f.write_str("P"),
+            Suffix::Mobile => f.write_str("M"),
+            Suffix::MaritimeMobile => f.write_str("MM"),
+            Suffix::AeronauticalMobile => f.write_str("AM"),
+            Suffix::Qrp => f.write_str("QRP"),
+            Suffix::CallArea(digit) => write!(f, "{digit}"),
+            Suffix::Other(other) => f.write_str(other),
+        }
+    }
+}
+
+/// Which prefix determines where a station is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// The base call's own prefix.
+    Home,
+    /// Another prefix: the operating prefix in `VP2E/K1ABC` or `G4ABC/EA8`,
+    /// or the home prefix moved to another call area, `W4` for `W1ABC/4`.
+    Prefix(String),
+    /// Maritime or aeronautical mobile: not in any country.
+    NoEntity,
+}
+
+/// A callsign split into its parts, e.g. `KH6/W1AW/M` into operating prefix
+/// `KH6`, base call `W1AW` and suffix [`Suffix::Mobile`]. Any `-SSID` is
+/// dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallsignParts {
+    pub prefix: Option<String>,
+    pub base: String,
+    pub suffixes: Vec<Suffix>,
+}
+
+impl CallsignParts {
+    pub fn parse(input: &str) -> Result<Self, CallsignError> {
+        let call = input.trim().to_ascii_uppercase();
+        let call = call.split_once('-').map_or(call.as_str(), |(call, _)| call);
+        if call.is_empty() {
+            return Err(CallsignError::Empty);
+        }
+
+        let segments: Vec<&str> = call.split('/').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(CallsignError::EmptySegment);
+        }
+        if segments.len() > 3 {
+            return Err(CallsignError::TooManySegments(segments.len()));
+        }
+
+        // The base is the longest segment that reads as a complete call;
+        // on a tie the later one wins, since prefixes are written first.
+        let (base_index, base) = segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| is_complete_call(segment))
+            .max_by_key(|(index, segment)| (segment.len(), *index))
+            .ok_or(CallsignError::MissingBase)?;
+        if base.len() > MAX_BASE_LEN {
+            return Err(CallsignError::BaseTooLong(base.len()));
+        }
+
+        let mut prefix = None;
+        let mut suffixes = Vec::new();
+        for (index, segment) in segments.iter().enumerate() {
+            if index == base_index {
+                continue;
+            }
+            let suffix = if index > base_index {
+                Suffix::parse(segment)
+            } else {
+                None
+            };
+            match suffix {
+                Some(suffix) => suffixes.push(suffix),
+                None if prefix.is_none() => prefix = Some(segment.to_string()),
+                None => return Err(CallsignError::MultiplePrefixes),
+            }
+        }
+
+        Ok(CallsignParts {
+            prefix,
+            base: base.to_string(),
+            suffixes,
+        })
+    }
+
+    /// Prefix of the base call: everything up to and including its last
+    /// digit, so `G4` for `G4ABC` and `3DA0` for `3DA0XYZ`.
+    pub fn home_prefix(&self) -> &str {
+        let end = self
+            .base
+            .rfind(|c: char| c.is_ascii_digit())
+            .map_or(self.base.len(), |index| index + 1);
+        &self.base[..end]
+    }
+
+    pub fn has_suffix(&self, suffix: &Suffix) -> bool {
+        self.suffixes.contains(suffix)
+    }
+
+    /// Work out which prefix places the station. `/MM` and `/AM` override
+    /// everything, then an operating prefix, then a call-area digit. The
+    /// digit replaces the home call's numeral only where that numeral is a
+    /// call area: `KH6ABC/4` is still in Hawaii.
+    pub fn location(&self) -> Location {
+        if self.has_suffix(&Suffix::MaritimeMobile) || self.has_suffix(&Suffix::AeronauticalMobile)
+        {
+            return Location::NoEntity;
+        }
+        if let Some(prefix) = &self.prefix {
+            return Location::Prefix(prefix.clone());
+        }
+        let area = self.suffixes.iter().find_map(|suffix| match suffix {
+            Suffix::CallArea(digit) => Some(*digit),
+            _ => None,
+        });
+        let home = self.home_prefix();
+        match area {
+            Some(digit) if !NUMBERED_PREFIXES.contains(&&home[..home.len() - 1]) => {
+                Location::Prefix(format!("{}{digit}", &home[..home.len() - 1]))
+            }
+            _ => Location::Home,
+        }
+    }
+
+    /// The prefix that determines location, or `None` for `/MM` and `/AM`.
+    pub fn location_prefix(&self) -> Option<String> {
+        match self.location() {
+            Location::Home => Some(self.home_prefix().to_string()),
+            Location::Prefix(prefix) => Some(prefix),
+            Location::NoEntity => None,
+        }
+    }
+}
+
+impl fmt::Display for CallsignParts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, "{prefix}/")?;
+        }
+        f.write_str(&self.base)?;
+        for suffix in &self.suffixes {
+            write!(f, "/{suffix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A complete call has a digit, not in first place, followed by letters:
+/// `K1ABC`, `2E0ABC`, `3DA0XYZ` but not `W1`, `EA8` or `2E`.
+fn is_complete_call(segment: &str) -> bool {
+    let Some(last_digit) = segment.rfind(|c: char| c.is_ascii_digit()) else {
+        return false;
+    };
+    last_digit > 0
+        && last_digit + 1 < segment.len()
+        && segment.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(call: &str) -> CallsignParts {
+        CallsignParts::parse(call).unwrap()
+    }
+
+    #[test]
+    fn splits_prefix_base_and_suffixes() {
+        let call = parts("W1/G4ABC/P");
+        assert_eq!(call.prefix.as_deref(), Some("W1"));
+        assert_eq!(call.base, "G4ABC");
+        assert_eq!(call.suffixes, vec![Suffix::Portable]);
+
+        let call = parts("KH6/W1AW/M");
+        assert_eq!(call.prefix.as_deref(), Some("KH6"));
+        assert_eq!(call.base, "W1AW");
+        assert_eq!(call.suffixes, vec![Suffix::Mobile]);
+        assert_eq!(call.to_string(), "KH6/W1AW/M");
+
+        let call = parts("dl1abc/qrp");
+        assert_eq!(call.prefix, None);
+        assert_eq!(call.base, "DL1ABC");
+        assert_eq!(call.suffixes, vec![Suffix::Qrp]);
+
+        assert_eq!(parts("G4ABC-2").base, "G4ABC");
+
+        let call = Callsign::parse_loose("EA8/G4ABC/P").unwrap();
+        assert_eq!(call.base_call().as_str(), "G4ABC");
+    }
+
+    #[test]
+    fn picks_base_among_call_like_segments() {
+        let call = parts("VP2E/K1ABC");
+        assert_eq!(call.prefix.as_deref(), Some("VP2E"));
+        assert_eq!(call.base, "K1ABC");
+
+        let call = parts("G4ABC/EA8");
+        assert_eq!(call.prefix.as_deref(), Some("EA8"));
+        assert_eq!(call.base, "G4ABC");
+        assert!(call.suffixes.is_empty());
+
+        let call = parts("F/G4ABC");
+        assert_eq!(call.prefix.as_deref(), Some("F"));
+    }
+
+    #[test]
+    fn determines_governing_prefix() {
+        assert_eq!(parts("G4ABC/P").location(), Location::Home);
+        assert_eq!(parts("G4ABC/P").location_prefix().as_deref(), Some("G4"));
+        assert_eq!(parts("3DA0XYZ").home_prefix(), "3DA0");
+        assert_eq!(
+            parts("W1/G4ABC/P").location(),
+            Location::Prefix("W1".to_string())
+        );
+        assert_eq!(
+            parts("VP2E/K1ABC").location_prefix().as_deref(),
+            Some("VP2E")
+        );
+        assert_eq!(parts("W1ABC/4").location_prefix().as_deref(), Some("W4"));
+        assert_eq!(parts("KH6ABC/4").location(), Location::Home);
+        assert_eq!(parts("KH6ABC/4").location_prefix().as_deref(), Some("KH6"));
+        assert_eq!(parts("VP2EAB/4").location(), Location::Home);
+        assert_eq!(parts("K1ABC/MM").location(), Location::NoEntity);
+        assert_eq!(parts("KH6/W1AW/AM").location_prefix(), None);
+    }
+
+    #[test]
+    fn rejects_impossible_structures() {
+        let cases = [
+            ("", CallsignError::Empty),
+            ("G4ABC//P", CallsignError::EmptySegment),
+            ("/G4ABC", CallsignError::EmptySegment),
+            ("W1/G4ABC/P/QRP", CallsignError::TooManySegments(4)),
+            ("W1/EA8", CallsignError::MissingBase),
+            ("QRP", CallsignError::MissingBase),
+            ("W1/VP2E/G4ABC", CallsignError::MultiplePrefixes),
+            ("AB1CDEFGHIJ", CallsignError::BaseTooLong(11)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(CallsignParts::parse(input), Err(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn strict_parsing_reports_specific_errors() {
+        assert_eq!(
+            Callsign::parse_strict("not-a-call"),
+            Err(CallsignError::InvalidCharacter('-'))
+        );
+        assert_eq!(
+            Callsign::parse_strict("G4ABC/"),
+            Err(CallsignError::EmptySegment)
+        );
+        assert_eq!(
+            Callsign::parse_strict("NOCALL"),
+            Err(CallsignError::MissingBase)
+        );
+        assert_eq!(
+            Callsign::parse_strict(" kh6/w1aw/m ").unwrap().as_str(),
+            "KH6/W1AW/M"
+        );
+    }
+}
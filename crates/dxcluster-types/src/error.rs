@@ -1,15 +1,73 @@
+use crate::frequency::FrequencyUnit;
+
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum CallsignError {
     #[error("callsign is empty")]
     Empty,
-    #[error("callsign has invalid format")]
-    InvalidFormat,
+    #[error("callsign contains invalid character {0:?}")]
+    InvalidCharacter(char),
+    #[error("callsign has an empty '/' segment")]
+    EmptySegment,
+    #[error("callsign has {0} '/' segments, at most 3 are allowed")]
+    TooManySegments(usize),
+    #[error("callsign has no base call (a digit followed by letters)")]
+    MissingBase,
+    #[error("callsign has more than one operating prefix")]
+    MultiplePrefixes,
+    #[error("base call is {0} characters, at most 10 are allowed")]
+    BaseTooLong(usize),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum FrequencyError {
     #[error("frequency value is missing")]
     Missing,
-    #[error("frequency string is invalid")]
-    Invalid,
+    #[error("frequency must not be negative")]
+    Negative,
+    #[error("{0:?} is not a decimal frequency")]
+    NotANumber(String),
+    #[error("exponent notation is not accepted in frequencies")]
+    Exponent,
+    #[error("unknown frequency unit {0:?}")]
+    UnknownUnit(String),
+    #[error("frequency has more than one decimal point")]
+    MultipleDecimalPoints,
+    #[error("{unit} frequencies allow at most {max} decimal places")]
+    TooManyDecimals { unit: FrequencyUnit, max: usize },
+    #[error("frequency is too large")]
+    Overflow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SpotIdError {
+    #[error("spot id must be 64 hex digits, got {0} characters")]
+    InvalidLength(usize),
+    #[error("spot id contains non-hex character {0:?}")]
+    InvalidDigit(char),
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LocatorError {
+    #[error("locator must be 4, 6 or 8 characters, got {0}")]
+    InvalidLength(usize),
+    #[error("invalid locator character {found:?} at position {position}")]
+    InvalidCharacter { position: usize, found: char },
+    #[error("coordinates {latitude}, {longitude} are out of range")]
+    OutOfRange { latitude: f64, longitude: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BandError {
+    #[error("unknown band: {0}")]
+    UnknownBand(String),
+    #[error("unknown IARU region: {0}")]
+    UnknownRegion(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ModeError {
+    #[error("mode is empty")]
+    Empty,
+    #[error("invalid mode: {0}")]
+    Invalid(String),
 }
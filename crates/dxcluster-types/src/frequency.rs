@@ -1,26 +1,122 @@
+//! Frequencies held as whole hertz and parsed as exact decimals; no value
+//! passes through floating point.
+
 use std::{fmt, str::FromStr};
 
 use crate::error::FrequencyError;
-use crate::normalize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct FrequencyHz(pub u64);
 
+/// Unit of a frequency string, given as a suffix or assumed by the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrequencyUnit {
+    Hz,
+    KHz,
+    MHz,
+    GHz,
+}
+
+impl FrequencyUnit {
+    /// Decimal places that still resolve to whole hertz.
+    pub const fn decimals(self) -> usize {
+        match self {
+            FrequencyUnit::Hz => 0,
+            FrequencyUnit::KHz => 3,
+            FrequencyUnit::MHz => 6,
+            FrequencyUnit::GHz => 9,
+        }
+    }
+
+    pub const fn hz(self) -> u64 {
+        10u64.pow(self.decimals() as u32)
+    }
+
+    /// Unit named by a suffix such as `kHz` or `MHZ`, ignoring case.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        let unit = match suffix.to_ascii_lowercase().as_str() {
+            "hz" => FrequencyUnit::Hz,
+            "k" | "khz" => FrequencyUnit::KHz,
+            "m" | "mhz" => FrequencyUnit::MHz,
+            "g" | "ghz" => FrequencyUnit::GHz,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+impl fmt::Display for FrequencyUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FrequencyUnit::Hz => "Hz",
+            FrequencyUnit::KHz => "kHz",
+            FrequencyUnit::MHz => "MHz",
+            FrequencyUnit::GHz => "GHz",
+        })
+    }
+}
+
 impl FrequencyHz {
+    /// Parse a kilohertz value such as `14074.5`, the unit used on the
+    /// wire. An explicit suffix (`Hz`, `kHz`, `MHz`, `GHz`) overrides it.
     pub fn from_khz_str(input: &str) -> Result<Self, FrequencyError> {
-        let cleaned = normalize::trim_units(input);
-        if cleaned.is_empty() {
+        FrequencyHz::parse(input, FrequencyUnit::KHz)
+    }
+
+    /// Parse a plain decimal with an optional unit suffix, assuming
+    /// `default_unit` when there is none. Digits past whole hertz are only
+    /// accepted when they are zero.
+    pub fn parse(input: &str, default_unit: FrequencyUnit) -> Result<Self, FrequencyError> {
+        let input = input.trim();
+        if input.is_empty() {
             return Err(FrequencyError::Missing);
         }
+        if input.starts_with('-') {
+            return Err(FrequencyError::Negative);
+        }
 
-        let value: f64 = cleaned.parse().map_err(|_| FrequencyError::Invalid)?;
-        let hz = (value * 1000.0).round();
-        if hz.is_sign_negative() {
-            return Err(FrequencyError::Invalid);
+        let end = input
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(input.len());
+        let (number, suffix) = input.split_at(end);
+        if !number.bytes().any(|b| b.is_ascii_digit()) {
+            return Err(FrequencyError::NotANumber(input.to_string()));
         }
-        Ok(FrequencyHz(hz as u64))
+
+        let suffix = suffix.trim_start();
+        let unit = if suffix.is_empty() {
+            default_unit
+        } else if is_exponent(suffix) {
+            return Err(FrequencyError::Exponent);
+        } else {
+            FrequencyUnit::from_suffix(suffix)
+                .ok_or_else(|| FrequencyError::UnknownUnit(suffix.to_string()))?
+        };
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if fraction.contains('.') {
+            return Err(FrequencyError::MultipleDecimalPoints);
+        }
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > unit.decimals() {
+            return Err(FrequencyError::TooManyDecimals {
+                unit,
+                max: unit.decimals(),
+            });
+        }
+
+        let whole_hz = digits_value(whole)?
+            .checked_mul(unit.hz())
+            .ok_or(FrequencyError::Overflow)?;
+        let scale = 10u64.pow((unit.decimals() - fraction.len()) as u32);
+        let fraction_hz = digits_value(fraction)? * scale;
+        whole_hz
+            .checked_add(fraction_hz)
+            .map(FrequencyHz)
+            .ok_or(FrequencyError::Overflow)
     }
 
+    /// Kilohertz with up to three decimals, e.g. `14074` or `5357.500`.
     pub fn to_khz_string(&self) -> String {
         let khz = self.0 / 1000;
         let remainder = self.0 % 1000;
@@ -32,6 +128,24 @@ impl FrequencyHz {
     }
 }
 
+/// `e4`, `E+6`, `e-3` and the like.
+fn is_exponent(suffix: &str) -> bool {
+    let mut chars = suffix.chars();
+    matches!(chars.next(), Some('e' | 'E'))
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_digit() || c == '+' || c == '-')
+}
+
+fn digits_value(digits: &str) -> Result<u64, FrequencyError> {
+    digits.bytes().try_fold(0u64, |value, digit| {
+        value
+            .checked_mul(10)
+            .and_then(|value| value.checked_add(u64::from(digit - b'0')))
+            .ok_or(FrequencyError::Overflow)
+    })
+}
+
 impl fmt::Display for FrequencyHz {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(&self.to_khz_string())
@@ -45,3 +159,75 @@ impl FromStr for FrequencyHz {
         FrequencyHz::from_khz_str(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn khz(input: &str) -> Result<u64, FrequencyError> {
+        FrequencyHz::from_khz_str(input).map(|freq| freq.0)
+    }
+
+    #[test]
+    fn parses_exact_decimals() {
+        assert_eq!(khz("14074"), Ok(14_074_000));
+        assert_eq!(khz(" 14074.5 "), Ok(14_074_500));
+        assert_eq!(khz("5357.001"), Ok(5_357_001));
+        assert_eq!(khz("14074.5000"), Ok(14_074_500));
+        assert_eq!(khz(".5"), Ok(500));
+        assert_eq!(khz("1296."), Ok(1_296_000));
+    }
+
+    #[test]
+    fn honours_unit_suffixes() {
+        assert_eq!(khz("14.074MHz"), Ok(14_074_000));
+        assert_eq!(khz("10368.100250 mhz"), Ok(10_368_100_250));
+        assert_eq!(khz("14074500 Hz"), Ok(14_074_500));
+        assert_eq!(khz("14074kHz"), Ok(14_074_000));
+        assert_eq!(khz("10.368100250GHz"), Ok(10_368_100_250));
+        assert_eq!(
+            FrequencyHz::parse("144.174", FrequencyUnit::MHz),
+            Ok(FrequencyHz(144_174_000))
+        );
+    }
+
+    #[test]
+    fn rejects_garbage_with_distinct_errors() {
+        assert_eq!(khz(""), Err(FrequencyError::Missing));
+        assert_eq!(khz("-14074"), Err(FrequencyError::Negative));
+        assert_eq!(khz("nan"), Err(FrequencyError::NotANumber("nan".into())));
+        assert_eq!(khz("inf"), Err(FrequencyError::NotANumber("inf".into())));
+        assert_eq!(khz("."), Err(FrequencyError::NotANumber(".".into())));
+        assert_eq!(khz("1e4"), Err(FrequencyError::Exponent));
+        assert_eq!(khz("1.4E+4"), Err(FrequencyError::Exponent));
+        assert_eq!(
+            khz("14074 ft8"),
+            Err(FrequencyError::UnknownUnit("ft8".into()))
+        );
+        assert_eq!(khz("14.07.4"), Err(FrequencyError::MultipleDecimalPoints));
+        assert_eq!(
+            khz("14074.0001"),
+            Err(FrequencyError::TooManyDecimals {
+                unit: FrequencyUnit::KHz,
+                max: 3
+            })
+        );
+        assert_eq!(
+            khz("14074500.5Hz"),
+            Err(FrequencyError::TooManyDecimals {
+                unit: FrequencyUnit::Hz,
+                max: 0
+            })
+        );
+        assert_eq!(khz("99999999999999999999"), Err(FrequencyError::Overflow));
+        assert_eq!(khz("18446744073709552GHz"), Err(FrequencyError::Overflow));
+    }
+
+    #[test]
+    fn sub_khz_values_round_trip() {
+        for hz in [5_357_500, 10_368_100_250, 14_074_001, 1_840_000] {
+            let freq = FrequencyHz(hz);
+            assert_eq!(freq.to_khz_string().parse(), Ok(freq));
+        }
+    }
+}
@@ -1,29 +1,217 @@
+use std::{fmt, str::FromStr};
+
+use crate::callsign::Callsign;
+use crate::error::SpotIdError;
+use crate::frequency::FrequencyHz;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NodeId(pub String);
 
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SpotId(pub [u8; 32]);
 
+/// Width of the frequency buckets used by [`SpotId::canonical`].
+pub const IDENTITY_FREQ_BUCKET_HZ: u64 = 1_000;
+/// Width of the time buckets used by [`SpotId::canonical`].
+pub const IDENTITY_TIME_BUCKET_SECS: i64 = 60;
+
 impl SpotId {
+    /// 64 lower-case hex digits.
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Parse 64 hex digits in either case.
+    pub fn from_hex(input: &str) -> Result<Self, SpotIdError> {
+        if input.len() != 64 {
+            return Err(SpotIdError::InvalidLength(input.len()));
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(input.as_bytes().chunks_exact(2)) {
+            let digit = |b: u8| {
+                char::from(b)
+                    .to_digit(16)
+                    .ok_or(SpotIdError::InvalidDigit(char::from(b)))
+            };
+            *byte = (digit(pair[0])? * 16 + digit(pair[1])?) as u8;
+        }
+        Ok(SpotId(bytes))
+    }
+
+    /// Hash `parts` into an id.
+    ///
+    /// Uses BLAKE3 with the `hash_blake3` feature and SHA-256 without it.
+    /// Both are deterministic, but they give different ids, so every node
+    /// in a network must be built the same way. BLAKE3 hashes the parts
+    /// concatenated, as deployed nodes always have, so their ids stay
+    /// compatible; the SHA-256 fallback length-prefixes each part, so
+    /// `["ab", "c"]` and `["a", "bc"]` differ.
     pub fn hash_components(parts: &[&[u8]]) -> Self {
-        if cfg!(feature = "hash_blake3") {
-            #[cfg(feature = "hash_blake3")]
-            {
-                use blake3::Hasher;
-                let mut hasher = Hasher::new();
-                for part in parts {
-                    hasher.update(part);
-                }
-                let mut bytes = [0u8; 32];
-                bytes.copy_from_slice(hasher.finalize().as_bytes());
-                return SpotId(bytes);
+        #[cfg(feature = "hash_blake3")]
+        {
+            let mut hasher = blake3::Hasher::new();
+            for part in parts {
+                hasher.update(part);
             }
+            SpotId(*hasher.finalize().as_bytes())
         }
 
-        let mut bytes = [0u8; 32];
-        for (idx, part) in parts.iter().enumerate() {
-            bytes[idx % 32] ^= part.len() as u8;
+        #[cfg(not(feature = "hash_blake3"))]
+        {
+            let mut hasher = crate::sha256::Sha256::new();
+            for part in parts {
+                hasher.update(&(part.len() as u64).to_be_bytes());
+                hasher.update(part);
+            }
+            SpotId(hasher.finalize())
         }
-        SpotId(bytes)
+    }
+
+    /// Identity of a human spot, the same on every node that hears it.
+    ///
+    /// Callsigns are compared upper-case, the frequency is rounded to the
+    /// nearest [`IDENTITY_FREQ_BUCKET_HZ`] and the timestamp is truncated
+    /// to [`IDENTITY_TIME_BUCKET_SECS`], so a spot relayed with a
+    /// minute-resolution `HHMMZ` time keeps the id given by its origin.
+    pub fn canonical(
+        dx: &Callsign,
+        spotter: &Callsign,
+        freq: FrequencyHz,
+        unix_timestamp: i64,
+    ) -> Self {
+        let bucket = (freq.0 + IDENTITY_FREQ_BUCKET_HZ / 2) / IDENTITY_FREQ_BUCKET_HZ;
+        let minute = unix_timestamp.div_euclid(IDENTITY_TIME_BUCKET_SECS);
+        SpotId::hash_components(&[
+            dx.as_str().to_ascii_uppercase().as_bytes(),
+            spotter.as_str().to_ascii_uppercase().as_bytes(),
+            &bucket.to_be_bytes(),
+            &minute.to_be_bytes(),
+        ])
+    }
+}
+
+impl fmt::Display for SpotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for SpotId {
+    type Err = SpotIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SpotId::from_hex(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(value: &str) -> Callsign {
+        Callsign::parse_loose(value).unwrap()
+    }
+
+    #[test]
+    fn hashes_are_deterministic_and_spread() {
+        let split = SpotId::hash_components(&[b"ab", b"c"]);
+        assert_eq!(split, SpotId::hash_components(&[b"ab", b"c"]));
+
+        // Equal-length parts used to collide without blake3.
+        let a = SpotId::hash_components(&[b"K1ABC", &14_074_000u64.to_be_bytes()]);
+        let b = SpotId::hash_components(&[b"K1ABD", &14_074_000u64.to_be_bytes()]);
+        assert_ne!(a, b);
+    }
+
+    #[cfg(feature = "hash_blake3")]
+    #[test]
+    fn blake3_ids_match_deployed_nodes() {
+        let id = SpotId::hash_components(&[b"K1ABC", b"14074"]);
+        assert_eq!(id.0, *blake3::hash(b"K1ABC14074").as_bytes());
+    }
+
+    #[cfg(not(feature = "hash_blake3"))]
+    #[test]
+    fn fallback_hashes_are_framed() {
+        assert_ne!(
+            SpotId::hash_components(&[b"ab", b"c"]),
+            SpotId::hash_components(&[b"a", b"bc"])
+        );
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let id = SpotId::hash_components(&[b"K1ABC"]);
+        let hex = id.to_string();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(hex.parse(), Ok(id.clone()));
+        assert_eq!(SpotId::from_hex(&hex.to_uppercase()), Ok(id));
+        assert_eq!(SpotId::from_hex("abc"), Err(SpotIdError::InvalidLength(3)));
+        assert_eq!(
+            SpotId::from_hex(&"g".repeat(64)),
+            Err(SpotIdError::InvalidDigit('g'))
+        );
+    }
+
+    #[test]
+    fn canonical_identity_absorbs_relay_noise() {
+        let id = SpotId::canonical(
+            &call("DL1ABC"),
+            &call("K1XYZ"),
+            FrequencyHz(14_074_000),
+            120,
+        );
+        assert_eq!(
+            SpotId::canonical(
+                &call("dl1abc"),
+                &call("k1xyz"),
+                FrequencyHz(14_074_300),
+                179
+            ),
+            id
+        );
+        assert_eq!(
+            SpotId::canonical(
+                &call("DL1ABC"),
+                &call("K1XYZ"),
+                FrequencyHz(14_073_600),
+                150
+            ),
+            id
+        );
+
+        assert_ne!(
+            SpotId::canonical(
+                &call("DL1ABC"),
+                &call("K1XYZ"),
+                FrequencyHz(14_075_000),
+                120
+            ),
+            id
+        );
+        assert_ne!(
+            SpotId::canonical(
+                &call("DL1ABC"),
+                &call("K1XYZ"),
+                FrequencyHz(14_074_000),
+                180
+            ),
+            id
+        );
+        assert_ne!(
+            SpotId::canonical(
+                &call("DL1ABC"),
+                &call("G4ABC"),
+                FrequencyHz(14_074_000),
+                120
+            ),
+            id
+        );
     }
 }
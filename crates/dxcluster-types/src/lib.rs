@@ -1,16 +1,39 @@
 //! Core primitive types for dxcluster.
+//!
+//! With the `serde` feature the types serialize to JSON as follows, and
+//! deserializing runs the same validation as parsing (strict parsing for
+//! callsigns):
+//!
+//! | Type | JSON | Example |
+//! |------|------|---------|
+//! | [`Callsign`] | upper-case string | `"DL1ABC/P"` |
+//! | [`FrequencyHz`] | integer hertz | `14074500` |
+//! | [`Band`] | band label | `"20m"` |
+//! | [`Region`] | IARU region | `"R1"` |
+//! | [`Mode`] | canonical name | `"FT8"` |
+//! | [`Locator`] | Maidenhead locator | `"IO91wm"` |
+//! | [`NodeId`] | string without whitespace or `\|` | `"GB7DJK"` |
+//! | [`SpotId`] | 64 lower-case hex digits | `"9f86d0..."` |
 
 pub mod band;
+pub mod bandplan;
 pub mod callsign;
 pub mod error;
 pub mod frequency;
 pub mod ids;
+pub mod locator;
 pub mod mode;
 pub mod normalize;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(any(test, not(feature = "hash_blake3")))]
+mod sha256;
 
-pub use band::Band;
-pub use callsign::Callsign;
-pub use error::{CallsignError, FrequencyError};
-pub use frequency::FrequencyHz;
+pub use band::{Band, Region};
+pub use bandplan::{Segment, SegmentKind};
+pub use callsign::{Callsign, CallsignParts, Location, Suffix};
+pub use error::{BandError, CallsignError, FrequencyError, LocatorError, ModeError, SpotIdError};
+pub use frequency::{FrequencyHz, FrequencyUnit};
 pub use ids::{NodeId, SpotId};
+pub use locator::Locator;
 pub use mode::Mode;
@@ -0,0 +1,273 @@
+//! Maidenhead grid locators and great-circle geometry.
+//!
+//! Distances use a spherical earth of radius [`EARTH_RADIUS_KM`]; bearings
+//! are degrees clockwise from true north. Coordinates are `(latitude,
+//! longitude)` in degrees, north and east positive.
+
+use std::{fmt, str::FromStr};
+
+use crate::error::LocatorError;
+
+/// Mean earth radius used for distances.
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Width and height in degrees of each pair's cells: field, square,
+/// subsquare, extended square.
+const CELL_DEGREES: [(f64, f64); 4] = [
+    (20.0, 10.0),
+    (2.0, 1.0),
+    (2.0 / 24.0, 1.0 / 24.0),
+    (2.0 / 240.0, 1.0 / 240.0),
+];
+
+/// A validated 4, 6 or 8 character Maidenhead locator such as `IO91wm`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locator(String);
+
+impl Locator {
+    /// Parse a locator, ignoring case. Fields are `A`-`R`, squares and
+    /// extended squares `0`-`9`, subsquares `A`-`X`.
+    pub fn parse(input: &str) -> Result<Self, LocatorError> {
+        let input = input.trim();
+        if !matches!(input.len(), 4 | 6 | 8) {
+            return Err(LocatorError::InvalidLength(input.chars().count()));
+        }
+
+        let mut canonical = String::with_capacity(input.len());
+        for (position, c) in input.chars().enumerate() {
+            let valid = match position / 2 {
+                0 => matches!(c.to_ascii_uppercase(), 'A'..='R'),
+                2 => matches!(c.to_ascii_uppercase(), 'A'..='X'),
+                _ => c.is_ascii_digit(),
+            };
+            if !valid {
+                return Err(LocatorError::InvalidCharacter { position, found: c });
+            }
+            canonical.push(if position / 2 == 2 {
+                c.to_ascii_lowercase()
+            } else {
+                c.to_ascii_uppercase()
+            });
+        }
+        Ok(Locator(canonical))
+    }
+
+    /// The locator of `chars` characters (4, 6 or 8) containing a point.
+    pub fn from_lat_lon(latitude: f64, longitude: f64, chars: usize) -> Result<Self, LocatorError> {
+        if !matches!(chars, 4 | 6 | 8) {
+            return Err(LocatorError::InvalidLength(chars));
+        }
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(LocatorError::OutOfRange {
+                latitude,
+                longitude,
+            });
+        }
+
+        // Keep the north pole and antimeridian inside the last cell.
+        let mut lon = (longitude + 180.0).min(360.0 - 1e-9);
+        let mut lat = (latitude + 90.0).min(180.0 - 1e-9);
+        let mut locator = String::with_capacity(chars);
+        for (pair, (width, height)) in CELL_DEGREES.iter().take(chars / 2).enumerate() {
+            let x = (lon / width).floor();
+            let y = (lat / height).floor();
+            lon -= x * width;
+            lat -= y * height;
+            let base = match pair {
+                0 => b'A',
+                2 => b'a',
+                _ => b'0',
+            };
+            locator.push(char::from(base + x as u8));
+            locator.push(char::from(base + y as u8));
+        }
+        Ok(Locator(locator))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Number of characters: 4, 6 or 8.
+    pub fn precision(&self) -> usize {
+        self.0.len()
+    }
+
+    /// South-west corner of the square as `(latitude, longitude)`.
+    pub fn south_west(&self) -> (f64, f64) {
+        let bytes = self.0.as_bytes();
+        let (mut lat, mut lon) = (-90.0, -180.0);
+        for (pair, (width, height)) in CELL_DEGREES.iter().enumerate().take(bytes.len() / 2) {
+            let base = match pair {
+                0 => b'A',
+                2 => b'a',
+                _ => b'0',
+            };
+            lon += f64::from(bytes[pair * 2] - base) * width;
+            lat += f64::from(bytes[pair * 2 + 1] - base) * height;
+        }
+        (lat, lon)
+    }
+
+    /// Centre of the square as `(latitude, longitude)`.
+    pub fn center(&self) -> (f64, f64) {
+        let (lat, lon) = self.south_west();
+        let (width, height) = CELL_DEGREES[self.0.len() / 2 - 1];
+        (lat + height / 2.0, lon + width / 2.0)
+    }
+
+    /// Short-path distance between the centres of two squares.
+    pub fn distance_km(&self, other: &Locator) -> f64 {
+        distance_km(self.center(), other.center())
+    }
+
+    /// Short-path bearing from this square's centre to `other`'s.
+    pub fn bearing_to(&self, other: &Locator) -> f64 {
+        bearing(self.center(), other.center())
+    }
+
+    /// Long-path bearing, the reciprocal of the short path.
+    pub fn long_path_bearing_to(&self, other: &Locator) -> f64 {
+        long_path_bearing(self.center(), other.center())
+    }
+
+    /// Long-path distance, the rest of the great circle.
+    pub fn long_path_distance_km(&self, other: &Locator) -> f64 {
+        long_path_distance_km(self.center(), other.center())
+    }
+}
+
+impl fmt::Display for Locator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Locator {
+    type Err = LocatorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locator::parse(s)
+    }
+}
+
+/// Great-circle distance between two points (haversine).
+pub fn distance_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Initial short-path bearing from `from` towards `to`, in `[0, 360)`.
+pub fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let delta = lon2 - lon1;
+    let y = delta.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+pub fn long_path_bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    (bearing(from, to) + 180.0).rem_euclid(360.0)
+}
+
+pub fn long_path_distance_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    2.0 * std::f64::consts::PI * EARTH_RADIUS_KM - distance_km(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) -> bool {
+        (actual - expected).abs() <= tolerance
+    }
+
+    #[test]
+    fn parses_and_normalises_case() {
+        assert_eq!(Locator::parse("io91WM").unwrap().as_str(), "IO91wm");
+        assert_eq!(Locator::parse("JO01").unwrap().precision(), 4);
+        assert_eq!(Locator::parse("fn31pr45").unwrap().to_string(), "FN31pr45");
+    }
+
+    #[test]
+    fn rejects_invalid_locators() {
+        assert_eq!(Locator::parse("IO9"), Err(LocatorError::InvalidLength(3)));
+        assert_eq!(
+            Locator::parse("SO91"),
+            Err(LocatorError::InvalidCharacter {
+                position: 0,
+                found: 'S'
+            })
+        );
+        assert_eq!(
+            Locator::parse("IO91wz"),
+            Err(LocatorError::InvalidCharacter {
+                position: 5,
+                found: 'z'
+            })
+        );
+        assert_eq!(
+            Locator::parse("IO91wm4x"),
+            Err(LocatorError::InvalidCharacter {
+                position: 7,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn converts_to_and_from_coordinates() {
+        let (lat, lon) = Locator::parse("IO91wm").unwrap().center();
+        assert!(close(lat, 51.52, 0.01), "{lat}");
+        assert!(close(lon, -0.125, 0.01), "{lon}");
+
+        assert_eq!(
+            Locator::from_lat_lon(51.5074, -0.1278, 6).unwrap().as_str(),
+            "IO91wm"
+        );
+        assert_eq!(
+            Locator::from_lat_lon(-33.8688, 151.2093, 8)
+                .unwrap()
+                .as_str(),
+            "QF56od51"
+        );
+        assert_eq!(
+            Locator::from_lat_lon(90.0, 180.0, 4).unwrap().as_str(),
+            "RR99"
+        );
+        assert!(Locator::from_lat_lon(91.0, 0.0, 4).is_err());
+
+        for locator in ["AA00", "JO01ab", "RR99xx99", "FN31pr45"] {
+            let locator = Locator::parse(locator).unwrap();
+            let (lat, lon) = locator.center();
+            assert_eq!(
+                Locator::from_lat_lon(lat, lon, locator.precision()).unwrap(),
+                locator
+            );
+        }
+    }
+
+    #[test]
+    fn computes_distance_and_bearings() {
+        let london = Locator::parse("IO91wm").unwrap();
+        let new_york = Locator::parse("FN30as").unwrap();
+
+        let distance = london.distance_km(&new_york);
+        assert!(close(distance, 5570.0, 15.0), "{distance}");
+        let bearing = london.bearing_to(&new_york);
+        assert!(close(bearing, 288.0, 1.0), "{bearing}");
+        let long_path = london.long_path_bearing_to(&new_york);
+        assert!(close(long_path, 108.0, 1.0), "{long_path}");
+        assert!(close(
+            london.long_path_distance_km(&new_york) + distance,
+            40_030.0,
+            1.0
+        ));
+
+        assert_eq!(london.distance_km(&london), 0.0);
+    }
+}
@@ -1,8 +1,255 @@
+use std::{fmt, str::FromStr};
+
+use crate::bandplan::{Segment, SegmentKind};
+use crate::error::ModeError;
+use crate::frequency::FrequencyHz;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Mode {
     Cw,
     Ssb,
     Fm,
+    Am,
+    Rtty,
+    Psk31,
+    Ft8,
+    Ft4,
+    Jt65,
+    Jt9,
+    Msk144,
+    Q65,
+    Js8,
+    Olivia,
+    Sstv,
+    /// Unspecified digital mode.
     Data,
     Other(String),
 }
+
+/// FT8 and FT4 dial frequencies in kHz; their signals sit up to 3 kHz
+/// above.
+const FT8_DIALS_KHZ: [u64; 13] = [
+    1_840, 3_573, 5_357, 7_074, 10_136, 14_074, 18_100, 21_074, 24_915, 28_074, 50_313, 70_154,
+    144_174,
+];
+const FT4_DIALS_HZ: [u64; 9] = [
+    3_575_000, 7_047_500, 10_140_000, 14_080_000, 18_104_000, 21_140_000, 24_919_000, 28_180_000,
+    50_318_000,
+];
+const DIAL_WIDTH_HZ: u64 = 3_000;
+
+/// Mode keywords that are also ordinary words or too broad to name a mode
+/// when they turn up in a comment: "I am QRV", "fm JO22", "phone later".
+const AMBIGUOUS_IN_COMMENTS: [&str; 6] = ["AM", "FM", "PHONE", "DATA", "DIGI", "DIGITAL"];
+
+impl Mode {
+    /// Canonical upper-case name, as used in filters and spot output.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Mode::Cw => "CW",
+            Mode::Ssb => "SSB",
+            Mode::Fm => "FM",
+            Mode::Am => "AM",
+            Mode::Rtty => "RTTY",
+            Mode::Psk31 => "PSK31",
+            Mode::Ft8 => "FT8",
+            Mode::Ft4 => "FT4",
+            Mode::Jt65 => "JT65",
+            Mode::Jt9 => "JT9",
+            Mode::Msk144 => "MSK144",
+            Mode::Q65 => "Q65",
+            Mode::Js8 => "JS8",
+            Mode::Olivia => "OLIVIA",
+            Mode::Sstv => "SSTV",
+            Mode::Data => "DATA",
+            Mode::Other(other) => other,
+        }
+    }
+
+    /// A known mode named by `word`, including common aliases such as
+    /// `USB` and `LSB` for SSB. Unknown words give `None`.
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        let mode = match word.to_ascii_uppercase().as_str() {
+            "CW" | "A1A" => Mode::Cw,
+            "SSB" | "USB" | "LSB" | "PHONE" => Mode::Ssb,
+            "FM" => Mode::Fm,
+            "AM" => Mode::Am,
+            "RTTY" => Mode::Rtty,
+            "PSK31" | "BPSK31" | "PSK" => Mode::Psk31,
+            "FT8" => Mode::Ft8,
+            "FT4" => Mode::Ft4,
+            "JT65" => Mode::Jt65,
+            "JT9" => Mode::Jt9,
+            "MSK144" => Mode::Msk144,
+            "Q65" => Mode::Q65,
+            "JS8" | "JS8CALL" => Mode::Js8,
+            "OLIVIA" => Mode::Olivia,
+            "SSTV" => Mode::Sstv,
+            "DATA" | "DIGI" | "DIGITAL" => Mode::Data,
+            _ => return None,
+        };
+        Some(mode)
+    }
+
+    pub fn is_digital(&self) -> bool {
+        !matches!(
+            self,
+            Mode::Cw | Mode::Ssb | Mode::Fm | Mode::Am | Mode::Other(_)
+        )
+    }
+
+    /// Whether a spot in `mode` is selected by this one; [`Mode::Data`]
+    /// selects every digital mode.
+    pub fn selects(&self, mode: &Mode) -> bool {
+        self == mode || (*self == Mode::Data && mode.is_digital())
+    }
+
+    /// The first unambiguous mode keyword in a spot comment, e.g. `FT8` in
+    /// `"FT8 -12dB from JO22"`. Words such as `AM` and `DATA` that are
+    /// accepted by [`Mode::from_keyword`] are skipped.
+    pub fn from_comment(comment: &str) -> Option<Self> {
+        comment
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| {
+                !word.is_empty()
+                    && !AMBIGUOUS_IN_COMMENTS
+                        .iter()
+                        .any(|ambiguous| ambiguous.eq_ignore_ascii_case(word))
+            })
+            .find_map(Mode::from_keyword)
+    }
+
+    /// Mode implied by frequency alone: FT8 and FT4 calling frequencies,
+    /// then the band-plan [`Segment`].
+    pub fn from_frequency(freq: FrequencyHz) -> Option<Self> {
+        let near = |dial: u64| (dial..=dial + DIAL_WIDTH_HZ).contains(&freq.0);
+        if FT8_DIALS_KHZ.iter().any(|khz| near(khz * 1000)) {
+            return Some(Mode::Ft8);
+        }
+        if FT4_DIALS_HZ.iter().copied().any(near) {
+            return Some(Mode::Ft4);
+        }
+        Segment::find(freq).map(|segment| match segment.kind {
+            SegmentKind::Cw => Mode::Cw,
+            SegmentKind::Digital => Mode::Data,
+            SegmentKind::Phone => Mode::Ssb,
+            SegmentKind::Fm => Mode::Fm,
+        })
+    }
+
+    /// Best guess at a spot's mode: a keyword in the comment wins, then the
+    /// frequency.
+    pub fn infer(freq: FrequencyHz, comment: &str) -> Option<Self> {
+        Mode::from_comment(comment).or_else(|| Mode::from_frequency(freq))
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ModeError;
+
+    /// Known names and aliases map to their variant; any other alphanumeric
+    /// word becomes [`Mode::Other`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = s.trim();
+        if word.is_empty() {
+            return Err(ModeError::Empty);
+        }
+        if !word.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(ModeError::Invalid(word.to_string()));
+        }
+        Ok(Mode::from_keyword(word).unwrap_or_else(|| Mode::Other(word.to_ascii_uppercase())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays() {
+        assert_eq!("usb".parse(), Ok(Mode::Ssb));
+        assert_eq!("Ft8".parse(), Ok(Mode::Ft8));
+        assert_eq!("bpsk31".parse(), Ok(Mode::Psk31));
+        assert_eq!("varac".parse(), Ok(Mode::Other("VARAC".to_string())));
+        assert_eq!("".parse::<Mode>(), Err(ModeError::Empty));
+        assert_eq!(
+            "ft-8".parse::<Mode>(),
+            Err(ModeError::Invalid("ft-8".to_string()))
+        );
+        for mode in [Mode::Cw, Mode::Msk144, Mode::Olivia, Mode::Data] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+    }
+
+    #[test]
+    fn comment_keywords_win() {
+        assert_eq!(
+            Mode::infer(FrequencyHz(14_025_000), "RTTY contest"),
+            Some(Mode::Rtty)
+        );
+        assert_eq!(
+            Mode::infer(FrequencyHz(14_200_000), "tnx QSO, cw later"),
+            Some(Mode::Cw)
+        );
+        assert_eq!(
+            Mode::infer(FrequencyHz(7_040_000), "ft4 -5dB"),
+            Some(Mode::Ft4)
+        );
+    }
+
+    #[test]
+    fn ambiguous_comment_words_are_not_modes() {
+        assert_eq!(Mode::from_comment("I am QRV now, data later"), None);
+        assert_eq!(Mode::from_comment("fm JO22 phone pse"), None);
+        assert_eq!(Mode::from_comment("am here, SSB"), Some(Mode::Ssb));
+        assert_eq!(
+            Mode::infer(FrequencyHz(14_025_000), "I am QRV"),
+            Some(Mode::Cw)
+        );
+        assert_eq!(Mode::from_keyword("AM"), Some(Mode::Am));
+        assert_eq!(Mode::from_keyword("data"), Some(Mode::Data));
+    }
+
+    #[test]
+    fn falls_back_to_frequency() {
+        assert_eq!(
+            Mode::infer(FrequencyHz(14_075_200), "-12dB JO22"),
+            Some(Mode::Ft8)
+        );
+        assert_eq!(
+            Mode::from_frequency(FrequencyHz(14_081_000)),
+            Some(Mode::Ft4)
+        );
+        assert_eq!(
+            Mode::from_frequency(FrequencyHz(14_025_000)),
+            Some(Mode::Cw)
+        );
+        assert_eq!(
+            Mode::from_frequency(FrequencyHz(14_090_000)),
+            Some(Mode::Data)
+        );
+        assert_eq!(
+            Mode::from_frequency(FrequencyHz(14_250_000)),
+            Some(Mode::Ssb)
+        );
+        assert_eq!(
+            Mode::from_frequency(FrequencyHz(29_600_000)),
+            Some(Mode::Fm)
+        );
+        assert_eq!(Mode::from_frequency(FrequencyHz(12_000_000)), None);
+    }
+
+    #[test]
+    fn data_selects_digital_modes() {
+        assert!(Mode::Data.selects(&Mode::Ft8));
+        assert!(Mode::Ft8.selects(&Mode::Ft8));
+        assert!(!Mode::Ft8.selects(&Mode::Ft4));
+        assert!(!Mode::Data.selects(&Mode::Cw));
+    }
+}
@@ -0,0 +1,137 @@
+//! `serde` support. Every type serializes as a JSON string or integer (see
+//! the crate docs) and deserializes through the same validation as its
+//! `FromStr` or constructor, or strict parsing for callsigns, so a stored
+//! value cannot bypass parsing.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Band, Callsign, FrequencyHz, Locator, Mode, NodeId, Region, SpotId};
+
+/// Serialize through `Display` and deserialize through `FromStr`.
+macro_rules! string_repr {
+    ($($ty:ty),* $(,)?) => {$(
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?
+                    .parse()
+                    .map_err(D::Error::custom)
+            }
+        }
+    )*};
+}
+
+string_repr!(Band, Region, Mode, Locator, SpotId);
+
+impl Serialize for Callsign {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Stored callsigns get the checks applied at login rather than the loose
+/// `FromStr`, which accepts any non-empty text.
+impl<'de> Deserialize<'de> for Callsign {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let call = String::deserialize(deserializer)?;
+        Callsign::parse_strict(&call).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for FrequencyHz {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for FrequencyHz {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(FrequencyHz)
+    }
+}
+
+impl Serialize for NodeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        if id.trim().is_empty() || id.chars().any(|c| c.is_whitespace() || c == '|') {
+            return Err(D::Error::custom(format!("invalid node id {id:?}")));
+        }
+        Ok(NodeId(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, json, to_value};
+
+    use super::*;
+
+    #[test]
+    fn primitives_have_stable_json() {
+        let call = Callsign::parse_loose("dl1abc/p").unwrap();
+        assert_eq!(to_value(&call).unwrap(), json!("DL1ABC/P"));
+        assert_eq!(
+            to_value(FrequencyHz(14_074_500)).unwrap(),
+            json!(14_074_500)
+        );
+        assert_eq!(to_value(Band::Meter20).unwrap(), json!("20m"));
+        assert_eq!(to_value(Region::R2).unwrap(), json!("R2"));
+        assert_eq!(to_value(Mode::Ft8).unwrap(), json!("FT8"));
+        assert_eq!(
+            to_value(Mode::Other("VARAC".into())).unwrap(),
+            json!("VARAC")
+        );
+        assert_eq!(
+            to_value(Locator::parse("io91wm").unwrap()).unwrap(),
+            json!("IO91wm")
+        );
+        assert_eq!(to_value(NodeId("GB7DJK".into())).unwrap(), json!("GB7DJK"));
+
+        let id = SpotId([0xab; 32]);
+        assert_eq!(to_value(&id).unwrap(), json!("ab".repeat(32)));
+    }
+
+    #[test]
+    fn round_trips_and_validates() {
+        assert_eq!(
+            from_str::<Callsign>(r#""k1abc""#).unwrap().as_str(),
+            "K1ABC"
+        );
+        assert_eq!(
+            from_str::<FrequencyHz>("14074000").unwrap(),
+            FrequencyHz(14_074_000)
+        );
+        assert_eq!(from_str::<Band>(r#""20m""#).unwrap(), Band::Meter20);
+        assert_eq!(from_str::<Mode>(r#""usb""#).unwrap(), Mode::Ssb);
+        let id = SpotId::hash_components(&[b"K1ABC"]);
+        assert_eq!(
+            from_str::<SpotId>(&serde_json::to_string(&id).unwrap()).unwrap(),
+            id
+        );
+
+        assert!(from_str::<Callsign>(r#""""#).is_err());
+        assert!(from_str::<Callsign>(r#""K1 ABC""#).is_err());
+        assert!(from_str::<Callsign>(r#""K1ABC|X""#).is_err());
+        assert!(from_str::<Callsign>(r#""QRP""#).is_err());
+        assert!(from_str::<FrequencyHz>("-5").is_err());
+        assert!(from_str::<FrequencyHz>(r#""14074""#).is_err());
+        assert!(from_str::<Band>(r#""11m""#).is_err());
+        assert!(from_str::<Mode>(r#""ft-8""#).is_err());
+        assert!(from_str::<Locator>(r#""ZZ99""#).is_err());
+        assert!(from_str::<SpotId>(r#""abcd""#).is_err());
+        assert!(from_str::<NodeId>(r#""""#).is_err());
+        assert!(from_str::<NodeId>(r#""GB7 DJK""#).is_err());
+    }
+}
@@ -0,0 +1,708 @@
+//! DXSpider `PCxx` node-to-node frames.
+//!
+//! DXSpider links exchange caret-separated lines such as
+//! `PC61^14025.0^K1ABC^5-Mar-2024^1253Z^cq dx^G4XYZ^GB7DJK^192.0.2.1^H96^~`.
+//! Most frames end with a hop counter `H<n>` that is decremented by every node
+//! forwarding the frame; the trailing `~` is optional on input and always
+//! written on output. Carets inside free text are escaped as `%5E`.
+//!
+//! [`PcFrame`] covers the frames needed to join a DXSpider network and
+//! converts to and from [`PeerFrame`] where the native protocol has an
+//! equivalent. The conversions share one hop budget with native links: a
+//! native hop count `n` is written as `H<max_hops - n>` and read back the
+//! same way, clamped to the budget.
+
+use dxcluster_model::{AnnounceScope, Announcement, Spot};
+use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use time::{Date, Month, OffsetDateTime, Time};
+
+use crate::error::PeerParseError;
+use crate::peer::PeerFrame;
+
+/// Hop counter given to link configuration frames that originate locally.
+pub const DEFAULT_HOPS: u32 = 99;
+
+/// Native hop count of a frame received with `H<hops>` under a budget of
+/// `max_hops`. Counters above the budget count as a fresh frame.
+pub fn hop_from_counter(hops: u32, max_hops: u32) -> u32 {
+    max_hops.saturating_sub(hops)
+}
+
+/// `H` counter for a native frame that has travelled `hop` hops under a
+/// budget of `max_hops`. Frames past the budget are sent as exhausted.
+pub fn counter_from_hop(hop: u32, max_hops: u32) -> u32 {
+    max_hops.saturating_sub(hop)
+}
+
+/// Protocol version advertised in `PC18`. DXSpider only enables the `PC9x`
+/// frames when the software string also contains `pc9x`.
+pub const PROTOCOL_VERSION: &str = "5457";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PcFrame {
+    /// `PC11` spot, or `PC61` when the spotter's IP address is known.
+    Spot(PcSpot),
+    /// `PC12` announcement.
+    Announce(PcAnnounce),
+    /// `PC93` talk/announce.
+    Talk(PcTalk),
+    /// `PC18` link initialisation sent by the accepting node.
+    LinkInit { software: String, version: String },
+    /// `PC20` request for the remote configuration.
+    ConfigRequest,
+    /// `PC22` configuration complete.
+    ConfigDone,
+    /// `PC92` node/user configuration.
+    Config(PcConfig),
+    /// `PC50` node keepalive carrying its local user count.
+    NodeUsers { node: String, users: u32, hops: u32 },
+    /// `PC51` ping (`request == true`) or its reply.
+    Ping {
+        to: String,
+        from: String,
+        request: bool,
+    },
+    /// `PC23` WWV propagation bulletin.
+    Wwv(PcWwv),
+    /// `PC73` WCY propagation bulletin.
+    Wcy(PcWcy),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcSpot {
+    pub freq: FrequencyHz,
+    pub dx: Callsign,
+    pub ts: OffsetDateTime,
+    pub comment: String,
+    pub spotter: Callsign,
+    pub origin: String,
+    pub ip: Option<String>,
+    pub hops: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcAnnounce {
+    pub from: String,
+    /// `*` for a full announce, otherwise a node or callsign.
+    pub to: String,
+    pub text: String,
+    pub sysop: bool,
+    pub origin: String,
+    pub wx: bool,
+    pub hops: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcTalk {
+    pub node: String,
+    /// Seconds since midnight as sent by the originating node.
+    pub time: String,
+    pub to: String,
+    pub from: String,
+    pub via: String,
+    pub text: String,
+    pub ip: Option<String>,
+    pub hops: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcConfig {
+    pub node: String,
+    pub time: String,
+    /// Record type such as `A` (add), `D` (delete), `C` (config) or `K`.
+    pub kind: String,
+    pub entries: Vec<String>,
+    pub hops: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcWwv {
+    pub date: Date,
+    pub hour: u8,
+    pub sfi: u16,
+    pub a: u16,
+    pub k: u16,
+    pub forecast: String,
+    pub logger: String,
+    pub origin: String,
+    pub hops: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcWcy {
+    pub date: Date,
+    pub hour: u8,
+    pub sfi: u16,
+    pub a: u16,
+    pub k: u16,
+    pub expk: u16,
+    pub r: u16,
+    pub sa: String,
+    pub gmf: String,
+    pub aurora: String,
+    pub logger: String,
+    pub origin: String,
+    pub hops: u32,
+}
+
+impl PcFrame {
+    pub fn parse(line: &str) -> Result<Self, PeerParseError> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Err(PeerParseError::Empty);
+        }
+
+        let mut fields: Vec<&str> = trimmed.split('^').collect();
+        while matches!(fields.last(), Some(&"") | Some(&"~")) {
+            fields.pop();
+        }
+        let mut fields = Fields::new(fields);
+
+        match fields.next("frame type")? {
+            "PC11" => parse_spot(&mut fields, false).map(PcFrame::Spot),
+            "PC61" => parse_spot(&mut fields, true).map(PcFrame::Spot),
+            "PC12" => Ok(PcFrame::Announce(PcAnnounce {
+                from: fields.text("from")?,
+                to: fields.text("to")?,
+                text: fields.text("text")?,
+                sysop: fields.next("sysop flag")?.trim() == "*",
+                origin: fields.text("origin")?,
+                wx: fields.next("wx flag")? == "1",
+                hops: fields.hops()?,
+            })),
+            "PC93" => {
+                let hops = fields.hops()?;
+                Ok(PcFrame::Talk(PcTalk {
+                    node: fields.text("node")?,
+                    time: fields.text("time")?,
+                    to: fields.text("to")?,
+                    from: fields.text("from")?,
+                    via: fields.text("via")?,
+                    text: fields.text("text")?,
+                    ip: fields.optional_text(),
+                    hops,
+                }))
+            }
+            "PC18" => Ok(PcFrame::LinkInit {
+                software: fields.text("software")?,
+                version: fields.text("version")?,
+            }),
+            "PC20" => Ok(PcFrame::ConfigRequest),
+            "PC22" => Ok(PcFrame::ConfigDone),
+            "PC92" => {
+                let hops = fields.hops()?;
+                Ok(PcFrame::Config(PcConfig {
+                    node: fields.text("node")?,
+                    time: fields.text("time")?,
+                    kind: fields.text("record type")?,
+                    entries: fields.rest(),
+                    hops,
+                }))
+            }
+            "PC50" => Ok(PcFrame::NodeUsers {
+                node: fields.text("node")?,
+                users: fields.number("user count")?,
+                hops: fields.hops()?,
+            }),
+            "PC51" => Ok(PcFrame::Ping {
+                to: fields.text("to")?,
+                from: fields.text("from")?,
+                request: fields.next("ping flag")? == "1",
+            }),
+            "PC23" => Ok(PcFrame::Wwv(PcWwv {
+                date: parse_date(fields.next("date")?)?,
+                hour: fields.number("hour")?,
+                sfi: fields.number("sfi")?,
+                a: fields.number("a index")?,
+                k: fields.number("k index")?,
+                forecast: fields.text("forecast")?,
+                logger: fields.text("logger")?,
+                origin: fields.text("origin")?,
+                hops: fields.hops()?,
+            })),
+            "PC73" => Ok(PcFrame::Wcy(PcWcy {
+                date: parse_date(fields.next("date")?)?,
+                hour: fields.number("hour")?,
+                sfi: fields.number("sfi")?,
+                a: fields.number("a index")?,
+                k: fields.number("k index")?,
+                expk: fields.number("expected k")?,
+                r: fields.number("sunspot number")?,
+                sa: fields.text("solar activity")?,
+                gmf: fields.text("geomagnetic field")?,
+                aurora: fields.text("aurora")?,
+                logger: fields.text("logger")?,
+                origin: fields.text("origin")?,
+                hops: fields.hops()?,
+            })),
+            _ => Err(PeerParseError::Unknown),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        match self {
+            PcFrame::Spot(spot) => {
+                let (kind, ip) = match &spot.ip {
+                    Some(ip) => ("PC61", format!("{}^", escape(ip))),
+                    None => ("PC11", String::new()),
+                };
+                format!(
+                    "{kind}^{}^{}^{}^{}^{}^{}^{}^{ip}H{}^~",
+                    format_freq(spot.freq),
+                    spot.dx,
+                    format_date(spot.ts.date()),
+                    format_time(spot.ts.time()),
+                    escape(&spot.comment),
+                    spot.spotter,
+                    escape(&spot.origin),
+                    spot.hops,
+                )
+            }
+            PcFrame::Announce(ann) => format!(
+                "PC12^{}^{}^{}^{}^{}^{}^H{}^~",
+                escape(&ann.from),
+                escape(&ann.to),
+                escape(&ann.text),
+                if ann.sysop { "*" } else { " " },
+                escape(&ann.origin),
+                if ann.wx { "1" } else { "0" },
+                ann.hops,
+            ),
+            PcFrame::Talk(talk) => {
+                let ip = talk
+                    .ip
+                    .as_ref()
+                    .map(|ip| format!("{}^", escape(ip)))
+                    .unwrap_or_default();
+                format!(
+                    "PC93^{}^{}^{}^{}^{}^{}^{ip}H{}^~",
+                    escape(&talk.node),
+                    escape(&talk.time),
+                    escape(&talk.to),
+                    escape(&talk.from),
+                    escape(&talk.via),
+                    escape(&talk.text),
+                    talk.hops,
+                )
+            }
+            PcFrame::LinkInit { software, version } => {
+                format!("PC18^{}^{}^~", escape(software), escape(version))
+            }
+            PcFrame::ConfigRequest => String::from("PC20^"),
+            PcFrame::ConfigDone => String::from("PC22^"),
+            PcFrame::Config(config) => {
+                let mut line = format!(
+                    "PC92^{}^{}^{}^",
+                    escape(&config.node),
+                    escape(&config.time),
+                    escape(&config.kind),
+                );
+                for entry in &config.entries {
+                    line.push_str(&escape(entry));
+                    line.push('^');
+                }
+                line.push_str(&format!("H{}^~", config.hops));
+                line
+            }
+            PcFrame::NodeUsers { node, users, hops } => {
+                format!("PC50^{}^{users}^H{hops}^~", escape(node))
+            }
+            PcFrame::Ping { to, from, request } => format!(
+                "PC51^{}^{}^{}^~",
+                escape(to),
+                escape(from),
+                if *request { "1" } else { "0" },
+            ),
+            PcFrame::Wwv(wwv) => format!(
+                "PC23^{}^{}^{}^{}^{}^{}^{}^{}^H{}^~",
+                format_date(wwv.date),
+                wwv.hour,
+                wwv.sfi,
+                wwv.a,
+                wwv.k,
+                escape(&wwv.forecast),
+                escape(&wwv.logger),
+                escape(&wwv.origin),
+                wwv.hops,
+            ),
+            PcFrame::Wcy(wcy) => format!(
+                "PC73^{}^{}^{}^{}^{}^{}^{}^{}^{}^{}^{}^{}^H{}^~",
+                format_date(wcy.date),
+                wcy.hour,
+                wcy.sfi,
+                wcy.a,
+                wcy.k,
+                wcy.expk,
+                wcy.r,
+                escape(&wcy.sa),
+                escape(&wcy.gmf),
+                escape(&wcy.aurora),
+                escape(&wcy.logger),
+                escape(&wcy.origin),
+                wcy.hops,
+            ),
+        }
+    }
+
+    /// Translate into the native frame with the same meaning, if any, using
+    /// the hop budget `max_hops`.
+    ///
+    /// `PC12` carries no time, so announcements are stamped with the current
+    /// time; see [`PcAnnounce::to_announcement`]. `PC93` talk frames are
+    /// handled the same way; see [`PcTalk::to_announcement`]. `PC51` pings
+    /// carry the sender's callsign as the nonce so that a [`PeerFrame::Pong`]
+    /// reply can be addressed back to it.
+    pub fn to_peer_frame(&self, max_hops: u32) -> Option<PeerFrame> {
+        match self {
+            PcFrame::Spot(spot) => Some(PeerFrame::Spot {
+                spot: Box::new(spot.to_spot(max_hops)),
+            }),
+            PcFrame::Announce(ann) => ann
+                .to_announcement(OffsetDateTime::now_utc(), max_hops)
+                .map(|announcement| PeerFrame::Announce {
+                    announcement: Box::new(announcement),
+                }),
+            PcFrame::Talk(talk) => talk
+                .to_announcement(OffsetDateTime::now_utc(), max_hops)
+                .map(|announcement| PeerFrame::Announce {
+                    announcement: Box::new(announcement),
+                }),
+            PcFrame::Ping {
+                from,
+                request: true,
+                ..
+            } => Some(PeerFrame::Ping {
+                nonce: from.clone(),
+            }),
+            PcFrame::Ping {
+                from,
+                request: false,
+                ..
+            } => Some(PeerFrame::Pong {
+                nonce: from.clone(),
+            }),
+            PcFrame::NodeUsers { users, .. } => Some(PeerFrame::Heartbeat {
+                nonce: users.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Translate a native frame for a DXSpider link between `local` and
+    /// `remote`, using the hop budget `max_hops`. Handshake frames
+    /// (`HELLO`, `CAPS`, `AUTH`) have no equivalent and yield `None`.
+    pub fn from_peer_frame(
+        frame: &PeerFrame,
+        local: &NodeId,
+        remote: &str,
+        max_hops: u32,
+    ) -> Option<Self> {
+        match frame {
+            PeerFrame::Spot { spot } => {
+                Some(PcFrame::Spot(PcSpot::from_spot(spot, local, max_hops)))
+            }
+            PeerFrame::Announce { announcement } => {
+                PcAnnounce::from_announcement(announcement, local, max_hops).map(PcFrame::Announce)
+            }
+            PeerFrame::Ping { .. } => Some(PcFrame::Ping {
+                to: remote.to_string(),
+                from: local.0.clone(),
+                request: true,
+            }),
+            PeerFrame::Pong { nonce } => Some(PcFrame::Ping {
+                to: nonce.clone(),
+                from: local.0.clone(),
+                request: false,
+            }),
+            // Keepalives are meant for the adjacent node only.
+            PeerFrame::Heartbeat { nonce } => Some(PcFrame::NodeUsers {
+                node: local.0.clone(),
+                users: nonce.parse().unwrap_or(0),
+                hops: 1,
+            }),
+            PeerFrame::Hello { .. } | PeerFrame::Capabilities { .. } | PeerFrame::Auth { .. } => {
+                None
+            }
+        }
+    }
+}
+
+impl PcSpot {
+    /// Convert into a native spot. The spot id is the canonical identity of
+    /// the spot's fields because DXSpider frames carry none. The origin is
+    /// the node the spotter is logged into.
+    pub fn to_spot(&self, max_hops: u32) -> Spot {
+        let spot_id =
+            SpotId::canonical(&self.dx, &self.spotter, self.freq, self.ts.unix_timestamp());
+        let origin = NodeId(self.origin.clone());
+        let mut spot = Spot::new_local(
+            spot_id,
+            self.ts,
+            self.freq,
+            self.dx.clone(),
+            self.spotter.clone(),
+            self.comment.clone(),
+            Some(origin.clone()),
+        );
+        spot.hop = hop_from_counter(self.hops, max_hops);
+        spot.spotter_ip = self.ip.as_deref().and_then(|ip| ip.parse().ok());
+        spot.spotter_node = Some(origin);
+        spot
+    }
+
+    /// Build a `PC11` spot from a native spot, using `local` as the origin
+    /// when the spot has none.
+    pub fn from_spot(spot: &Spot, local: &NodeId, max_hops: u32) -> Self {
+        PcSpot {
+            freq: spot.freq,
+            dx: spot.dx.clone(),
+            ts: spot.ts,
+            comment: spot.comment.clone(),
+            spotter: spot.spotter.clone(),
+            origin: spot.origin.as_ref().unwrap_or(local).0.clone(),
+            ip: spot.spotter_ip.map(|ip| ip.to_string()),
+            hops: counter_from_hop(spot.hop, max_hops),
+        }
+    }
+}
+
+impl PcAnnounce {
+    /// Convert a network-wide announcement (`to` is `*`) into a native one
+    /// received at `now`. Announcements addressed to a single node and
+    /// weather announcements have no native equivalent and yield `None`, as
+    /// do senders that are not valid callsigns.
+    pub fn to_announcement(&self, now: OffsetDateTime, max_hops: u32) -> Option<Announcement> {
+        if self.to != "*" || self.wx {
+            return None;
+        }
+        let scope = if self.sysop {
+            AnnounceScope::Sysop
+        } else {
+            AnnounceScope::Full
+        };
+        let from = Callsign::parse_loose(&self.from).ok()?;
+        let mut announcement = Announcement::new_local(
+            now,
+            from,
+            self.text.clone(),
+            scope,
+            Some(NodeId(self.origin.clone())),
+        );
+        announcement.hop = hop_from_counter(self.hops, max_hops);
+        Some(announcement)
+    }
+
+    /// Build a `PC12` from a native announcement, using `local` as the
+    /// origin when it has none. Local announcements are not sent to peers and
+    /// yield `None`.
+    pub fn from_announcement(
+        announcement: &Announcement,
+        local: &NodeId,
+        max_hops: u32,
+    ) -> Option<Self> {
+        if !announcement.scope.is_networked() {
+            return None;
+        }
+        Some(PcAnnounce {
+            from: announcement.from.as_str().to_string(),
+            to: "*".to_string(),
+            text: announcement.text.clone(),
+            sysop: announcement.scope == AnnounceScope::Sysop,
+            origin: announcement.origin.as_ref().unwrap_or(local).0.clone(),
+            wx: false,
+            hops: counter_from_hop(announcement.hop, max_hops),
+        })
+    }
+}
+
+impl PcTalk {
+    /// Convert a talk frame addressed to everyone (`*`) or to all sysops
+    /// (`SYSOP`) into a native announcement received at `now`; the frame's
+    /// seconds-since-midnight time is not used. Talk to a single station
+    /// has no native equivalent and yields `None`, as do senders that are
+    /// not valid callsigns.
+    pub fn to_announcement(&self, now: OffsetDateTime, max_hops: u32) -> Option<Announcement> {
+        let scope = if self.to == "*" {
+            AnnounceScope::Full
+        } else if self.to.eq_ignore_ascii_case("SYSOP") {
+            AnnounceScope::Sysop
+        } else {
+            return None;
+        };
+        let from = Callsign::parse_loose(&self.from).ok()?;
+        let mut announcement = Announcement::new_local(
+            now,
+            from,
+            self.text.clone(),
+            scope,
+            Some(NodeId(self.node.clone())),
+        );
+        announcement.hop = hop_from_counter(self.hops, max_hops);
+        Some(announcement)
+    }
+}
+
+fn parse_spot(fields: &mut Fields<'_>, with_ip: bool) -> Result<PcSpot, PeerParseError> {
+    let freq = FrequencyHz::from_khz_str(fields.next("frequency")?)
+        .map_err(|_| PeerParseError::Invalid("frequency"))?;
+    let dx = Callsign::parse_loose(fields.next("dx callsign")?)
+        .map_err(|_| PeerParseError::Invalid("dx callsign"))?;
+    let date = parse_date(fields.next("date")?)?;
+    let time = parse_time(fields.next("time")?)?;
+    let comment = fields.text("comment")?;
+    let spotter = Callsign::parse_loose(fields.next("spotter callsign")?)
+        .map_err(|_| PeerParseError::Invalid("spotter callsign"))?;
+    let origin = fields.text("origin")?;
+    let ip = if with_ip {
+        Some(fields.text("ip address")?)
+    } else {
+        None
+    };
+    let hops = fields.hops()?;
+
+    Ok(PcSpot {
+        freq,
+        dx,
+        ts: date.with_time(time).assume_utc(),
+        comment,
+        spotter,
+        origin,
+        ip,
+        hops,
+    })
+}
+
+/// Cursor over the caret-separated fields of a frame.
+struct Fields<'a> {
+    fields: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(fields: Vec<&'a str>) -> Self {
+        Fields { fields, pos: 0 }
+    }
+
+    fn next(&mut self, name: &'static str) -> Result<&'a str, PeerParseError> {
+        let field = self
+            .fields
+            .get(self.pos)
+            .copied()
+            .ok_or(PeerParseError::Missing(name))?;
+        self.pos += 1;
+        Ok(field)
+    }
+
+    fn text(&mut self, name: &'static str) -> Result<String, PeerParseError> {
+        self.next(name).map(unescape)
+    }
+
+    fn optional_text(&mut self) -> Option<String> {
+        self.next("optional").ok().map(unescape)
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, name: &'static str) -> Result<T, PeerParseError> {
+        self.next(name)?
+            .trim()
+            .parse()
+            .map_err(|_| PeerParseError::Invalid(name))
+    }
+
+    /// Remove and parse the trailing `H<n>` field.
+    fn hops(&mut self) -> Result<u32, PeerParseError> {
+        let last = self
+            .fields
+            .last()
+            .filter(|_| self.fields.len() > self.pos)
+            .ok_or(PeerParseError::Missing("hop count"))?;
+        let hops = last
+            .strip_prefix('H')
+            .and_then(|count| count.parse().ok())
+            .ok_or(PeerParseError::Invalid("hop count"))?;
+        self.fields.pop();
+        Ok(hops)
+    }
+
+    /// Remaining non-empty fields; DXSpider leaves blank placeholders in
+    /// some `PC92` records.
+    fn rest(&mut self) -> Vec<String> {
+        let rest = self.fields[self.pos..]
+            .iter()
+            .filter(|field| !field.is_empty())
+            .map(|field| unescape(field))
+            .collect();
+        self.pos = self.fields.len();
+        rest
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn parse_date(input: &str) -> Result<Date, PeerParseError> {
+    let invalid = PeerParseError::Invalid("date");
+    let mut parts = input.trim().splitn(3, '-');
+    let day: u8 = parts
+        .next()
+        .and_then(|day| day.parse().ok())
+        .ok_or(invalid.clone())?;
+    let month = parts
+        .next()
+        .and_then(|name| MONTHS.iter().position(|m| m.eq_ignore_ascii_case(name)))
+        .and_then(|idx| Month::try_from(idx as u8 + 1).ok())
+        .ok_or(invalid.clone())?;
+    let year: i32 = parts
+        .next()
+        .and_then(|year| year.parse().ok())
+        .ok_or(invalid.clone())?;
+    Date::from_calendar_date(year, month, day).map_err(|_| invalid)
+}
+
+fn format_date(date: Date) -> String {
+    format!(
+        "{}-{}-{}",
+        date.day(),
+        MONTHS[date.month() as usize - 1],
+        date.year()
+    )
+}
+
+fn parse_time(input: &str) -> Result<Time, PeerParseError> {
+    let digits = input.trim().trim_end_matches('Z');
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(PeerParseError::Invalid("time"));
+    }
+    let hour = digits[..2]
+        .parse()
+        .map_err(|_| PeerParseError::Invalid("time"))?;
+    let minute = digits[2..]
+        .parse()
+        .map_err(|_| PeerParseError::Invalid("time"))?;
+    Time::from_hms(hour, minute, 0).map_err(|_| PeerParseError::Invalid("time"))
+}
+
+fn format_time(time: Time) -> String {
+    format!("{:02}{:02}Z", time.hour(), time.minute())
+}
+
+/// DXSpider writes frequencies in kHz with at least one decimal place.
+fn format_freq(freq: FrequencyHz) -> String {
+    let khz = freq.0 / 1000;
+    let remainder = freq.0 % 1000;
+    if remainder == 0 {
+        format!("{khz}.0")
+    } else {
+        format!("{khz}.{remainder:03}")
+            .trim_end_matches('0')
+            .to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('%', "%25").replace('^', "%5E")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("%5E", "^").replace("%25", "%")
+}
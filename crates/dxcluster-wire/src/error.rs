@@ -1,3 +1,4 @@
+use dxcluster_model::FilterError;
 use dxcluster_types::{CallsignError, FrequencyError};
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
@@ -14,6 +15,16 @@ pub enum UserParseError {
     InvalidCallsign(#[source] CallsignError),
     #[error("invalid frequency: {0}")]
     InvalidFrequency(#[source] FrequencyError),
+    #[error("invalid filter: {0}")]
+    InvalidFilter(#[source] FilterError),
+    #[error("announcement has no text")]
+    MissingText,
+    #[error("line is not a DX spot")]
+    NotSpot,
+    #[error("line is not an announcement")]
+    NotAnnouncement,
+    #[error("invalid sh/dx query: {0}")]
+    InvalidQuery(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
@@ -27,3 +38,13 @@ pub enum PeerParseError {
     #[error("frame invalid: {0}")]
     Invalid(&'static str),
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SkimmerParseError {
+    #[error("line is not a skimmer spot")]
+    NotSpot,
+    #[error("skimmer spot missing {0}")]
+    Missing(&'static str),
+    #[error("skimmer spot invalid: {0}")]
+    Invalid(&'static str),
+}
@@ -1,4 +1,4 @@
-use dxcluster_model::Spot;
+use dxcluster_model::{AnnounceScope, Announcement, Spot};
 
 use crate::user;
 
@@ -12,6 +12,20 @@ pub fn spot_user_line(spot: &Spot) -> String {
     )
 }
 
+/// Announcement as shown to users, e.g. `To ALL de G4ABC: qsl via bureau`.
+pub fn announce_user_line(announcement: &Announcement) -> String {
+    let to = match announcement.scope {
+        AnnounceScope::Local => "LOCAL",
+        AnnounceScope::Full => "ALL",
+        AnnounceScope::Sysop => "SYSOP",
+    };
+    format!(
+        "To {to} de {}: {}",
+        announcement.from.as_str(),
+        announcement.text
+    )
+}
+
 pub fn banner(node_name: &str) -> String {
     user::format_banner(node_name)
 }
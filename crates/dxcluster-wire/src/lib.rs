@@ -4,19 +4,33 @@
 //! - `DX <call> <frequency_khz> <comment>` publishes a new spot.
 //! - `SH/DX` returns recent spots, while `SH/FILTERS` reports the active
 //!   filter configuration.
+//! - `ANN [FULL|SYSOP] <text>` makes an announcement to local users, the
+//!   whole network or its sysops, and `SH/ANN [n]` lists recent ones.
+//! - `accept/<type> [slot] <expr>` and `reject/<type> [slot] <expr>` set
+//!   DXSpider-style filter lines (see [`dxcluster_model::filter`]), and
+//!   `clear/<type> [slot|all]` removes them.
 //! - `PING`/`HEARTBEAT` is a keep-alive with no payload.
 //!
 //! Peer-to-peer frames use pipe-separated fields prefixed by a keyword, for
-//! example `HELLO|<node_id>|<version>`, `SPOT|...`, `CAPS|...`, or
-//! `HEARTBEAT|<nonce>`. Formatting helpers round-trip with the parsers to make
-//! it easy to test protocol compliance.
+//! example `HELLO|<node_id>|<version>`, `SPOT|...`, `ANNOUNCE|...`,
+//! `CAPS|...`, or `HEARTBEAT|<nonce>`. Formatting helpers round-trip with the
+//! parsers to make it easy to test protocol compliance.
+//!
+//! [`skimmer`] parses the `DX de` lines of Reverse Beacon Network style
+//! skimmer feeds, which carry the mode, SNR and CW speed of each spot.
+//!
+//! With the `compat_dxspider` feature, [`dxspider`] parses and formats the
+//! caret-separated `PCxx` frames spoken by DXSpider nodes.
 
+#[cfg(feature = "compat_dxspider")]
+pub mod dxspider;
 pub mod error;
 pub mod format;
 pub mod parse;
 pub mod peer;
+pub mod skimmer;
 pub mod user;
 
-pub use error::{PeerParseError, UserParseError};
+pub use error::{PeerParseError, SkimmerParseError, UserParseError};
 pub use peer::PeerFrame;
 pub use user::{ServerLine, UserCommand};
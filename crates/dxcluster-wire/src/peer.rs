@@ -1,15 +1,27 @@
-use dxcluster_model::Spot;
-use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use std::str::FromStr;
+
+use dxcluster_model::{AnnounceScope, Announcement, Spot, SpotKind};
+use dxcluster_types::{Band, Callsign, FrequencyHz, Mode, NodeId, SpotId};
 use time::OffsetDateTime;
 
 use crate::error::PeerParseError;
 
+/// A frame on a native peer link.
+///
+/// `SPOT` lines carry `id|ts|freq|dx|spotter|comment|origin|hop`, optionally
+/// followed by `mode|snr|wpm|locator|ip|node|kind`. Trailing metadata fields
+/// are left off when unset, and older peers ignore them, so spots without
+/// metadata look the same as before.
+///
+/// `ANNOUNCE` lines carry `id|ts|from|scope|text|origin|hop` for full and
+/// sysop announcements; local ones are never sent to peers.
 #[derive(Debug, Clone, PartialEq)]
 pub enum PeerFrame {
     Hello { node_id: NodeId, version: String },
     Capabilities { values: Vec<String> },
     Auth { token: String },
-    Spot { spot: Spot },
+    Spot { spot: Box<Spot> },
+    Announce { announcement: Box<Announcement> },
     Heartbeat { nonce: String },
     Ping { nonce: String },
     Pong { nonce: String },
@@ -74,13 +86,26 @@ impl PeerFrame {
                 let comment = parts.next().unwrap_or_default();
                 let origin = parts.next().unwrap_or_default();
                 let hop = parts.next().unwrap_or_default();
+                // Trailing metadata is best effort: a value this version
+                // does not understand, e.g. a mode or kind added by a newer
+                // peer, is dropped rather than rejecting the spot.
+                let mode = optional(parts.next());
+                let snr_db = optional(parts.next());
+                let wpm = optional(parts.next());
+                let spotter_locator = optional(parts.next());
+                let spotter_ip = optional(parts.next());
+                let spotter_node = parts
+                    .next()
+                    .filter(|node| !node.is_empty())
+                    .map(|node| NodeId(node.to_string()));
+                let kind = parts
+                    .next()
+                    .and_then(SpotKind::from_keyword)
+                    .unwrap_or(SpotKind::Human);
 
-                let spot_id = parse_spot_id(spot_id)?;
-                let timestamp = ts
-                    .parse::<i64>()
-                    .map_err(|_| PeerParseError::Invalid("timestamp"))?;
-                let ts = OffsetDateTime::from_unix_timestamp(timestamp)
-                    .map_err(|_| PeerParseError::Invalid("timestamp"))?;
+                let spot_id =
+                    SpotId::from_hex(spot_id).map_err(|_| PeerParseError::Invalid("spot id"))?;
+                let ts = parse_unix_seconds(ts)?;
                 let freq = freq
                     .parse::<u64>()
                     .map(FrequencyHz)
@@ -98,17 +123,74 @@ impl PeerFrame {
                     .parse::<u32>()
                     .map_err(|_| PeerParseError::Invalid("hop"))?;
 
+                let comment = unescape_comment(comment);
                 Ok(PeerFrame::Spot {
-                    spot: Spot {
+                    spot: Box::new(Spot {
                         spot_id,
                         ts,
+                        // Peers that predate the mode field leave it to us.
+                        mode: mode.or_else(|| Mode::infer(freq, &comment)),
+                        band: Band::from_frequency(freq),
                         freq,
                         dx,
                         spotter,
-                        comment: unescape_comment(comment),
+                        comment,
+                        origin,
+                        hop,
+                        snr_db,
+                        wpm,
+                        spotter_locator,
+                        spotter_ip,
+                        spotter_node,
+                        kind,
+                    }),
+                })
+            }
+            Some("ANNOUNCE") => {
+                let Some(id) = parts.next() else {
+                    return Err(PeerParseError::Missing("announcement id"));
+                };
+                let Some(ts) = parts.next() else {
+                    return Err(PeerParseError::Missing("timestamp"));
+                };
+                let Some(from) = parts.next() else {
+                    return Err(PeerParseError::Missing("from callsign"));
+                };
+                let Some(scope) = parts.next() else {
+                    return Err(PeerParseError::Missing("scope"));
+                };
+                let Some(text) = parts.next() else {
+                    return Err(PeerParseError::Missing("text"));
+                };
+                let origin = parts.next().unwrap_or_default();
+                let hop = parts.next().unwrap_or_default();
+
+                let id =
+                    SpotId::from_hex(id).map_err(|_| PeerParseError::Invalid("announcement id"))?;
+                let ts = parse_unix_seconds(ts)?;
+                let from = Callsign::parse_loose(from)
+                    .map_err(|_| PeerParseError::Invalid("from callsign"))?;
+                let scope =
+                    AnnounceScope::from_keyword(scope).ok_or(PeerParseError::Invalid("scope"))?;
+                let origin = if origin.is_empty() {
+                    None
+                } else {
+                    Some(NodeId(origin.to_string()))
+                };
+                let hop = hop
+                    .parse::<u32>()
+                    .map_err(|_| PeerParseError::Invalid("hop"))?;
+
+                Ok(PeerFrame::Announce {
+                    announcement: Box::new(Announcement {
+                        id,
+                        ts,
+                        from,
+                        text: unescape_comment(text),
+                        scope,
                         origin,
                         hop,
-                    },
+                    }),
                 })
             }
             Some("PING") => {
@@ -132,16 +214,41 @@ impl PeerFrame {
                 format!("CAPS|{}", values.join(","))
             }
             PeerFrame::Auth { token } => format!("AUTH|{token}"),
-            PeerFrame::Spot { spot } => format!(
-                "SPOT|{}|{}|{}|{}|{}|{}|{}|{}",
-                format_spot_id(&spot.spot_id),
-                spot.ts.unix_timestamp(),
-                spot.freq.0,
-                spot.dx.as_str(),
-                spot.spotter.as_str(),
-                escape_comment(&spot.comment),
-                spot.origin.as_ref().map(|id| id.0.as_str()).unwrap_or(""),
-                spot.hop,
+            PeerFrame::Spot { spot } => {
+                let mut line = format!(
+                    "SPOT|{}|{}|{}|{}|{}|{}|{}|{}",
+                    spot.spot_id.to_hex(),
+                    spot.ts.unix_timestamp(),
+                    spot.freq.0,
+                    spot.dx.as_str(),
+                    spot.spotter.as_str(),
+                    escape_comment(&spot.comment),
+                    spot.origin.as_ref().map(|id| id.0.as_str()).unwrap_or(""),
+                    spot.hop,
+                );
+                let mut metadata = spot_metadata(spot);
+                while metadata.last().is_some_and(String::is_empty) {
+                    metadata.pop();
+                }
+                for field in metadata {
+                    line.push('|');
+                    line.push_str(&field);
+                }
+                line
+            }
+            PeerFrame::Announce { announcement } => format!(
+                "ANNOUNCE|{}|{}|{}|{}|{}|{}|{}",
+                announcement.id.to_hex(),
+                announcement.ts.unix_timestamp(),
+                announcement.from.as_str(),
+                announcement.scope,
+                escape_comment(&announcement.text),
+                announcement
+                    .origin
+                    .as_ref()
+                    .map(|id| id.0.as_str())
+                    .unwrap_or(""),
+                announcement.hop,
             ),
             PeerFrame::Heartbeat { nonce } => format!("HEARTBEAT|{}", nonce),
             PeerFrame::Ping { nonce } => format!("PING|{}", nonce),
@@ -150,21 +257,37 @@ impl PeerFrame {
     }
 }
 
-fn format_spot_id(spot_id: &SpotId) -> String {
-    spot_id.0.iter().map(|byte| format!("{byte:02x}")).collect()
+/// Trailing `SPOT` fields, empty when unset.
+fn spot_metadata(spot: &Spot) -> Vec<String> {
+    fn field<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(T::to_string).unwrap_or_default()
+    }
+    let kind = match spot.kind {
+        SpotKind::Human => String::new(),
+        kind => kind.to_string(),
+    };
+    vec![
+        field(&spot.mode),
+        field(&spot.snr_db),
+        field(&spot.wpm),
+        field(&spot.spotter_locator),
+        field(&spot.spotter_ip),
+        field(&spot.spotter_node),
+        kind,
+    ]
 }
 
-fn parse_spot_id(input: &str) -> Result<SpotId, PeerParseError> {
-    if input.len() != 64 {
-        return Err(PeerParseError::Invalid("spot id"));
-    }
+fn parse_unix_seconds(ts: &str) -> Result<OffsetDateTime, PeerParseError> {
+    let timestamp = ts
+        .parse::<i64>()
+        .map_err(|_| PeerParseError::Invalid("timestamp"))?;
+    OffsetDateTime::from_unix_timestamp(timestamp).map_err(|_| PeerParseError::Invalid("timestamp"))
+}
 
-    let mut bytes = [0u8; 32];
-    for (idx, chunk) in input.as_bytes().chunks(2).enumerate() {
-        let hex = std::str::from_utf8(chunk).map_err(|_| PeerParseError::Invalid("spot id"))?;
-        bytes[idx] = u8::from_str_radix(hex, 16).map_err(|_| PeerParseError::Invalid("spot id"))?;
-    }
-    Ok(SpotId(bytes))
+/// Parse an optional trailing field; missing and empty fields are `None`.
+/// An optional trailing field, `None` when empty or unparseable.
+fn optional<T: FromStr>(field: Option<&str>) -> Option<T> {
+    field.and_then(|field| field.parse().ok())
 }
 
 fn escape_comment(comment: &str) -> String {
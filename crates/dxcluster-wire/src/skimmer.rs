@@ -0,0 +1,91 @@
+//! Reverse Beacon Network style skimmer feeds.
+//!
+//! Skimmer feeds are telnet services that, once given a callsign, stream
+//! one spot per line in the cluster `DX de` format with the decoder's
+//! readings in the comment:
+//!
+//! ```text
+//! DX de KM3T-#:    14025.0  K1ABC          CW    18 dB  24 WPM  CQ      1234Z
+//! DX de W3OA-#:    14074.0  DL1XYZ         FT8  -12 dB          CQ      1234Z
+//! ```
+
+use std::str::FromStr;
+
+use dxcluster_model::{Spot, SpotKind};
+use dxcluster_types::{Callsign, FrequencyHz, Mode, SpotId};
+use time::OffsetDateTime;
+
+use crate::error::SkimmerParseError;
+use crate::user::{parse_utc_time, resolve_time};
+
+/// Suffix marking a skimmer callsign, e.g. `KM3T-#`.
+const SKIMMER_SUFFIX: &str = "-#";
+
+/// Parse a skimmer `DX de` line into a [`Spot`] with its mode, SNR and, for
+/// CW, speed.
+///
+/// The `-#` skimmer marker is dropped from the spotter. FT8 and FT4 spots
+/// come from digital decoders, everything else from skimmers. A trailing
+/// `HHMMZ` time is resolved against `now` as for
+/// [`parse_spot_line`](crate::user::parse_spot_line). The comment keeps the
+/// readings as sent, e.g. `CW 18 dB 24 WPM CQ`.
+pub fn parse_skimmer_line(line: &str, now: OffsetDateTime) -> Result<Spot, SkimmerParseError> {
+    // Feeds end lines with CR and sometimes BEL.
+    let trimmed = line.trim_matches(|c: char| c.is_whitespace() || c.is_control());
+    let rest = trimmed
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("DX de "))
+        .map(|_| &trimmed[6..])
+        .ok_or(SkimmerParseError::NotSpot)?;
+    let (spotter, rest) = rest.split_once(':').ok_or(SkimmerParseError::NotSpot)?;
+    let spotter = spotter.trim();
+    let spotter = spotter.strip_suffix(SKIMMER_SUFFIX).unwrap_or(spotter);
+
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let ts = match words.last().and_then(|word| parse_utc_time(word)) {
+        Some(time) => {
+            words.pop();
+            resolve_time(time, now)
+        }
+        None => now,
+    };
+    let [freq, dx, readings @ ..] = words.as_slice() else {
+        return Err(SkimmerParseError::Missing("dx callsign"));
+    };
+    let [mode, snr, db, tail @ ..] = readings else {
+        return Err(SkimmerParseError::Missing("signal report"));
+    };
+    if !db.eq_ignore_ascii_case("dB") {
+        return Err(SkimmerParseError::Invalid("signal report"));
+    }
+
+    let spotter =
+        Callsign::parse_loose(spotter).map_err(|_| SkimmerParseError::Invalid("spotter"))?;
+    let dx = Callsign::parse_loose(dx).map_err(|_| SkimmerParseError::Invalid("dx callsign"))?;
+    let freq =
+        FrequencyHz::from_khz_str(freq).map_err(|_| SkimmerParseError::Invalid("frequency"))?;
+    let mode = Mode::from_str(mode).map_err(|_| SkimmerParseError::Invalid("mode"))?;
+    let snr_db = snr
+        .parse::<i16>()
+        .map_err(|_| SkimmerParseError::Invalid("signal report"))?;
+    let wpm = match tail {
+        [speed, unit, ..] if unit.eq_ignore_ascii_case("WPM") => Some(
+            speed
+                .parse::<u16>()
+                .map_err(|_| SkimmerParseError::Invalid("speed"))?,
+        ),
+        _ => None,
+    };
+
+    let spot_id = SpotId::canonical(&dx, &spotter, freq, ts.unix_timestamp());
+    let kind = match mode {
+        Mode::Ft8 | Mode::Ft4 => SpotKind::Digital,
+        _ => SpotKind::Skimmer,
+    };
+    let mut spot = Spot::new_local(spot_id, ts, freq, dx, spotter, readings.join(" "), None);
+    spot.mode = Some(mode);
+    spot.snr_db = Some(snr_db);
+    spot.wpm = wpm;
+    spot.kind = kind;
+    Ok(spot)
+}
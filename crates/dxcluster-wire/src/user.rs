@@ -1,7 +1,12 @@
 //! Parsers and formatters for user-facing commands and responses.
 
-use dxcluster_model::Spot;
-use dxcluster_types::{Callsign, FrequencyHz, normalize};
+use dxcluster_model::filter::{DEFAULT_SLOT, FreqSelector, parse_slot};
+use dxcluster_model::query::MAX_DX_COUNT;
+use dxcluster_model::{
+    AnnounceScope, Announcement, CallMatch, DayRange, DxQuery, FilterRule, FilterType, Spot,
+};
+use dxcluster_types::{Callsign, FrequencyHz, SpotId, normalize};
+use time::{OffsetDateTime, Time};
 
 use crate::error::UserParseError;
 
@@ -13,21 +18,44 @@ pub enum UserCommand {
         comment: String,
     },
     Show(ShowCommand),
+    /// `ann <text>`, `ann full <text>` or `ann sysop <text>`.
+    Announce {
+        scope: AnnounceScope,
+        text: String,
+    },
+    /// `accept/<type> [slot] ...` or `reject/<type> [slot] ...` filter line.
+    Filter(FilterRule),
+    /// `clear/<type> [slot|all]`.
+    ClearFilter {
+        kind: FilterType,
+        target: ClearTarget,
+    },
     Heartbeat,
     Raw(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ShowCommand {
-    Dx,
+    /// `sh/dx [args]`, see [`parse_dx_query`].
+    Dx(DxQuery),
     Filters,
+    /// `sh/ann [n]`: the most recent announcements, 10 unless `n` is given.
+    Announce(Option<usize>),
+}
+
+/// Which lines a `clear/<type>` command removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClearTarget {
+    Slot(u8),
+    All,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ServerLine {
     Banner(String),
     Prompt,
-    Spot(Spot),
+    Spot(Box<Spot>),
+    Announce(Box<Announcement>),
     Message(String),
 }
 
@@ -45,8 +73,29 @@ pub fn parse_line(line: &str) -> Result<UserCommand, UserParseError> {
         return parse_dx_command(&trimmed[2..]);
     }
 
-    if trimmed.eq_ignore_ascii_case("SH/DX") {
-        return Ok(UserCommand::Show(ShowCommand::Dx));
+    if is_filter_line(trimmed) {
+        return FilterRule::parse(trimmed)
+            .map(UserCommand::Filter)
+            .map_err(UserParseError::InvalidFilter);
+    }
+
+    if let Some(rest) = strip_clear_prefix(trimmed) {
+        return parse_clear_command(rest);
+    }
+
+    let (head, args) = trimmed
+        .split_once(char::is_whitespace)
+        .unwrap_or((trimmed, ""));
+    if head.eq_ignore_ascii_case("SH/DX") || head.eq_ignore_ascii_case("SHOW/DX") {
+        return parse_dx_query(args).map(|query| UserCommand::Show(ShowCommand::Dx(query)));
+    }
+
+    if head.eq_ignore_ascii_case("ANN") || head.eq_ignore_ascii_case("ANNOUNCE") {
+        return parse_announce_command(args);
+    }
+
+    if head.eq_ignore_ascii_case("SH/ANN") || head.eq_ignore_ascii_case("SHOW/ANNOUNCE") {
+        return parse_show_announce(args);
     }
 
     if trimmed.eq_ignore_ascii_case("SH/FILTERS") || trimmed.eq_ignore_ascii_case("SHOW/FILTERS") {
@@ -64,6 +113,23 @@ pub fn format_prompt() -> String {
     String::from(">")
 }
 
+/// Login prompt sent before the callsign is known. Like classic telnet
+/// clusters it is written without a trailing newline.
+pub fn format_login_prompt() -> String {
+    String::from("login: ")
+}
+
+/// Password prompt for registered callsigns, written without a trailing
+/// newline.
+pub fn format_password_prompt() -> String {
+    String::from("password: ")
+}
+
+/// Personalised greeting shown once a user has logged in.
+pub fn format_welcome(callsign: &Callsign, node_name: &str) -> String {
+    format!("Hello {callsign}, this is {node_name} DX cluster")
+}
+
 pub fn format_command(cmd: &UserCommand) -> String {
     match cmd {
         UserCommand::Dx {
@@ -71,13 +137,168 @@ pub fn format_command(cmd: &UserCommand) -> String {
             frequency,
             comment,
         } => format!("DX {dx} {} {comment}", frequency.to_khz_string()),
-        UserCommand::Show(ShowCommand::Dx) => String::from("SH/DX"),
+        UserCommand::Show(ShowCommand::Dx(query)) if *query == DxQuery::default() => {
+            String::from("SH/DX")
+        }
+        UserCommand::Show(ShowCommand::Dx(query)) => format!("SH/DX {query}"),
         UserCommand::Show(ShowCommand::Filters) => String::from("SH/FILTERS"),
+        UserCommand::Show(ShowCommand::Announce(None)) => String::from("SH/ANN"),
+        UserCommand::Show(ShowCommand::Announce(Some(count))) => format!("SH/ANN {count}"),
+        UserCommand::Announce {
+            scope: AnnounceScope::Local,
+            text,
+        } => format!("ANN {text}"),
+        UserCommand::Announce { scope, text } => format!("ANN {} {text}", scope.as_str()),
+        UserCommand::Filter(rule) => rule.to_string(),
+        UserCommand::ClearFilter {
+            kind,
+            target: ClearTarget::Slot(slot),
+        } => format!("clear/{kind} {slot}"),
+        UserCommand::ClearFilter {
+            kind,
+            target: ClearTarget::All,
+        } => format!("clear/{kind} all"),
         UserCommand::Heartbeat => String::from("PING"),
         UserCommand::Raw(raw) => raw.to_string(),
     }
 }
 
+/// Parse a `DX de <spotter>: <freq> <dx> <comment> [HHMMZ]` line as sent to
+/// users into a [`Spot`].
+///
+/// The line carries no identifier or origin, so the spot id is derived from
+/// its fields and the origin is left empty. A trailing `HHMMZ` time is
+/// resolved against `now`, falling back to the previous day when it would
+/// otherwise lie in the future.
+pub fn parse_spot_line(line: &str, now: OffsetDateTime) -> Result<Spot, UserParseError> {
+    let trimmed = line.trim();
+    let rest = trimmed
+        .get(..6)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("DX de "))
+        .map(|_| &trimmed[6..])
+        .ok_or(UserParseError::NotSpot)?;
+    let (spotter, rest) = rest.split_once(':').ok_or(UserParseError::NotSpot)?;
+
+    let mut tokens = rest.split_whitespace().peekable();
+    let Some(freq) = tokens.next() else {
+        return Err(UserParseError::MissingFrequency);
+    };
+    let Some(dx) = tokens.next() else {
+        return Err(UserParseError::MissingCallsign);
+    };
+    let mut words: Vec<&str> = tokens.collect();
+    let ts = match words.last().and_then(|word| parse_utc_time(word)) {
+        Some(time) => {
+            words.pop();
+            resolve_time(time, now)
+        }
+        None => now,
+    };
+
+    let spotter = Callsign::parse_loose(spotter).map_err(UserParseError::InvalidCallsign)?;
+    let dx = Callsign::parse_loose(dx).map_err(UserParseError::InvalidCallsign)?;
+    let freq = FrequencyHz::from_khz_str(freq).map_err(UserParseError::InvalidFrequency)?;
+    let comment = normalize::comment(&words.join(" "));
+    let spot_id = SpotId::canonical(&dx, &spotter, freq, ts.unix_timestamp());
+
+    Ok(Spot::new_local(
+        spot_id, ts, freq, dx, spotter, comment, None,
+    ))
+}
+
+/// Parse a `To ALL de <call>: <text>` line as sent to users into an
+/// [`Announcement`]. `LOCAL` and `SYSOP` select the other scopes.
+///
+/// As with [`parse_spot_line`], the line carries no origin, so the
+/// announcement is stamped with `now` and treated as local.
+pub fn parse_announce_line(
+    line: &str,
+    now: OffsetDateTime,
+) -> Result<Announcement, UserParseError> {
+    let trimmed = line.trim();
+    let (head, text) = trimmed
+        .split_once(':')
+        .ok_or(UserParseError::NotAnnouncement)?;
+    let mut words = head.split_whitespace();
+    let (Some(to), Some(scope), Some(de), Some(from), None) = (
+        words.next(),
+        words.next(),
+        words.next(),
+        words.next(),
+        words.next(),
+    ) else {
+        return Err(UserParseError::NotAnnouncement);
+    };
+    if !to.eq_ignore_ascii_case("to") || !de.eq_ignore_ascii_case("de") {
+        return Err(UserParseError::NotAnnouncement);
+    }
+    let scope = match scope.to_ascii_uppercase().as_str() {
+        "ALL" => AnnounceScope::Full,
+        "LOCAL" => AnnounceScope::Local,
+        "SYSOP" => AnnounceScope::Sysop,
+        _ => return Err(UserParseError::NotAnnouncement),
+    };
+    let from = Callsign::parse_loose(from).map_err(UserParseError::InvalidCallsign)?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(UserParseError::MissingText);
+    }
+    Ok(Announcement::new_local(now, from, text, scope, None))
+}
+
+pub(crate) fn parse_utc_time(word: &str) -> Option<Time> {
+    let digits = word.strip_suffix('Z')?;
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hour = digits[..2].parse().ok()?;
+    let minute = digits[2..].parse().ok()?;
+    Time::from_hms(hour, minute, 0).ok()
+}
+
+pub(crate) fn resolve_time(time: Time, now: OffsetDateTime) -> OffsetDateTime {
+    let candidate = now.replace_time(time);
+    if candidate > now + time::Duration::minutes(5) {
+        candidate - time::Duration::days(1)
+    } else {
+        candidate
+    }
+}
+
+fn is_filter_line(line: &str) -> bool {
+    let head = line.split_whitespace().next().unwrap_or_default();
+    let Some((action, _)) = head.split_once('/') else {
+        return false;
+    };
+    let action = action.to_ascii_lowercase();
+    action.len() >= 3 && ("accept".starts_with(&action) || "reject".starts_with(&action))
+}
+
+fn strip_clear_prefix(line: &str) -> Option<&str> {
+    let (head, rest) = line.split_once('/')?;
+    let head = head.to_ascii_lowercase();
+    (head.len() >= 3 && "clear".starts_with(&head)).then_some(rest)
+}
+
+fn parse_clear_command(rest: &str) -> Result<UserCommand, UserParseError> {
+    let mut tokens = rest.split_whitespace();
+    let kind = FilterType::parse(tokens.next().unwrap_or_default())
+        .map_err(UserParseError::InvalidFilter)?;
+    let target = match tokens.next() {
+        None => ClearTarget::Slot(DEFAULT_SLOT),
+        Some(all) if all.eq_ignore_ascii_case("all") => ClearTarget::All,
+        Some(slot) => parse_slot(slot)
+            .map(ClearTarget::Slot)
+            .map_err(UserParseError::InvalidFilter)?,
+    };
+    if let Some(extra) = tokens.next() {
+        return Err(UserParseError::InvalidFilter(
+            dxcluster_model::FilterError::UnexpectedToken(extra.to_string()),
+        ));
+    }
+    Ok(UserCommand::ClearFilter { kind, target })
+}
+
 fn parse_dx_command(rest: &str) -> Result<UserCommand, UserParseError> {
     let mut tokens = rest.split_whitespace();
     let Some(dx) = tokens.next() else {
@@ -98,3 +319,138 @@ fn parse_dx_command(rest: &str) -> Result<UserCommand, UserParseError> {
         comment,
     })
 }
+
+/// Parse the arguments of `ann`: an optional `full` or `sysop` keyword
+/// followed by the text.
+fn parse_announce_command(args: &str) -> Result<UserCommand, UserParseError> {
+    let (first, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let (scope, text) = match AnnounceScope::from_keyword(first) {
+        Some(scope @ (AnnounceScope::Full | AnnounceScope::Sysop)) => (scope, rest),
+        _ => (AnnounceScope::Local, args),
+    };
+    let text = normalize::comment(text);
+    if text.is_empty() {
+        return Err(UserParseError::MissingText);
+    }
+    Ok(UserCommand::Announce { scope, text })
+}
+
+fn parse_show_announce(args: &str) -> Result<UserCommand, UserParseError> {
+    let args = args.trim();
+    let count = if args.is_empty() {
+        None
+    } else {
+        let count = args
+            .parse()
+            .map_err(|_| UserParseError::InvalidQuery(format!("bad count {args:?}")))?;
+        Some(count)
+    };
+    Ok(UserCommand::Show(ShowCommand::Announce(count)))
+}
+
+/// Parse the arguments of `sh/dx`, in any order:
+///
+/// - `<n>`: number of spots to show, at most [`MAX_DX_COUNT`]
+/// - `<low>/<high>` or `on <band|group|low/high>`: frequency in kHz or band
+/// - `<call>`: DX calls starting with `call`; `*<text>` for calls containing
+///   `text` anywhere
+/// - `by <call>`: spotter, matched the same way
+/// - `day <n>` or `day <from>-<to>`: spots from the last `n` days, or from
+///   between `from` and `to` days ago
+/// - `info <text>`: comment contains `text`
+/// - `exact`: calls given without `*` must match exactly
+pub fn parse_dx_query(args: &str) -> Result<DxQuery, UserParseError> {
+    let invalid = |message: String| UserParseError::InvalidQuery(message);
+    let mut query = DxQuery::default();
+    let mut exact = false;
+    let mut tokens = args.split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        let mut argument = |keyword: &str| {
+            tokens
+                .next()
+                .ok_or_else(|| invalid(format!("{keyword} needs an argument")))
+        };
+        match token.to_ascii_lowercase().as_str() {
+            "exact" => exact = true,
+            "on" => {
+                let value = argument("on")?;
+                set(&mut query.freq, parse_freq(value)?, "frequency")?;
+            }
+            "by" => {
+                let value = argument("by")?;
+                set(&mut query.spotter, parse_call_match(value)?, "spotter")?;
+            }
+            "day" | "days" => {
+                let value = argument("day")?;
+                set(&mut query.days, parse_days(value)?, "day")?;
+            }
+            "info" => {
+                let value = argument("info")?;
+                set(&mut query.info, value.to_string(), "info")?;
+            }
+            _ if token.chars().all(|c| c.is_ascii_digit()) => {
+                // Only overflow fails to parse; it is clamped like any other
+                // large count.
+                let count = token.parse().unwrap_or(MAX_DX_COUNT).min(MAX_DX_COUNT);
+                set(&mut query.count, count, "count")?;
+            }
+            _ => match FreqSelector::parse("on", token) {
+                Ok(freq) if token.contains('/') => set(&mut query.freq, freq, "frequency")?,
+                _ => set(&mut query.dx, parse_call_match(token)?, "callsign")?,
+            },
+        }
+    }
+
+    if exact {
+        query.dx = query.dx.map(CallMatch::into_exact);
+        query.spotter = query.spotter.map(CallMatch::into_exact);
+    }
+    Ok(query)
+}
+
+fn set<T>(slot: &mut Option<T>, value: T, what: &str) -> Result<(), UserParseError> {
+    if slot.is_some() {
+        return Err(UserParseError::InvalidQuery(format!(
+            "{what} given more than once"
+        )));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn parse_freq(value: &str) -> Result<FreqSelector, UserParseError> {
+    FreqSelector::parse("on", value)
+        .map_err(|_| UserParseError::InvalidQuery(format!("bad band or range {value:?}")))
+}
+
+fn parse_call_match(value: &str) -> Result<CallMatch, UserParseError> {
+    let (anywhere, call) = match value.strip_prefix('*') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let call = call.trim_end_matches('*').to_ascii_uppercase();
+    if call.is_empty() || !call.chars().all(|c| c.is_ascii_alphanumeric() || c == '/') {
+        return Err(UserParseError::InvalidQuery(format!(
+            "bad callsign {value:?}"
+        )));
+    }
+    Ok(if anywhere {
+        CallMatch::Anywhere(call)
+    } else {
+        CallMatch::Prefix(call)
+    })
+}
+
+fn parse_days(value: &str) -> Result<DayRange, UserParseError> {
+    let invalid = || UserParseError::InvalidQuery(format!("bad day range {value:?}"));
+    let number = |text: &str| text.parse::<u32>().map_err(|_| invalid());
+    let (from, to) = match value.split_once('-') {
+        Some((from, to)) => (number(from)?, number(to)?),
+        None => (0, number(value)?),
+    };
+    if from >= to {
+        return Err(invalid());
+    }
+    Ok(DayRange { from, to })
+}
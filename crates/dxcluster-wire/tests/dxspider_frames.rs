@@ -0,0 +1,289 @@
+#![cfg(feature = "compat_dxspider")]
+
+use dxcluster_model::AnnounceScope;
+use dxcluster_types::{FrequencyHz, NodeId};
+use dxcluster_wire::PeerFrame;
+use dxcluster_wire::dxspider::{DEFAULT_HOPS, PcFrame};
+use time::macros::datetime;
+
+#[test]
+fn parses_pc11_spot() {
+    let line = "PC11^14001.0^G1TLH^5-Mar-2000^1253Z^cq dx^G0RDI^GB7DJK^H96^~";
+    let PcFrame::Spot(spot) = PcFrame::parse(line).expect("parse pc11") else {
+        panic!("expected spot");
+    };
+    assert_eq!(spot.freq, FrequencyHz(14_001_000));
+    assert_eq!(spot.dx.as_str(), "G1TLH");
+    assert_eq!(spot.ts, datetime!(2000-03-05 12:53 UTC));
+    assert_eq!(spot.comment, "cq dx");
+    assert_eq!(spot.spotter.as_str(), "G0RDI");
+    assert_eq!(spot.origin, "GB7DJK");
+    assert_eq!(spot.ip, None);
+    assert_eq!(spot.hops, 96);
+    assert_eq!(PcFrame::Spot(spot).to_line(), line);
+}
+
+#[test]
+fn parses_pc61_spot_with_ip_and_no_trailer() {
+    let line = "PC61^7025.5^K1ABC^12-Oct-2024^0001Z^up 2^DL1XYZ^DB0SUE-7^192.0.2.7^H12^";
+    let frame = PcFrame::parse(line).expect("parse pc61");
+    let PcFrame::Spot(spot) = &frame else {
+        panic!("expected spot");
+    };
+    assert_eq!(spot.freq, FrequencyHz(7_025_500));
+    assert_eq!(spot.ip.as_deref(), Some("192.0.2.7"));
+    assert_eq!(spot.hops, 12);
+    assert_eq!(
+        frame.to_line(),
+        "PC61^7025.5^K1ABC^12-Oct-2024^0001Z^up 2^DL1XYZ^DB0SUE-7^192.0.2.7^H12^~"
+    );
+}
+
+#[test]
+fn spot_maps_to_peer_frame_and_back() {
+    let line = "PC11^14025.0^K1ABC^5-Mar-2024^1253Z^tnx^G4XYZ^GB7DJK^H96^~";
+    let frame = PcFrame::parse(line).expect("parse pc11");
+    let Some(PeerFrame::Spot { spot }) = frame.to_peer_frame(DEFAULT_HOPS) else {
+        panic!("expected native spot");
+    };
+    assert_eq!(spot.origin, Some(NodeId("GB7DJK".into())));
+    assert_eq!(spot.hop, DEFAULT_HOPS - 96);
+
+    let back = PcFrame::from_peer_frame(
+        &PeerFrame::Spot { spot },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        DEFAULT_HOPS,
+    )
+    .expect("spot maps back");
+    assert_eq!(back.to_line(), line);
+}
+
+#[test]
+fn hop_counters_share_the_native_budget() {
+    let frame =
+        PcFrame::parse("PC11^14025.0^K1ABC^5-Mar-2024^1253Z^tnx^G4XYZ^GB7DJK^H12^~").unwrap();
+    let Some(PeerFrame::Spot { mut spot }) = frame.to_peer_frame(16) else {
+        panic!("expected native spot");
+    };
+    assert_eq!(spot.hop, 4);
+
+    spot.hop += 1;
+    let back = PcFrame::from_peer_frame(
+        &PeerFrame::Spot { spot: spot.clone() },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        16,
+    )
+    .unwrap();
+    assert!(back.to_line().ends_with("^H11^~"));
+
+    // Counters above the budget clamp to a fresh frame, and frames past the
+    // budget go out exhausted.
+    let fresh =
+        PcFrame::parse("PC11^14025.0^K1ABC^5-Mar-2024^1253Z^tnx^G4XYZ^GB7DJK^H99^~").unwrap();
+    let Some(PeerFrame::Spot { spot: fresh }) = fresh.to_peer_frame(16) else {
+        panic!("expected native spot");
+    };
+    assert_eq!(fresh.hop, 0);
+    spot.hop = 20;
+    let exhausted = PcFrame::from_peer_frame(
+        &PeerFrame::Spot { spot },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        16,
+    )
+    .unwrap();
+    assert!(exhausted.to_line().ends_with("^H0^~"));
+}
+
+#[test]
+fn pc61_ip_maps_to_spotter_ip() {
+    let line = "PC61^7025.5^K1ABC^12-Oct-2024^0001Z^up 2^DL1XYZ^DB0SUE-7^192.0.2.7^H12^~";
+    let frame = PcFrame::parse(line).expect("parse pc61");
+    let Some(PeerFrame::Spot { spot }) = frame.to_peer_frame(DEFAULT_HOPS) else {
+        panic!("expected native spot");
+    };
+    assert_eq!(spot.spotter_ip, Some("192.0.2.7".parse().unwrap()));
+    assert_eq!(spot.spotter_node, Some(NodeId("DB0SUE-7".into())));
+
+    let back = PcFrame::from_peer_frame(
+        &PeerFrame::Spot { spot },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        DEFAULT_HOPS,
+    )
+    .expect("spot maps back");
+    assert_eq!(back.to_line(), line);
+}
+
+#[test]
+fn announce_frames_round_trip() {
+    for line in [
+        "PC12^G1TLH^*^Hello world^ ^GB7DJK^0^H99^~",
+        "PC12^G1TLH^*^sysop only^*^GB7DJK^1^H98^~",
+        "PC93^GB7DJK^43210^*^G1TLH^GB7DJK^Hello all^H99^~",
+        "PC93^GB7DJK^43210^ALL^G1TLH^*^Hi^192.0.2.1^H97^~",
+    ] {
+        let frame = PcFrame::parse(line).expect("parse announce");
+        assert_eq!(frame.to_line(), line);
+    }
+
+    let PcFrame::Announce(ann) = PcFrame::parse("PC12^G1TLH^*^a%5Eb^*^GB7DJK^1^H5^~").unwrap()
+    else {
+        panic!("expected announce");
+    };
+    assert_eq!(ann.text, "a^b");
+    assert!(ann.sysop);
+    assert!(ann.wx);
+    assert_eq!(ann.hops, 5);
+}
+
+#[test]
+fn pc12_maps_to_native_announcement_and_back() {
+    let line = "PC12^G1TLH^*^qsl via bureau^*^GB7DJK^0^H96^~";
+    let frame = PcFrame::parse(line).expect("parse pc12");
+    let Some(PeerFrame::Announce { announcement }) = frame.to_peer_frame(DEFAULT_HOPS) else {
+        panic!("expected native announcement");
+    };
+    assert_eq!(announcement.from.as_str(), "G1TLH");
+    assert_eq!(announcement.scope, AnnounceScope::Sysop);
+    assert_eq!(announcement.origin, Some(NodeId("GB7DJK".into())));
+    assert_eq!(announcement.hop, DEFAULT_HOPS - 96);
+
+    let back = PcFrame::from_peer_frame(
+        &PeerFrame::Announce { announcement },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        DEFAULT_HOPS,
+    )
+    .expect("announcement maps back");
+    assert_eq!(back.to_line(), line);
+
+    // Node-addressed and weather announcements have no native equivalent.
+    for line in [
+        "PC12^G1TLH^GB7ZZZ^hello^ ^GB7DJK^0^H96^~",
+        "PC12^G1TLH^*^rain^ ^GB7DJK^1^H96^~",
+    ] {
+        assert_eq!(
+            PcFrame::parse(line).unwrap().to_peer_frame(DEFAULT_HOPS),
+            None
+        );
+    }
+}
+
+#[test]
+fn pc93_maps_to_native_announcement() {
+    let frame = PcFrame::parse("PC93^GB7DJK^43210^*^G1TLH^GB7DJK^Hello all^H96^~").unwrap();
+    let Some(PeerFrame::Announce { announcement }) = frame.to_peer_frame(DEFAULT_HOPS) else {
+        panic!("expected native announcement");
+    };
+    assert_eq!(announcement.from.as_str(), "G1TLH");
+    assert_eq!(announcement.text, "Hello all");
+    assert_eq!(announcement.scope, AnnounceScope::Full);
+    assert_eq!(announcement.origin, Some(NodeId("GB7DJK".into())));
+    assert_eq!(announcement.hop, DEFAULT_HOPS - 96);
+
+    let frame = PcFrame::parse("PC93^GB7DJK^43210^SYSOP^G1TLH^GB7DJK^reboot^H96^~").unwrap();
+    let Some(PeerFrame::Announce { announcement }) = frame.to_peer_frame(DEFAULT_HOPS) else {
+        panic!("expected native announcement");
+    };
+    assert_eq!(announcement.scope, AnnounceScope::Sysop);
+
+    // Talk to a single station has no native equivalent.
+    let talk = PcFrame::parse("PC93^GB7DJK^43210^G4XYZ^G1TLH^GB7DJK^hi^H96^~").unwrap();
+    assert_eq!(talk.to_peer_frame(DEFAULT_HOPS), None);
+}
+
+#[test]
+fn link_init_frames_round_trip() {
+    let init = PcFrame::parse("PC18^DXSpider Version: 1.57 Build: 123 pc9x^5457^~").unwrap();
+    assert_eq!(
+        init,
+        PcFrame::LinkInit {
+            software: "DXSpider Version: 1.57 Build: 123 pc9x".into(),
+            version: "5457".into(),
+        }
+    );
+    assert_eq!(PcFrame::parse("PC20^").unwrap(), PcFrame::ConfigRequest);
+    assert_eq!(PcFrame::parse("PC22^").unwrap(), PcFrame::ConfigDone);
+    assert_eq!(PcFrame::ConfigRequest.to_line(), "PC20^");
+
+    let line = "PC92^GB7DJK^67445^C^5GB7DJK:5457^1G1TLH^^H99^~";
+    let PcFrame::Config(config) = PcFrame::parse(line).unwrap() else {
+        panic!("expected config");
+    };
+    assert_eq!(config.node, "GB7DJK");
+    assert_eq!(config.kind, "C");
+    assert_eq!(config.entries, vec!["5GB7DJK:5457", "1G1TLH"]);
+    assert_eq!(config.hops, 99);
+}
+
+#[test]
+fn ping_and_keepalive_map_to_native_frames() {
+    let ping = PcFrame::parse("PC51^GB7ZZZ^GB7DJK^1^").unwrap();
+    assert_eq!(
+        ping.to_peer_frame(DEFAULT_HOPS),
+        Some(PeerFrame::Ping {
+            nonce: "GB7DJK".into()
+        })
+    );
+
+    let pong = PcFrame::from_peer_frame(
+        &PeerFrame::Pong {
+            nonce: "GB7DJK".into(),
+        },
+        &NodeId("GB7ZZZ".into()),
+        "GB7DJK",
+        DEFAULT_HOPS,
+    )
+    .unwrap();
+    assert_eq!(pong.to_line(), "PC51^GB7DJK^GB7ZZZ^0^~");
+
+    let users = PcFrame::parse("PC50^GB7DJK^35^H99^").unwrap();
+    assert_eq!(
+        users,
+        PcFrame::NodeUsers {
+            node: "GB7DJK".into(),
+            users: 35,
+            hops: 99,
+        }
+    );
+
+    let hello = PeerFrame::Hello {
+        node_id: NodeId("GB7ZZZ".into()),
+        version: "1".into(),
+    };
+    assert_eq!(
+        PcFrame::from_peer_frame(&hello, &NodeId("GB7ZZZ".into()), "GB7DJK", DEFAULT_HOPS),
+        None
+    );
+}
+
+#[test]
+fn propagation_bulletins_round_trip() {
+    let wwv = "PC23^5-Mar-2000^12^150^10^2^No Storms -> No Storms^VE7CC^GB7DJK^H99^~";
+    let PcFrame::Wwv(bulletin) = PcFrame::parse(wwv).unwrap() else {
+        panic!("expected wwv");
+    };
+    assert_eq!((bulletin.sfi, bulletin.a, bulletin.k), (150, 10, 2));
+    assert_eq!(PcFrame::Wwv(bulletin).to_line(), wwv);
+
+    let wcy = "PC73^5-Mar-2000^12^150^10^2^3^88^qui^qui^no^DK0WCY-1^GB7DJK^H99^~";
+    let PcFrame::Wcy(bulletin) = PcFrame::parse(wcy).unwrap() else {
+        panic!("expected wcy");
+    };
+    assert_eq!((bulletin.expk, bulletin.r), (3, 88));
+    assert_eq!(bulletin.aurora, "no");
+    assert_eq!(PcFrame::Wcy(bulletin).to_line(), wcy);
+}
+
+#[test]
+fn rejects_malformed_frames() {
+    assert!(PcFrame::parse("").is_err());
+    assert!(PcFrame::parse("PC99^x^~").is_err());
+    assert!(PcFrame::parse("PC11^14001.0^G1TLH^5-Mar-2000^1253Z^c^G0RDI^GB7DJK^~").is_err());
+    assert!(PcFrame::parse("PC11^14001.0^G1TLH^31-Feb-2000^1253Z^c^G0RDI^GB7DJK^H9^~").is_err());
+    assert!(PcFrame::parse("PC11^14001.0^G1TLH^5-Mar-2000^2561Z^c^G0RDI^GB7DJK^H9^~").is_err());
+    assert!(PcFrame::parse("PC50^GB7DJK^many^H99^").is_err());
+}
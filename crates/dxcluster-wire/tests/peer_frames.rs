@@ -1,30 +1,57 @@
-use dxcluster_model::Spot;
-use dxcluster_types::{Callsign, FrequencyHz, NodeId, SpotId};
+use dxcluster_model::{AnnounceScope, Announcement, Spot, SpotKind};
+use dxcluster_types::{Band, Callsign, FrequencyHz, Locator, Mode, NodeId, SpotId};
 use dxcluster_wire::PeerFrame;
 
 fn sample_spot() -> Spot {
-    Spot {
-        spot_id: SpotId([1u8; 32]),
-        ts: time::OffsetDateTime::from_unix_timestamp(1_700_000_000).expect("timestamp"),
-        freq: FrequencyHz(14_074_000),
-        dx: Callsign::parse_loose("K1ABC").expect("dx callsign"),
-        spotter: Callsign::parse_loose("N0CALL").expect("spotter callsign"),
-        comment: "test spot".to_string(),
-        origin: Some(NodeId("node-a".to_string())),
-        hop: 2,
-    }
+    let mut spot = Spot::new_local(
+        SpotId([1u8; 32]),
+        time::OffsetDateTime::from_unix_timestamp(1_700_000_000).expect("timestamp"),
+        FrequencyHz(14_074_000),
+        Callsign::parse_loose("K1ABC").expect("dx callsign"),
+        Callsign::parse_loose("N0CALL").expect("spotter callsign"),
+        "test spot",
+        Some(NodeId("node-a".to_string())),
+    );
+    spot.hop = 2;
+    spot
 }
 
 #[test]
 fn spot_frame_round_trip() {
     let frame = PeerFrame::Spot {
-        spot: sample_spot(),
+        spot: Box::new(sample_spot()),
     };
     let line = frame.to_line();
     let parsed = PeerFrame::parse(&line).expect("parse frame");
     assert_eq!(parsed, frame);
 }
 
+#[test]
+fn announce_frame_round_trip() {
+    let mut announcement = Announcement::new_local(
+        time::OffsetDateTime::from_unix_timestamp(1_700_000_000).expect("timestamp"),
+        Callsign::parse_loose("G4ABC").expect("callsign"),
+        "contest | 100% fun",
+        AnnounceScope::Full,
+        Some(NodeId("node-a".to_string())),
+    );
+    announcement.hop = 3;
+    let frame = PeerFrame::Announce {
+        announcement: Box::new(announcement),
+    };
+    let line = frame.to_line();
+    assert!(line.starts_with("ANNOUNCE|"), "{line}");
+    assert!(
+        line.ends_with("|G4ABC|full|contest %7C 100%25 fun|node-a|3"),
+        "{line}"
+    );
+    let parsed = PeerFrame::parse(&line).expect("parse frame");
+    assert_eq!(parsed, frame);
+
+    let bad_scope = line.replace("|full|", "|everyone|");
+    assert!(PeerFrame::parse(&bad_scope).is_err());
+}
+
 #[test]
 fn capabilities_round_trip() {
     let frame = PeerFrame::Capabilities {
@@ -39,9 +66,77 @@ fn capabilities_round_trip() {
 fn spot_frame_comment_escapes_pipe() {
     let mut spot = sample_spot();
     spot.comment = "pipe | percent % ok".to_string();
-    let frame = PeerFrame::Spot { spot };
+    let frame = PeerFrame::Spot {
+        spot: Box::new(spot),
+    };
     let line = frame.to_line();
     assert!(line.contains("%7C"));
     let parsed = PeerFrame::parse(&line).expect("parse frame");
     assert_eq!(parsed, frame);
 }
+
+#[test]
+fn spot_frame_carries_optional_metadata() {
+    let plain = PeerFrame::Spot {
+        spot: Box::new(sample_spot()),
+    };
+    let plain_line = plain.to_line();
+    // Only the inferred mode is appended to the original eight fields.
+    assert!(plain_line.ends_with("|node-a|2|FT8"), "{plain_line}");
+
+    let mut spot = sample_spot();
+    spot.mode = Some(Mode::Cw);
+    spot.snr_db = Some(-7);
+    spot.wpm = Some(24);
+    spot.spotter_locator = Some(Locator::parse("FN42").unwrap());
+    spot.spotter_ip = Some("192.0.2.7".parse().unwrap());
+    spot.spotter_node = Some(NodeId("GB7DJK".into()));
+    spot.kind = SpotKind::Skimmer;
+    let frame = PeerFrame::Spot {
+        spot: Box::new(spot),
+    };
+    let line = frame.to_line();
+    assert!(
+        line.ends_with("|2|CW|-7|24|FN42|192.0.2.7|GB7DJK|skimmer"),
+        "{line}"
+    );
+    assert_eq!(PeerFrame::parse(&line).expect("parse frame"), frame);
+}
+
+#[test]
+fn spot_frame_from_older_peer_infers_metadata() {
+    let line = format!(
+        "SPOT|{}|1700000000|14025000|K1ABC|N0CALL|cq|node-a|1",
+        "01".repeat(32)
+    );
+    let Ok(PeerFrame::Spot { spot }) = PeerFrame::parse(&line) else {
+        panic!("expected spot");
+    };
+    assert_eq!(spot.mode, Some(Mode::Cw));
+    assert_eq!(spot.band, Some(Band::Meter20));
+    assert_eq!(spot.kind, SpotKind::Human);
+    assert_eq!(spot.snr_db, None);
+
+    let bad = format!("{line}|CW|loud");
+    let Ok(PeerFrame::Spot { spot }) = PeerFrame::parse(&bad) else {
+        panic!("expected spot");
+    };
+    assert_eq!(spot.mode, Some(Mode::Cw));
+    assert_eq!(spot.snr_db, None);
+}
+
+#[test]
+fn spot_frame_from_newer_peer_ignores_unknown_metadata() {
+    let line = format!(
+        "SPOT|{}|1700000000|14025000|K1ABC|N0CALL|cq|node-a|1|n3w-mode|-7|24|FN42||GB7DJK|beacon",
+        "01".repeat(32)
+    );
+    let Ok(PeerFrame::Spot { spot }) = PeerFrame::parse(&line) else {
+        panic!("expected spot despite unknown mode and kind");
+    };
+    // The mode falls back to inference and the kind to a person.
+    assert_eq!(spot.mode, Some(Mode::Cw));
+    assert_eq!(spot.kind, SpotKind::Human);
+    assert_eq!(spot.snr_db, Some(-7));
+    assert_eq!(spot.spotter_node, Some(NodeId("GB7DJK".into())));
+}
@@ -0,0 +1,62 @@
+use dxcluster_model::SpotKind;
+use dxcluster_types::{FrequencyHz, Mode};
+use dxcluster_wire::SkimmerParseError;
+use dxcluster_wire::skimmer::parse_skimmer_line;
+use time::macros::datetime;
+
+#[test]
+fn parses_cw_skimmer_spot() {
+    let now = datetime!(2024-03-05 12:40 UTC);
+    let line =
+        "DX de KM3T-#:    14025.0  K1ABC          CW    18 dB  24 WPM  CQ      1234Z\x07\x07\r\n";
+    let spot = parse_skimmer_line(line, now).expect("parse skimmer line");
+
+    assert_eq!(spot.spotter.as_str(), "KM3T");
+    assert_eq!(spot.dx.as_str(), "K1ABC");
+    assert_eq!(spot.freq, FrequencyHz(14_025_000));
+    assert_eq!(spot.ts, datetime!(2024-03-05 12:34 UTC));
+    assert_eq!(spot.mode, Some(Mode::Cw));
+    assert_eq!(spot.snr_db, Some(18));
+    assert_eq!(spot.wpm, Some(24));
+    assert_eq!(spot.kind, SpotKind::Skimmer);
+    assert_eq!(spot.comment, "CW 18 dB 24 WPM CQ");
+}
+
+#[test]
+fn parses_rtty_and_ft8_spots() {
+    let now = datetime!(2024-03-05 12:40 UTC);
+    let rtty = parse_skimmer_line(
+        "DX de DK9IP-#:   14085.5  OH2XX   RTTY   7 dB  45 BPS  CQ  1235Z",
+        now,
+    )
+    .expect("parse rtty");
+    assert_eq!(rtty.mode, Some(Mode::Rtty));
+    assert_eq!(rtty.wpm, None);
+    assert_eq!(rtty.kind, SpotKind::Skimmer);
+
+    let ft8 = parse_skimmer_line(
+        "DX de W3OA-#:    14074.0  DL1XYZ  FT8  -12 dB  CQ  1236Z",
+        now,
+    )
+    .expect("parse ft8");
+    assert_eq!(ft8.mode, Some(Mode::Ft8));
+    assert_eq!(ft8.snr_db, Some(-12));
+    assert_eq!(ft8.kind, SpotKind::Digital);
+}
+
+#[test]
+fn rejects_other_lines() {
+    let now = datetime!(2024-03-05 12:40 UTC);
+    assert_eq!(
+        parse_skimmer_line("Please enter your call: ", now),
+        Err(SkimmerParseError::NotSpot)
+    );
+    assert_eq!(
+        parse_skimmer_line("DX de KM3T-#: 14025.0 K1ABC CW loud 1234Z", now),
+        Err(SkimmerParseError::Missing("signal report"))
+    );
+    assert_eq!(
+        parse_skimmer_line("DX de KM3T-#: 14025.0 K1ABC CW 18 WPM CQ 1234Z", now),
+        Err(SkimmerParseError::Invalid("signal report"))
+    );
+}
@@ -1,5 +1,13 @@
-use dxcluster_types::{Callsign, FrequencyHz};
-use dxcluster_wire::user::{ShowCommand, UserCommand, format_command, parse_line};
+use dxcluster_model::filter::FreqSelector;
+use dxcluster_model::query::MAX_DX_COUNT;
+use dxcluster_model::{AnnounceScope, CallMatch, DayRange, DxQuery, FilterAction, FilterType};
+use dxcluster_types::{Band, Callsign, FrequencyHz};
+use dxcluster_wire::UserParseError;
+use dxcluster_wire::format::{announce_user_line, spot_user_line};
+use dxcluster_wire::user::{
+    ClearTarget, ShowCommand, UserCommand, format_command, parse_announce_line, parse_line,
+    parse_spot_line,
+};
 
 #[test]
 fn dx_command_roundtrips() {
@@ -27,6 +35,55 @@ fn show_filters_roundtrips() {
     assert_eq!(reparsed, parsed);
 }
 
+#[test]
+fn announce_commands_roundtrip() {
+    for (line, scope, text, formatted) in [
+        (
+            "ann qrv 6m tonight",
+            AnnounceScope::Local,
+            "qrv 6m tonight",
+            "ANN qrv 6m tonight",
+        ),
+        (
+            "ANNOUNCE FULL  contest starts ",
+            AnnounceScope::Full,
+            "contest starts",
+            "ANN full contest starts",
+        ),
+        (
+            "ann sysop link down",
+            AnnounceScope::Sysop,
+            "link down",
+            "ANN sysop link down",
+        ),
+    ] {
+        let parsed = parse_line(line).expect("announce parses");
+        let expected = UserCommand::Announce {
+            scope,
+            text: text.to_string(),
+        };
+        assert_eq!(parsed, expected, "{line}");
+        assert_eq!(format_command(&parsed), formatted);
+        assert_eq!(parse_line(formatted).unwrap(), expected);
+    }
+    assert_eq!(parse_line("ann"), Err(UserParseError::MissingText));
+    assert_eq!(parse_line("ann full"), Err(UserParseError::MissingText));
+    assert_eq!(parse_line("ann sysop "), Err(UserParseError::MissingText));
+}
+
+#[test]
+fn show_announce_roundtrips() {
+    for (line, count) in [("sh/ann", None), ("SHOW/ANNOUNCE 5", Some(5))] {
+        let parsed = parse_line(line).expect("sh/ann parses");
+        assert_eq!(parsed, UserCommand::Show(ShowCommand::Announce(count)));
+        assert_eq!(parse_line(&format_command(&parsed)).unwrap(), parsed);
+    }
+    assert!(matches!(
+        parse_line("sh/ann lots"),
+        Err(UserParseError::InvalidQuery(_))
+    ));
+}
+
 #[test]
 fn heartbeat_roundtrips() {
     let parsed = parse_line("ping").expect("heartbeat parses");
@@ -36,3 +93,220 @@ fn heartbeat_roundtrips() {
     let reparsed = parse_line(&formatted).expect("format should produce parseable command");
     assert_eq!(reparsed, parsed);
 }
+
+#[test]
+fn filter_line_roundtrips() {
+    let parsed = parse_line("rej/spots on 6m and (call K or by_dxcc 291)").expect("filter parses");
+    let UserCommand::Filter(rule) = &parsed else {
+        panic!("expected filter command, got {parsed:?}");
+    };
+    assert_eq!(rule.action, FilterAction::Reject);
+
+    let formatted = format_command(&parsed);
+    assert_eq!(
+        formatted,
+        "reject/spots 1 on 6m and (call K or by_dxcc 291)"
+    );
+    let reparsed = parse_line(&formatted).expect("format should produce parseable command");
+    assert_eq!(reparsed, parsed);
+}
+
+#[test]
+fn invalid_filter_reports_error() {
+    let err = parse_line("accept/spots on 11m").expect_err("unknown band");
+    assert!(matches!(err, UserParseError::InvalidFilter(_)));
+}
+
+#[test]
+fn slotted_filter_line_parses() {
+    let parsed = parse_line("reject/announce 2 by G").expect("filter parses");
+    let UserCommand::Filter(rule) = &parsed else {
+        panic!("expected filter command, got {parsed:?}");
+    };
+    assert_eq!(rule.kind, FilterType::Announce);
+    assert_eq!(rule.slot, 2);
+    assert_eq!(format_command(&parsed), "reject/announce 2 by G");
+}
+
+#[test]
+fn clear_filter_roundtrips() {
+    for (line, kind, target) in [
+        ("clear/spots 1", FilterType::Spots, ClearTarget::Slot(1)),
+        ("CLEAR/SPOTS ALL", FilterType::Spots, ClearTarget::All),
+        ("clear/wcy", FilterType::Wcy, ClearTarget::Slot(1)),
+        (
+            "clear/announce 0",
+            FilterType::Announce,
+            ClearTarget::Slot(0),
+        ),
+    ] {
+        let parsed = parse_line(line).expect("clear parses");
+        assert_eq!(parsed, UserCommand::ClearFilter { kind, target });
+        let reparsed = parse_line(&format_command(&parsed)).expect("reparse");
+        assert_eq!(reparsed, parsed);
+    }
+
+    assert!(matches!(
+        parse_line("clear/spots 12"),
+        Err(UserParseError::InvalidFilter(_))
+    ));
+    assert!(matches!(
+        parse_line("clear/qsl 1"),
+        Err(UserParseError::InvalidFilter(_))
+    ));
+}
+
+#[test]
+fn spot_line_roundtrips_through_formatter() {
+    let now = time::macros::datetime!(2024-03-01 12:00 UTC);
+    let spot = parse_spot_line("DX de N0CALL: 14074 K1ABC ft8 cq", now).expect("spot parses");
+    assert_eq!(spot.spotter.as_str(), "N0CALL");
+    assert_eq!(spot.dx.as_str(), "K1ABC");
+    assert_eq!(spot.freq, FrequencyHz(14_074_000));
+    assert_eq!(spot.comment, "ft8 cq");
+    assert_eq!(spot.ts, now);
+
+    let reparsed = parse_spot_line(&spot_user_line(&spot), now).expect("formatted line parses");
+    assert_eq!(reparsed, spot);
+}
+
+#[test]
+fn spot_line_with_dxspider_spacing_and_time() {
+    let now = time::macros::datetime!(2024-03-01 00:10 UTC);
+    let spot = parse_spot_line(
+        "DX de W3LPL:     14025.0  VP8PJ        up 2                         2358Z",
+        now,
+    )
+    .expect("spot parses");
+    assert_eq!(spot.freq, FrequencyHz(14_025_000));
+    assert_eq!(spot.comment, "up 2");
+    assert_eq!(spot.ts, time::macros::datetime!(2024-02-29 23:58 UTC));
+
+    assert_eq!(
+        parse_spot_line("WWV de VE7CC <18>:   SFI=150", now),
+        Err(UserParseError::NotSpot)
+    );
+}
+
+#[test]
+fn announce_line_roundtrips_through_formatter() {
+    let now = time::macros::datetime!(2024-03-01 12:00 UTC);
+    for (line, scope) in [
+        ("To ALL de G4ABC: qsl via bureau", AnnounceScope::Full),
+        ("To LOCAL de G4ABC: qsl via bureau", AnnounceScope::Local),
+        ("To SYSOP de G4ABC: qsl via bureau", AnnounceScope::Sysop),
+    ] {
+        let announcement = parse_announce_line(line, now).expect("announcement parses");
+        assert_eq!(announcement.scope, scope);
+        assert_eq!(announcement.from.as_str(), "G4ABC");
+        assert_eq!(announcement.text, "qsl via bureau");
+        assert_eq!(announcement.ts, now);
+        assert_eq!(announce_user_line(&announcement), line);
+    }
+
+    assert_eq!(
+        parse_announce_line("To ALL de G4ABC:  ", now),
+        Err(UserParseError::MissingText)
+    );
+    assert_eq!(
+        parse_announce_line("To you de G4ABC: hello", now),
+        Err(UserParseError::NotAnnouncement)
+    );
+    assert_eq!(
+        parse_announce_line("Talk to ALL de G4ABC: hello", now),
+        Err(UserParseError::NotAnnouncement)
+    );
+}
+
+fn dx_query(line: &str) -> DxQuery {
+    match parse_line(line) {
+        Ok(UserCommand::Show(ShowCommand::Dx(query))) => query,
+        other => panic!("expected sh/dx, got {other:?}"),
+    }
+}
+
+#[test]
+fn sh_dx_parses_classic_arguments() {
+    assert_eq!(dx_query("SH/DX"), DxQuery::default());
+    assert_eq!(dx_query("show/dx 25").count, Some(25));
+    assert_eq!(dx_query("sh/dx 5000").count, Some(MAX_DX_COUNT));
+    assert_eq!(
+        dx_query("sh/dx 99999999999999999999999").count,
+        Some(MAX_DX_COUNT)
+    );
+    assert_eq!(
+        dx_query("sh/dx 14000/14100").freq,
+        Some(FreqSelector::Range(
+            FrequencyHz(14_000_000),
+            FrequencyHz(14_100_000)
+        ))
+    );
+    assert_eq!(
+        dx_query("sh/dx on 20m").freq,
+        Some(FreqSelector::Band(Band::Meter20))
+    );
+    assert_eq!(
+        dx_query("sh/dx k1abc").dx,
+        Some(CallMatch::Prefix("K1ABC".into()))
+    );
+    assert_eq!(
+        dx_query("sh/dx 4X1AB/P").dx,
+        Some(CallMatch::Prefix("4X1AB/P".into()))
+    );
+    assert_eq!(
+        dx_query("sh/dx *ABC").dx,
+        Some(CallMatch::Anywhere("ABC".into()))
+    );
+    assert_eq!(
+        dx_query("sh/dx by G4ABC").spotter,
+        Some(CallMatch::Prefix("G4ABC".into()))
+    );
+    assert_eq!(
+        dx_query("sh/dx day 3").days,
+        Some(DayRange { from: 0, to: 3 })
+    );
+    assert_eq!(
+        dx_query("sh/dx day 2-5").days,
+        Some(DayRange { from: 2, to: 5 })
+    );
+    assert_eq!(dx_query("sh/dx info FT8").info.as_deref(), Some("FT8"));
+
+    let combined = dx_query("sh/dx exact K1ABC 5 by *XYZ on hf info cw");
+    assert_eq!(combined.dx, Some(CallMatch::Exact("K1ABC".into())));
+    assert_eq!(combined.spotter, Some(CallMatch::Anywhere("XYZ".into())));
+    assert_eq!(combined.count, Some(5));
+    assert!(combined.freq.is_some());
+    assert_eq!(combined.info.as_deref(), Some("cw"));
+}
+
+#[test]
+fn sh_dx_rejects_bad_arguments() {
+    for line in [
+        "sh/dx on",
+        "sh/dx on 11m",
+        "sh/dx by",
+        "sh/dx day 0",
+        "sh/dx day 5-2",
+        "sh/dx 10 20",
+        "sh/dx K1ABC G4ABC",
+        "sh/dx K1-ABC",
+    ] {
+        assert!(
+            matches!(parse_line(line), Err(UserParseError::InvalidQuery(_))),
+            "{line} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn sh_dx_roundtrips() {
+    for line in [
+        "SH/DX",
+        "SH/DX 25 on 20m K1ABC by *XYZ day 2-5 info FT8 exact",
+        "SH/DX on 14000/14100 *ABC",
+    ] {
+        let parsed = parse_line(line).expect("sh/dx parses");
+        assert_eq!(format_command(&parsed), line);
+        assert_eq!(parse_line(&format_command(&parsed)).unwrap(), parsed);
+    }
+}